@@ -0,0 +1,416 @@
+#[macro_use]
+extern crate log;
+
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use michel_core::backend::{Backends, PersistenceBackend, PersistenceConfig};
+use michel_core::grants::{Capability, Grants};
+use michel_core::package::{PluginManifest, PluginPackage};
+use michel_core::persistence::{Index, MichelPersistence};
+use michel_core::signature::{self, SignatureConfig, SignatureStatus};
+use michel_core::{Entry, MichelConfig, MichelInstance, Plugin, PluginInfo};
+use michel_index::{dump, MilliPersistence};
+use michel_index_sqlite::SqlitePersistence;
+use rand::RngCore;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+type Michel = MichelInstance<Box<dyn MichelPersistence>>;
+
+const BACKENDS: Backends = Backends {
+    milli: MilliPersistence::open_backend,
+    sqlite: SqlitePersistence::open_backend,
+};
+
+/// Query Michel and manage its plugins without the desktop app. Everything is printed as JSON.
+#[derive(Parser)]
+#[command(name = "michel")]
+struct Cli {
+    /// Folder holding the plugins, the one of the desktop app by default
+    #[arg(long, global = true)]
+    plugins: Option<PathBuf>,
+
+    /// Index with every plugin before running the command, rather than searching the last indexes
+    #[arg(long, global = true)]
+    index: bool,
+
+    /// URL, or local folder, of the registry index plugins are installed from
+    #[arg(long, global = true)]
+    registry: Option<String>,
+
+    /// Embed the documents pushed without a vector with their character trigrams, for hybrid
+    /// searches. The vectors match spellings rather than meaning, so it is off unless set here
+    /// or in the config file
+    #[arg(long, global = true)]
+    trigram_embedder: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Entries of every plugin for an input
+    Query { input: String },
+    #[command(subcommand)]
+    Plugins(PluginsCommand),
+    #[command(subcommand)]
+    Index(IndexCommand),
+}
+
+#[derive(Subcommand)]
+enum PluginsCommand {
+    List,
+    /// Runs the indexing of a plugin, then prints the stats of its indexes
+    Index {
+        identifier: String,
+    },
+    /// Loads a plugin package or wasm file on its own and prints its infos
+    Info {
+        path: PathBuf,
+    },
+    /// Checks a plugin package without running it, then prints its manifest and signature
+    Check {
+        path: PathBuf,
+    },
+    /// Generates an ed25519 key pair to sign packages with
+    Keygen,
+    /// Signs a plugin package folder with the hexadecimal secret key in the `key` file
+    Sign {
+        path: PathBuf,
+        #[arg(long)]
+        key: PathBuf,
+    },
+    /// Capabilities plugins asked for, that weren't granted nor denied yet
+    Pending,
+    /// Grants a plugin a capability it's waiting for, as JSON like `pending` prints it, or
+    /// every one of them with `--yes`
+    Grant {
+        identifier: String,
+        capability: Option<String>,
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Plugins of the registry, along with the version installed of each
+    Available,
+    /// Installs the newest version of a plugin of the registry this host can run
+    Install {
+        identifier: String,
+    },
+    /// Installs the newest version of a plugin, if it isn't already
+    Update {
+        identifier: String,
+    },
+    /// Removes a plugin, keeping its indexes and the capabilities granted to it
+    Uninstall {
+        identifier: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum IndexCommand {
+    List,
+    /// Stats of an index, or of every index
+    Stats {
+        name: Option<String>,
+    },
+    /// Exports an index as a portable dump, or as a snapshot with `--snapshot`. Only for the
+    /// milli backend
+    Export {
+        name: String,
+        path: PathBuf,
+        #[arg(long)]
+        snapshot: bool,
+    },
+    /// Imports a dump or a snapshot into the indexes of the config folder, checking its checksums
+    /// first. Plugins find it on their next load. Only for the milli backend
+    Import {
+        path: PathBuf,
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Checks the checksums of a dump or a snapshot
+    Verify {
+        path: PathBuf,
+    },
+}
+
+#[derive(Serialize)]
+struct PluginEntries {
+    plugin: String,
+    entries: Vec<Entry>,
+}
+
+#[derive(Serialize)]
+struct DisplayedPlugin<'a> {
+    #[serde(flatten)]
+    infos: &'a PluginInfo,
+    can_index: bool,
+    enabled: bool,
+}
+
+#[derive(Serialize)]
+struct CheckedPackage<'a> {
+    manifest: &'a PluginManifest,
+    signature: SignatureStatus,
+}
+
+#[derive(Serialize)]
+struct KeyPair {
+    secret_key: String,
+    public_key: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    // Plugins left aside while loading are reported on stderr, away from the printed json
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    match cli.command {
+        Command::Plugins(PluginsCommand::Info { ref path }) => {
+            let persistence = Arc::new(MilliPersistence::new()?);
+            let plugin =
+                Plugin::load_from_path(path, persistence, Arc::new(Grants::in_memory())).await?;
+
+            print(plugin.infos())
+        }
+        Command::Plugins(PluginsCommand::Check { ref path }) => {
+            let package = PluginPackage::open(path)?;
+            let signatures = SignatureConfig::load(MichelConfig::default_signatures_path()?)?;
+
+            print(&CheckedPackage {
+                signature: signatures.verify(&package)?,
+                manifest: &package.manifest,
+            })
+        }
+        Command::Plugins(PluginsCommand::Keygen) => {
+            let mut secret = [0u8; 32];
+            rand::thread_rng().fill_bytes(&mut secret);
+            let secret_key: String = secret.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+            print(&KeyPair {
+                public_key: signature::public_key(&secret_key)?,
+                secret_key,
+            })
+        }
+        Command::Plugins(PluginsCommand::Sign { ref path, ref key }) => {
+            let public_key = signature::sign_package(path, &fs::read_to_string(key)?)?;
+
+            print(&serde_json::json!({ "public_key": public_key }))
+        }
+        Command::Plugins(PluginsCommand::Pending) => {
+            print(&Grants::load(MichelConfig::default_grants_path()?)?.pending())
+        }
+        Command::Plugins(PluginsCommand::Grant {
+            ref identifier,
+            ref capability,
+            yes,
+        }) => {
+            let grants = Grants::load(MichelConfig::default_grants_path()?)?;
+            let pending: Vec<Capability> = grants
+                .pending()
+                .into_iter()
+                .filter(|request| &request.plugin == identifier)
+                .map(|request| request.capability)
+                .collect();
+
+            let granted = match capability {
+                Some(capability) => {
+                    let capability: Capability = serde_json::from_str(capability)?;
+                    if !pending.contains(&capability) {
+                        return Err(anyhow!("{} isn't waiting for this capability", identifier));
+                    }
+
+                    vec![capability]
+                }
+                None if yes => pending,
+                None => {
+                    return Err(anyhow!(
+                        "{} is waiting for {} capabilities : pass one of them, or --yes to grant \
+                         them all",
+                        identifier,
+                        pending.len()
+                    ))
+                }
+            };
+
+            for capability in &granted {
+                grants.grant(identifier, capability.clone())?;
+            }
+
+            print(&granted)
+        }
+        // The index commands open the persistence alone, without loading the plugins
+        Command::Index(IndexCommand::Verify { ref path }) => print(&dump::verify(path)?),
+        Command::Index(IndexCommand::Import { ref path, ref name }) => {
+            print(&milli()?.import(path, name.clone()).await?)
+        }
+        Command::Index(IndexCommand::List) => print(&persistence(&cli)?.list_indexes().await?),
+        Command::Index(IndexCommand::Stats { ref name }) => {
+            let persistence = persistence(&cli)?;
+            let names = match name {
+                Some(name) => vec![name.clone()],
+                None => persistence.list_indexes().await?,
+            };
+
+            let mut stats = Vec::new();
+            for name in names {
+                stats.push(persistence.stats(Index { name }).await?);
+            }
+
+            print(&stats)
+        }
+        Command::Index(IndexCommand::Export {
+            ref name,
+            ref path,
+            snapshot,
+        }) => {
+            let persistence = milli()?;
+            let index = Index { name: name.clone() };
+            let manifest = if snapshot {
+                persistence.export_snapshot(index, path).await?
+            } else {
+                persistence.export_dump(index, path).await?
+            };
+
+            print(&manifest)
+        }
+        _ => {
+            let michel = load(&cli).await?;
+
+            run(&michel, cli.command).await
+        }
+    }
+}
+
+async fn load(cli: &Cli) -> Result<Michel> {
+    let plugins_path = match &cli.plugins {
+        Some(path) => path.clone(),
+        None => MichelConfig::default_plugins_path()?,
+    };
+
+    if !plugins_path.is_dir() {
+        return Err(anyhow!(
+            "no plugins folder at {}",
+            plugins_path.to_string_lossy()
+        ));
+    }
+
+    let michel = MichelInstance::new(
+        persistence(cli)?,
+        MichelConfig {
+            name: "Michel".to_string(),
+            plugins_path,
+            grants_path: Some(MichelConfig::default_grants_path()?),
+            kv_path: Some(MichelConfig::default_kv_path()?),
+            signatures: SignatureConfig::load(MichelConfig::default_signatures_path()?)?,
+            registry: cli.registry.clone(),
+        },
+    )
+    .await?;
+
+    if cli.index {
+        for plugin in michel.plugins().iter().filter(|plugin| plugin.can_index()) {
+            plugin.index().await?;
+        }
+    }
+
+    Ok(michel)
+}
+
+/// The backend of the config file, the trigram embedder being also enabled by the flag.
+fn persistence(cli: &Cli) -> Result<Box<dyn MichelPersistence>> {
+    let mut config = PersistenceConfig::load()?;
+    config.trigram_embedder |= cli.trigram_embedder;
+
+    config.open(&BACKENDS)
+}
+
+/// The milli indexes of the config folder, which dumps and snapshots are made of.
+fn milli() -> Result<MilliPersistence> {
+    let config = PersistenceConfig::load()?;
+    if config.persistence != PersistenceBackend::Milli {
+        return Err(anyhow!(
+            "dumps and snapshots are only made of milli indexes, not of the configured backend"
+        ));
+    }
+
+    MilliPersistence::open(config.path()?)
+}
+
+async fn run(michel: &Michel, command: Command) -> Result<()> {
+    match command {
+        Command::Query { input } => {
+            let mut results = Vec::new();
+
+            for plugin in michel.plugins().iter().filter(|plugin| plugin.enabled()) {
+                match plugin.entries_for_input(&input).await {
+                    Ok(entries) => results.push(PluginEntries {
+                        plugin: plugin.identifier(),
+                        entries,
+                    }),
+                    Err(error) => warn!("{:#}", error),
+                }
+            }
+
+            print(&results)
+        }
+        Command::Plugins(PluginsCommand::List) => print(
+            &michel
+                .plugins()
+                .iter()
+                .map(|plugin| DisplayedPlugin {
+                    infos: plugin.infos(),
+                    can_index: plugin.can_index(),
+                    enabled: plugin.enabled(),
+                })
+                .collect::<Vec<DisplayedPlugin>>(),
+        ),
+        Command::Plugins(PluginsCommand::Index { identifier }) => {
+            let plugin = michel
+                .plugin(identifier.clone())
+                .ok_or(anyhow!("no plugin {}", identifier))?;
+            plugin.index().await?;
+
+            print(&plugin.index_stats().await?)
+        }
+        Command::Plugins(
+            PluginsCommand::Info { .. }
+            | PluginsCommand::Check { .. }
+            | PluginsCommand::Keygen
+            | PluginsCommand::Sign { .. }
+            | PluginsCommand::Pending
+            | PluginsCommand::Grant { .. },
+        ) => unreachable!("runs without instance"),
+        Command::Plugins(PluginsCommand::Available) => print(&michel.available_plugins().await?),
+        Command::Plugins(PluginsCommand::Install { identifier }) => {
+            michel.install(&identifier).await?;
+
+            print(
+                &michel
+                    .plugin(identifier)
+                    .map(|plugin| plugin.infos().clone()),
+            )
+        }
+        Command::Plugins(PluginsCommand::Update { identifier }) => {
+            let updated = michel.update(&identifier).await?;
+
+            print(&serde_json::json!({ "updated": updated }))
+        }
+        Command::Plugins(PluginsCommand::Uninstall { identifier }) => {
+            michel.uninstall(&identifier).await?;
+
+            print(&serde_json::json!({ "uninstalled": identifier }))
+        }
+        Command::Index(_) => unreachable!("runs without instance"),
+    }
+}
+
+fn print<T: Serialize + ?Sized>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+
+    Ok(())
+}
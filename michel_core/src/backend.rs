@@ -0,0 +1,79 @@
+/*!
+    Which persistence the frontends keep their indexes in, as set in the `config.toml` of the
+    config folder they share. The backends live in crates depending on this one, so frontends
+    hand in how to open each of them, and get the one the user chose.
+*/
+
+use crate::embedder::{Embedder, TrigramEmbedder};
+use crate::persistence::MichelPersistence;
+use crate::MichelConfig;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PersistenceBackend {
+    #[default]
+    Milli,
+    Sqlite,
+}
+
+/// Opens a backend at a path, embedding the documents pushed without a vector if given one.
+pub type OpenBackend = fn(&Path, Option<Box<dyn Embedder>>) -> Result<Box<dyn MichelPersistence>>;
+
+pub struct Backends {
+    pub milli: OpenBackend,
+    pub sqlite: OpenBackend,
+}
+
+/// The keys of `config.toml` about persistence, the other ones being left to each frontend.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct PersistenceConfig {
+    #[serde(default)]
+    pub persistence: PersistenceBackend,
+    /// Hash the character trigrams of documents pushed without a vector into one. It isn't a
+    /// semantic model, so hybrid searches only gain typo tolerance from it
+    #[serde(default)]
+    pub trigram_embedder: bool,
+}
+
+impl PersistenceConfig {
+    /// The one of the config folder, the default one if there is no config file yet.
+    pub fn load() -> Result<PersistenceConfig> {
+        let path = MichelConfig::default_config_path()?;
+
+        if !path.exists() {
+            return Ok(PersistenceConfig::default());
+        }
+
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Where the chosen backend keeps the indexes.
+    pub fn path(&self) -> Result<PathBuf> {
+        match self.persistence {
+            PersistenceBackend::Milli => MichelConfig::default_indexes_path(),
+            PersistenceBackend::Sqlite => MichelConfig::default_sqlite_path(),
+        }
+    }
+
+    pub fn open(&self, backends: &Backends) -> Result<Box<dyn MichelPersistence>> {
+        let path = self.path()?;
+        if let Some(folder) = path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+
+        let embedder = if self.trigram_embedder {
+            Some(Box::new(TrigramEmbedder::default()) as Box<dyn Embedder>)
+        } else {
+            None
+        };
+
+        match self.persistence {
+            PersistenceBackend::Milli => (backends.milli)(&path, embedder),
+            PersistenceBackend::Sqlite => (backends.sqlite)(&path, embedder),
+        }
+    }
+}
@@ -0,0 +1,574 @@
+/*!
+    Behaviour every `MichelPersistence` backend has to share. Each backend runs it from its
+    own tests with `persistence_conformance_tests!`.
+*/
+
+use crate::persistence::{
+    DateFilter, HybridQuery, Index, IndexMetadata, MichelPersistence, PersistedDocument, SortOrder,
+    IDENTIFIER_FIELD, VECTOR_FIELD,
+};
+use crate::plugins::wasi::types;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::future::Future;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+#[macro_export]
+macro_rules! persistence_conformance_tests {
+    ($persistence:expr) => {
+        $crate::persistence_conformance_tests!(
+            @checks $persistence;
+            init_index_twice_fails,
+            adding_to_unknown_index_fails,
+            searching_unknown_index_fails,
+            flushing_unknown_index_fails,
+            flush_makes_documents_searchable,
+            indexes_are_listed,
+            stats_describe_documents,
+            stats_of_unknown_index_fail,
+            metadata_is_recorded,
+            clearing_index_removes_documents,
+            search_finds_documents_by_word,
+            search_matches_word_prefix,
+            empty_query_returns_every_document,
+            search_respects_limit,
+            documents_are_replaced_by_identifier,
+            documents_round_trip,
+            date_filter_restricts_range,
+            date_filter_sorts,
+            date_filter_reaches_nested_fields,
+            date_filter_refuses_invalid_fields,
+            hybrid_search_ranks_by_vector,
+            vectors_are_not_returned
+        );
+    };
+    (@checks $persistence:expr; $($check:ident),*) => {
+        $(
+            #[test]
+            fn $check() {
+                $crate::conformance::block_on($crate::conformance::$check($persistence));
+            }
+        )*
+    };
+}
+
+/// Runs a check without requiring the backend tests to set up a runtime.
+pub fn block_on<F: Future>(check: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("tokio runtime")
+        .block_on(check)
+}
+
+fn index(name: &str) -> Index {
+    Index {
+        name: String::from(name),
+    }
+}
+
+fn document(value: serde_json::Value) -> PersistedDocument {
+    value
+        .as_object()
+        .cloned()
+        .expect("a document is a json object")
+}
+
+fn datetime(date: &str) -> serde_json::Value {
+    serde_json::Value::try_from(types::Value::Datetime(String::from(date))).expect("valid datetime")
+}
+
+fn parse_datetime(date: &str) -> OffsetDateTime {
+    OffsetDateTime::parse(date, &Rfc3339).expect("valid datetime")
+}
+
+fn identifiers(documents: &[PersistedDocument]) -> Vec<String> {
+    documents
+        .iter()
+        .map(|document| {
+            document
+                .get(IDENTIFIER_FIELD)
+                .and_then(|id| id.as_str())
+                .map(String::from)
+                .expect("document with an identifier")
+        })
+        .collect()
+}
+
+fn sorted_identifiers(documents: &[PersistedDocument]) -> Vec<String> {
+    let mut identifiers = identifiers(documents);
+    identifiers.sort();
+    identifiers
+}
+
+async fn init_with<P: MichelPersistence>(
+    persistence: &P,
+    name: &str,
+    documents: Vec<serde_json::Value>,
+) {
+    persistence.init_index(String::from(name)).await.unwrap();
+    persistence
+        .add_documents(index(name), documents.into_iter().map(document).collect())
+        .await
+        .unwrap();
+    persistence.flush(index(name)).await.unwrap();
+}
+
+pub async fn init_index_twice_fails<P: MichelPersistence>(persistence: P) {
+    persistence.init_index(String::from("twice")).await.unwrap();
+
+    assert!(persistence.init_index(String::from("twice")).await.is_err());
+}
+
+pub async fn adding_to_unknown_index_fails<P: MichelPersistence>(persistence: P) {
+    let result = persistence
+        .add_document(index("unknown"), document(json!({"id": "1"})))
+        .await;
+
+    assert!(result.is_err());
+}
+
+pub async fn searching_unknown_index_fails<P: MichelPersistence>(persistence: P) {
+    let result = persistence
+        .search_document(index("unknown"), String::from("michel"), None)
+        .await;
+
+    assert!(result.is_err());
+}
+
+pub async fn flushing_unknown_index_fails<P: MichelPersistence>(persistence: P) {
+    assert!(persistence.flush(index("unknown")).await.is_err());
+}
+
+pub async fn flush_makes_documents_searchable<P: MichelPersistence>(persistence: P) {
+    persistence.init_index(String::from("flush")).await.unwrap();
+
+    for identifier in 0..50 {
+        persistence
+            .add_document(
+                index("flush"),
+                document(json!({"id": identifier.to_string(), "title": "pushed one by one"})),
+            )
+            .await
+            .unwrap();
+    }
+    persistence.flush(index("flush")).await.unwrap();
+
+    let found = persistence
+        .search_document(index("flush"), String::from("pushed"), None)
+        .await
+        .unwrap();
+
+    assert_eq!(found.len(), 50);
+}
+
+pub async fn indexes_are_listed<P: MichelPersistence>(persistence: P) {
+    persistence.init_index(String::from("zebra")).await.unwrap();
+    persistence
+        .init_index(String::from("aardvark"))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        persistence.list_indexes().await.unwrap(),
+        vec![String::from("aardvark"), String::from("zebra")]
+    );
+}
+
+pub async fn stats_describe_documents<P: MichelPersistence>(persistence: P) {
+    init_with(
+        &persistence,
+        "stats",
+        vec![
+            json!({"id": "1", "title": "invoice", "amount": 120}),
+            json!({"id": "2", "title": "receipt"}),
+        ],
+    )
+    .await;
+
+    let stats = persistence.stats(index("stats")).await.unwrap();
+
+    assert_eq!(stats.name, "stats");
+    assert_eq!(stats.document_count, 2);
+    assert_eq!(
+        stats.field_distribution,
+        BTreeMap::from([
+            (String::from("amount"), 1),
+            (String::from("id"), 2),
+            (String::from("title"), 2),
+        ])
+    );
+    assert!(stats.last_update.is_some());
+}
+
+pub async fn stats_of_unknown_index_fail<P: MichelPersistence>(persistence: P) {
+    assert!(persistence.stats(index("unknown")).await.is_err());
+}
+
+pub async fn metadata_is_recorded<P: MichelPersistence>(persistence: P) {
+    persistence
+        .init_index(String::from("versioned"))
+        .await
+        .unwrap();
+    let metadata = IndexMetadata {
+        plugin: String::from("files"),
+        plugin_version: String::from("1.2.0"),
+        schema_version: 3,
+    };
+
+    assert_eq!(
+        persistence.metadata(index("versioned")).await.unwrap(),
+        None
+    );
+
+    persistence
+        .set_metadata(index("versioned"), metadata.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        persistence.metadata(index("versioned")).await.unwrap(),
+        Some(metadata.clone())
+    );
+    assert!(persistence
+        .set_metadata(index("unknown"), metadata)
+        .await
+        .is_err());
+}
+
+pub async fn clearing_index_removes_documents<P: MichelPersistence>(persistence: P) {
+    init_with(
+        &persistence,
+        "clear",
+        vec![
+            json!({"id": "1", "title": "outdated", VECTOR_FIELD: [1.0, 0.0]}),
+            json!({"id": "2", "title": "outdated too"}),
+        ],
+    )
+    .await;
+
+    persistence.clear_index(index("clear")).await.unwrap();
+    persistence
+        .add_document(
+            index("clear"),
+            document(json!({"id": "3", "title": "fresh"})),
+        )
+        .await
+        .unwrap();
+    persistence.flush(index("clear")).await.unwrap();
+
+    let everything = persistence
+        .search_document(index("clear"), String::new(), None)
+        .await
+        .unwrap();
+    let similar = persistence
+        .hybrid_search_document(
+            index("clear"),
+            HybridQuery {
+                query: String::new(),
+                vector: Some(vec![1.0, 0.0]),
+                semantic_ratio: 1.0,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(identifiers(&everything), vec!["3"]);
+    assert!(!identifiers(&similar).contains(&String::from("1")));
+    assert!(persistence.clear_index(index("unknown")).await.is_err());
+}
+
+pub async fn search_finds_documents_by_word<P: MichelPersistence>(persistence: P) {
+    let invoice = json!({"id": "1", "title": "invoice from the plumber"});
+    init_with(
+        &persistence,
+        "words",
+        vec![
+            invoice.clone(),
+            json!({"id": "2", "title": "holiday pictures"}),
+        ],
+    )
+    .await;
+
+    let found = persistence
+        .search_document(index("words"), String::from("plumber"), None)
+        .await
+        .unwrap();
+
+    assert_eq!(found, vec![document(invoice)]);
+}
+
+pub async fn search_matches_word_prefix<P: MichelPersistence>(persistence: P) {
+    init_with(
+        &persistence,
+        "prefix",
+        vec![
+            json!({"id": "1", "title": "invoice from the plumber"}),
+            json!({"id": "2", "title": "holiday pictures"}),
+        ],
+    )
+    .await;
+
+    let found = persistence
+        .search_document(index("prefix"), String::from("plumb"), None)
+        .await
+        .unwrap();
+
+    assert_eq!(identifiers(&found), vec!["1"]);
+}
+
+pub async fn empty_query_returns_every_document<P: MichelPersistence>(persistence: P) {
+    init_with(
+        &persistence,
+        "everything",
+        vec![
+            json!({"id": "1", "title": "invoice from the plumber"}),
+            json!({"id": "2", "title": "holiday pictures"}),
+        ],
+    )
+    .await;
+
+    let found = persistence
+        .search_document(index("everything"), String::new(), None)
+        .await
+        .unwrap();
+
+    assert_eq!(sorted_identifiers(&found), vec!["1", "2"]);
+}
+
+pub async fn search_respects_limit<P: MichelPersistence>(persistence: P) {
+    init_with(
+        &persistence,
+        "limit",
+        vec![
+            json!({"id": "1", "title": "monthly report"}),
+            json!({"id": "2", "title": "weekly report"}),
+            json!({"id": "3", "title": "yearly report"}),
+        ],
+    )
+    .await;
+
+    let found = persistence
+        .search_document(index("limit"), String::from("report"), Some(2))
+        .await
+        .unwrap();
+
+    assert_eq!(found.len(), 2);
+}
+
+pub async fn documents_are_replaced_by_identifier<P: MichelPersistence>(persistence: P) {
+    init_with(
+        &persistence,
+        "replace",
+        vec![json!({"id": "1", "title": "draft"})],
+    )
+    .await;
+    persistence
+        .add_document(
+            index("replace"),
+            document(json!({"id": "1", "title": "final"})),
+        )
+        .await
+        .unwrap();
+    persistence.flush(index("replace")).await.unwrap();
+
+    let everything = persistence
+        .search_document(index("replace"), String::new(), None)
+        .await
+        .unwrap();
+    let drafts = persistence
+        .search_document(index("replace"), String::from("draft"), None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        everything,
+        vec![document(json!({"id": "1", "title": "final"}))]
+    );
+    assert!(drafts.is_empty());
+}
+
+pub async fn documents_round_trip<P: MichelPersistence>(persistence: P) {
+    let complete = json!({
+        "id": "1",
+        "title": "round trip",
+        "size": 1024,
+        "offset": -12,
+        "ratio": 0.5,
+        "hidden": false,
+        "parent": null,
+        "modified": datetime("2023-03-14T15:09:26+01:00"),
+        "tags": ["a", 1, 2.5, null, ["nested"]],
+        "owner": {"name": "michel", "groups": [{"name": "staff"}]}
+    });
+    init_with(&persistence, "round-trip", vec![complete.clone()]).await;
+
+    let found = persistence
+        .search_document(index("round-trip"), String::new(), None)
+        .await
+        .unwrap();
+
+    assert_eq!(found, vec![document(complete)]);
+}
+
+pub async fn date_filter_restricts_range<P: MichelPersistence>(persistence: P) {
+    init_with(
+        &persistence,
+        "dates",
+        vec![
+            json!({"id": "1", "modified": datetime("2023-01-01T00:00:00Z")}),
+            json!({"id": "2", "modified": datetime("2023-02-01T00:00:00Z")}),
+            json!({"id": "3", "modified": datetime("2023-03-01T00:00:00Z")}),
+        ],
+    )
+    .await;
+
+    let between = persistence
+        .search_document_by_date(
+            index("dates"),
+            String::new(),
+            DateFilter::between(
+                "modified",
+                parse_datetime("2023-01-15T00:00:00Z"),
+                parse_datetime("2023-02-15T00:00:00Z"),
+            ),
+            None,
+        )
+        .await
+        .unwrap();
+    let since = persistence
+        .search_document_by_date(
+            index("dates"),
+            String::new(),
+            DateFilter::since("modified", parse_datetime("2023-02-01T00:00:00Z")),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(identifiers(&between), vec!["2"]);
+    assert_eq!(sorted_identifiers(&since), vec!["2", "3"]);
+}
+
+pub async fn date_filter_sorts<P: MichelPersistence>(persistence: P) {
+    init_with(
+        &persistence,
+        "sorted-dates",
+        vec![
+            json!({"id": "1", "modified": datetime("2023-02-01T00:00:00Z")}),
+            json!({"id": "2", "modified": datetime("2023-03-01T00:00:00Z")}),
+            json!({"id": "3", "modified": datetime("2023-01-01T00:00:00Z")}),
+        ],
+    )
+    .await;
+
+    let found = persistence
+        .search_document_by_date(
+            index("sorted-dates"),
+            String::new(),
+            DateFilter::sorted_by("modified", SortOrder::Descending),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(identifiers(&found), vec!["2", "1", "3"]);
+}
+
+pub async fn date_filter_reaches_nested_fields<P: MichelPersistence>(persistence: P) {
+    init_with(
+        &persistence,
+        "nested-dates",
+        vec![
+            json!({"id": "1", "author": {"born": datetime("1815-12-10T00:00:00Z")}}),
+            json!({"id": "2", "author": {"born": datetime("1906-12-09T00:00:00Z")}}),
+            json!({"id": "3", "author": {"name": "unknown"}}),
+        ],
+    )
+    .await;
+
+    let found = persistence
+        .search_document_by_date(
+            index("nested-dates"),
+            String::new(),
+            DateFilter::since("author.born", parse_datetime("1900-01-01T00:00:00Z"))
+                .sorted(SortOrder::Ascending),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(identifiers(&found), vec!["2"]);
+}
+
+pub async fn date_filter_refuses_invalid_fields<P: MichelPersistence>(persistence: P) {
+    init_with(
+        &persistence,
+        "invalid-dates",
+        vec![json!({"id": "1", "modified": datetime("2023-01-01T00:00:00Z")})],
+    )
+    .await;
+
+    for field in [
+        "modified\" OR id = 1 OR \"",
+        "modified.",
+        "modified._michel_timestamp",
+    ] {
+        let found = persistence
+            .search_document_by_date(
+                index("invalid-dates"),
+                String::new(),
+                DateFilter::since(field, parse_datetime("2023-01-01T00:00:00Z")),
+                None,
+            )
+            .await;
+
+        assert!(found.is_err(), "{} was accepted", field);
+    }
+}
+
+pub async fn hybrid_search_ranks_by_vector<P: MichelPersistence>(persistence: P) {
+    init_with(
+        &persistence,
+        "vectors",
+        vec![
+            json!({"id": "1", "title": "east", VECTOR_FIELD: [1.0, 0.0]}),
+            json!({"id": "2", "title": "north", VECTOR_FIELD: [0.0, 1.0]}),
+            json!({"id": "3", "title": "north east", VECTOR_FIELD: [0.7, 0.7]}),
+        ],
+    )
+    .await;
+
+    let found = persistence
+        .hybrid_search_document(
+            index("vectors"),
+            HybridQuery {
+                query: String::new(),
+                vector: Some(vec![1.0, 0.0]),
+                semantic_ratio: 1.0,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(identifiers(&found), vec!["1", "3", "2"]);
+}
+
+pub async fn vectors_are_not_returned<P: MichelPersistence>(persistence: P) {
+    init_with(
+        &persistence,
+        "hidden-vectors",
+        vec![json!({"id": "1", "title": "east", VECTOR_FIELD: [1.0, 0.0]})],
+    )
+    .await;
+
+    let found = persistence
+        .search_document(index("hidden-vectors"), String::new(), None)
+        .await
+        .unwrap();
+
+    assert_eq!(found, vec![document(json!({"id": "1", "title": "east"}))]);
+}
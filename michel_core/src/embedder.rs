@@ -0,0 +1,111 @@
+/*!
+    Embedding vectors of the documents and queries, for hybrid searches. Every backend embeds
+    through the same `Embedder` trait, so it lives here rather than in one of them, and
+    `michel_index` re-exports it for the users of the milli backend.
+
+    No embedder is enabled by default : `TrigramEmbedder` is the only one shipped, and it isn't
+    semantic. Without an embedder, only the documents pushed with their own vector take part in
+    the semantic side of hybrid searches.
+*/
+
+use crate::persistence::{PersistedDocument, IDENTIFIER_FIELD};
+use anyhow::Result;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Turns text into an embedding vector. Used for the documents pushed without their own
+/// embedding, and for the hybrid search queries.
+pub trait Embedder: Send + Sync {
+    fn embed(&self, text: &str) -> Result<Vec<f32>>;
+}
+
+impl Embedder for Box<dyn Embedder> {
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        (**self).embed(text)
+    }
+}
+
+/**
+    Embeds text by hashing its character trigrams in a fixed size vector. It runs on CPU
+    without any model, and catches typos and word variations rather than meaning : a model
+    based `Embedder` can be plugged instead for the latter.
+*/
+pub struct TrigramEmbedder {
+    dimensions: usize,
+}
+
+impl TrigramEmbedder {
+    pub fn new(dimensions: usize) -> TrigramEmbedder {
+        TrigramEmbedder {
+            dimensions: dimensions.max(1),
+        }
+    }
+}
+
+impl Default for TrigramEmbedder {
+    fn default() -> Self {
+        TrigramEmbedder::new(256)
+    }
+}
+
+impl Embedder for TrigramEmbedder {
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let mut vector = vec![0f32; self.dimensions];
+
+        for word in text.split(|c: char| !c.is_alphanumeric()) {
+            if word.is_empty() {
+                continue;
+            }
+
+            let chars: Vec<char> = format!(" {} ", word.to_lowercase()).chars().collect();
+
+            for trigram in chars.windows(3) {
+                let hash = fnv1a(trigram);
+                let bucket = (hash % self.dimensions as u64) as usize;
+                let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+
+                vector[bucket] += sign;
+            }
+        }
+
+        let norm = vector.iter().map(|c| c * c).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|c| *c /= norm);
+        }
+
+        Ok(vector)
+    }
+}
+
+/// Stable across builds, unlike the std hasher.
+fn fnv1a(chars: &[char]) -> u64 {
+    chars
+        .iter()
+        .flat_map(|c| (*c as u32).to_le_bytes())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+}
+
+/// Text of a document worth embedding : its top level text fields.
+pub fn document_text(document: &PersistedDocument) -> String {
+    document
+        .iter()
+        .filter(|(field, _)| field.as_str() != IDENTIFIER_FIELD)
+        .filter_map(|(_, value)| value.as_str())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(a, b)| a * b).sum();
+    let norms =
+        a.iter().map(|c| c * c).sum::<f32>().sqrt() * b.iter().map(|c| c * c).sum::<f32>().sqrt();
+
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
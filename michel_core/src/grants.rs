@@ -0,0 +1,228 @@
+/*!
+    Capabilities the user granted to each plugin, or refused.
+
+    Plugins ask for the capabilities their manifest declares once loaded, and for an index when
+    initializing one they didn't declare. Each request the user didn't answer yet stays pending
+    until a frontend asks them, the plugin going without meanwhile. Pending requests are saved
+    along with the answers, for a frontend running in another process, like the CLI, to find
+    them. Answers are taken into account on the next call to the plugin.
+*/
+
+use crate::package::Capabilities;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::{env, fs};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Capability {
+    /// A folder preopened for the plugin, read-only unless `write` is set.
+    Fs {
+        path: PathBuf,
+        write: bool,
+    },
+    Network {
+        domain: String,
+    },
+    Clipboard,
+    Index {
+        name: String,
+    },
+}
+
+impl Capabilities {
+    pub fn list(&self) -> Vec<Capability> {
+        let folders = self.fs.iter().map(|folder| Capability::Fs {
+            path: folder.path.clone(),
+            write: folder.write,
+        });
+        let domains = self.network.iter().map(|domain| Capability::Network {
+            domain: domain.clone(),
+        });
+        let indexes = self
+            .indexes
+            .iter()
+            .map(|name| Capability::Index { name: name.clone() });
+        let clipboard = self.clipboard.then_some(Capability::Clipboard);
+
+        folders
+            .chain(domains)
+            .chain(clipboard)
+            .chain(indexes)
+            .collect()
+    }
+}
+
+/// Path on the host of a folder capability, a leading `~` standing for the home folder.
+pub(crate) fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), env::var("HOME")) {
+        (Ok(relative), Ok(home)) => Path::new(&home).join(relative),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Granted,
+    Denied,
+    Pending,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityRequest {
+    pub plugin: String,
+    pub capability: Capability,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct PluginGrants {
+    #[serde(default)]
+    granted: Vec<Capability>,
+    #[serde(default)]
+    denied: Vec<Capability>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct GrantsFile {
+    #[serde(default)]
+    plugins: BTreeMap<String, PluginGrants>,
+    /// Requests the user didn't answer yet, in the order they were made.
+    #[serde(default)]
+    pending: Vec<CapabilityRequest>,
+}
+
+pub struct Grants {
+    /// Where answers and pending requests are saved, kept in memory only when missing.
+    path: Option<PathBuf>,
+    state: Mutex<GrantsFile>,
+}
+
+impl Grants {
+    pub fn in_memory() -> Grants {
+        Grants {
+            path: None,
+            state: Mutex::new(GrantsFile::default()),
+        }
+    }
+
+    /// Reads what was saved at `path`, nothing if the file doesn't exist yet.
+    pub fn load<T: AsRef<Path>>(path: T) -> Result<Grants> {
+        let path = path.as_ref();
+        let file = if path.exists() {
+            toml::from_str(&fs::read_to_string(path)?)?
+        } else {
+            GrantsFile::default()
+        };
+
+        Ok(Grants {
+            path: Some(path.to_path_buf()),
+            state: Mutex::new(file),
+        })
+    }
+
+    pub fn decision(&self, plugin: &str, capability: &Capability) -> Decision {
+        decision(&self.state.lock().unwrap(), plugin, capability)
+    }
+
+    pub fn is_granted(&self, plugin: &str, capability: &Capability) -> bool {
+        self.decision(plugin, capability) == Decision::Granted
+    }
+
+    /// Records a pending request unless the user already answered, returning the decision.
+    pub fn request(&self, plugin: &str, capability: Capability) -> Result<Decision> {
+        let mut state = self.state.lock().unwrap();
+        let decision = decision(&state, plugin, &capability);
+
+        if decision == Decision::Pending {
+            let request = CapabilityRequest {
+                plugin: String::from(plugin),
+                capability,
+            };
+
+            if !state.pending.contains(&request) {
+                state.pending.push(request);
+                self.save(&state)?;
+            }
+        }
+
+        Ok(decision)
+    }
+
+    /// Requests the user didn't answer yet, in the order they were made.
+    pub fn pending(&self) -> Vec<CapabilityRequest> {
+        self.state.lock().unwrap().pending.clone()
+    }
+
+    pub fn is_pending(&self, plugin: &str, capability: &Capability) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .pending
+            .iter()
+            .any(|request| request.plugin == plugin && &request.capability == capability)
+    }
+
+    pub fn granted(&self, plugin: &str) -> Vec<Capability> {
+        self.state
+            .lock()
+            .unwrap()
+            .plugins
+            .get(plugin)
+            .map(|grants| grants.granted.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn grant(&self, plugin: &str, capability: Capability) -> Result<()> {
+        self.answer(plugin, capability, Decision::Granted)
+    }
+
+    /// Refuses a pending capability, or revokes a granted one.
+    pub fn deny(&self, plugin: &str, capability: Capability) -> Result<()> {
+        self.answer(plugin, capability, Decision::Denied)
+    }
+
+    fn answer(&self, plugin: &str, capability: Capability, answer: Decision) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+
+        state
+            .pending
+            .retain(|request| request.plugin != plugin || request.capability != capability);
+
+        let grants = state.plugins.entry(String::from(plugin)).or_default();
+        grants.granted.retain(|granted| granted != &capability);
+        grants.denied.retain(|denied| denied != &capability);
+
+        match answer {
+            Decision::Granted => grants.granted.push(capability),
+            Decision::Denied => grants.denied.push(capability),
+            Decision::Pending => {}
+        }
+
+        self.save(&state)
+    }
+
+    fn save(&self, state: &GrantsFile) -> Result<()> {
+        match &self.path {
+            Some(path) => {
+                if let Some(folder) = path.parent() {
+                    fs::create_dir_all(folder)?;
+                }
+
+                fs::write(path, toml::to_string(state)?).map_err(Into::into)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+fn decision(state: &GrantsFile, plugin: &str, capability: &Capability) -> Decision {
+    match state.plugins.get(plugin) {
+        Some(grants) if grants.granted.contains(capability) => Decision::Granted,
+        Some(grants) if grants.denied.contains(capability) => Decision::Denied,
+        _ => Decision::Pending,
+    }
+}
@@ -0,0 +1,355 @@
+/*!
+    Requests plugins make through the host, which can't reach the network themselves : only to
+    the domains they declared and the user granted, redirects included, within a timeout and a
+    size limit. Successful `GET` responses are cached, as long as their `Cache-Control` allows.
+    Credentials are only sent to the origin they were meant for, not to the ones it redirects to.
+*/
+
+use anyhow::{anyhow, Result};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CACHE_CONTROL, COOKIE, LOCATION,
+    PROXY_AUTHORIZATION,
+};
+use reqwest::{redirect, Client, StatusCode, Url};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct HttpLimits {
+    /// For the whole request, redirects included.
+    pub timeout: Duration,
+    /// Size of a response body, in bytes.
+    pub max_response_size: usize,
+    pub max_redirects: usize,
+    /// How long a response is cached, when the server doesn't say less.
+    pub cache_ttl: Duration,
+    /// Size of all the cached bodies, in bytes.
+    pub max_cache_size: usize,
+}
+
+impl Default for HttpLimits {
+    fn default() -> Self {
+        HttpLimits {
+            timeout: Duration::from_secs(30),
+            max_response_size: 10 * 1024 * 1024,
+            max_redirects: 5,
+            cache_ttl: Duration::from_secs(5 * 60),
+            max_cache_size: 32 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HttpMethod {
+    Get,
+    Head,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl From<HttpMethod> for reqwest::Method {
+    fn from(value: HttpMethod) -> Self {
+        match value {
+            HttpMethod::Get => reqwest::Method::GET,
+            HttpMethod::Head => reqwest::Method::HEAD,
+            HttpMethod::Post => reqwest::Method::POST,
+            HttpMethod::Put => reqwest::Method::PUT,
+            HttpMethod::Patch => reqwest::Method::PATCH,
+            HttpMethod::Delete => reqwest::Method::DELETE,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl HttpRequest {
+    pub fn get(url: impl Into<String>) -> HttpRequest {
+        HttpRequest {
+            method: HttpMethod::Get,
+            url: url.into(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Whether `url` is on one of `domains`, or one of their subdomains.
+pub fn domain_allowed(url: &Url, domains: &[String]) -> bool {
+    let host = match url.host_str() {
+        Some(host) => host.to_lowercase(),
+        None => return false,
+    };
+
+    domains.iter().any(|domain| {
+        let domain = domain.trim_end_matches('.').to_lowercase();
+
+        host == domain || host.ends_with(&format!(".{}", domain))
+    })
+}
+
+/// Drops the headers carrying credentials, before a request is redirected to another origin.
+fn strip_credentials(headers: &mut HeaderMap) {
+    for name in [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION] {
+        headers.remove(name);
+    }
+}
+
+/// Refuses the URLs that aren't http, or not on one of `domains`.
+fn check_url(url: &Url, domains: &[String]) -> Result<()> {
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(anyhow!("{} is neither http nor https", url));
+    }
+
+    if !domain_allowed(url, domains) {
+        return Err(anyhow!(
+            "{} is not a domain the plugin was granted",
+            url.host_str().unwrap_or_default()
+        ));
+    }
+
+    Ok(())
+}
+
+/// How long a response can be cached, according to its `Cache-Control`, at most `limit`.
+fn cache_ttl(headers: &HeaderMap, limit: Duration) -> Option<Duration> {
+    let control = match headers
+        .get(CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(control) => control.to_lowercase(),
+        None => return Some(limit),
+    };
+
+    let mut ttl = limit;
+    for directive in control.split(',').map(str::trim) {
+        if directive == "no-store" || directive == "no-cache" {
+            return None;
+        }
+
+        if let Some(Ok(seconds)) = directive.strip_prefix("max-age=").map(str::parse::<u64>) {
+            ttl = ttl.min(Duration::from_secs(seconds));
+        }
+    }
+
+    (!ttl.is_zero()).then_some(ttl)
+}
+
+/// A `GET` is cached by URL and headers, which may carry credentials.
+#[derive(PartialEq, Eq, Hash)]
+struct CacheKey {
+    url: String,
+    headers: Vec<(String, String)>,
+}
+
+struct Cached {
+    response: HttpResponse,
+    /// Where the response came from, once redirects were followed.
+    url: Url,
+    expires: Instant,
+}
+
+/// Client of one plugin, so that plugins don't share their cache.
+pub struct HttpClient {
+    client: Client,
+    limits: HttpLimits,
+    cache: Mutex<HashMap<CacheKey, Cached>>,
+}
+
+impl HttpClient {
+    pub fn new(limits: HttpLimits) -> Result<HttpClient> {
+        // Redirects are followed by hand, as each of them has to be granted
+        let client = Client::builder()
+            .timeout(limits.timeout)
+            .redirect(redirect::Policy::none())
+            .build()?;
+
+        Ok(HttpClient {
+            client,
+            limits,
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Performs `request`, refusing the URLs that are not on one of `domains`.
+    pub async fn fetch(&self, request: HttpRequest, domains: &[String]) -> Result<HttpResponse> {
+        // Checked before the cache, the domains may have been revoked since it was filled
+        check_url(&Url::parse(&request.url)?, domains)?;
+
+        let key = CacheKey {
+            url: request.url.clone(),
+            headers: request.headers.clone(),
+        };
+
+        if request.method == HttpMethod::Get {
+            if let Some(cached) = self.cache.lock().unwrap().get(&key) {
+                if cached.expires > Instant::now() && domain_allowed(&cached.url, domains) {
+                    return Ok(cached.response.clone());
+                }
+            }
+        }
+
+        let (response, url, ttl) = self.send(request, domains).await?;
+
+        if let Some(ttl) = ttl {
+            self.cache(key, response.clone(), url, ttl);
+        }
+
+        Ok(response)
+    }
+
+    /**
+        Sends a request and follows its redirects. Returns the response along with the URL it
+        came from, and how long it can be cached.
+    */
+    async fn send(
+        &self,
+        request: HttpRequest,
+        domains: &[String],
+    ) -> Result<(HttpResponse, Url, Option<Duration>)> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &request.headers {
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+
+        let mut url = Url::parse(&request.url)?;
+        let mut method = request.method;
+        let mut body = request.body;
+        let deadline = Instant::now() + self.limits.timeout;
+
+        for _ in 0..=self.limits.max_redirects {
+            check_url(&url, domains)?;
+
+            let mut builder = self
+                .client
+                .request(method.into(), url.clone())
+                .headers(headers.clone())
+                .timeout(deadline.saturating_duration_since(Instant::now()));
+            if let Some(body) = &body {
+                builder = builder.body(body.clone());
+            }
+
+            let mut response = builder.send().await.map_err(|error| self.error(error))?;
+
+            if response.status().is_redirection() {
+                if let Some(location) = response.headers().get(LOCATION) {
+                    let next = url.join(location.to_str()?)?;
+                    if next.origin() != url.origin() {
+                        strip_credentials(&mut headers);
+                    }
+                    url = next;
+
+                    if response.status() == StatusCode::SEE_OTHER {
+                        method = HttpMethod::Get;
+                        body = None;
+                    }
+
+                    continue;
+                }
+            }
+
+            if response.content_length().map_or(false, |length| {
+                length as usize > self.limits.max_response_size
+            }) {
+                return Err(self.too_large(&url));
+            }
+
+            let mut content = Vec::new();
+            while let Some(chunk) = response.chunk().await.map_err(|error| self.error(error))? {
+                if content.len() + chunk.len() > self.limits.max_response_size {
+                    return Err(self.too_large(&url));
+                }
+
+                content.extend_from_slice(&chunk);
+            }
+
+            let ttl = match method == HttpMethod::Get && response.status().is_success() {
+                true => cache_ttl(response.headers(), self.limits.cache_ttl),
+                false => None,
+            };
+
+            let response = HttpResponse {
+                status: response.status().as_u16(),
+                headers: response
+                    .headers()
+                    .iter()
+                    .map(|(name, value)| {
+                        (
+                            name.to_string(),
+                            String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                        )
+                    })
+                    .collect(),
+                body: content,
+            };
+
+            return Ok((response, url, ttl));
+        }
+
+        Err(anyhow!(
+            "{} redirected more than {} times",
+            request.url,
+            self.limits.max_redirects
+        ))
+    }
+
+    fn cache(&self, key: CacheKey, response: HttpResponse, url: Url, ttl: Duration) {
+        let mut cache = self.cache.lock().unwrap();
+        let now = Instant::now();
+
+        cache.retain(|_, cached| cached.expires > now);
+
+        let size: usize = cache
+            .values()
+            .map(|cached| cached.response.body.len())
+            .sum();
+        if size + response.body.len() > self.limits.max_cache_size {
+            return;
+        }
+
+        cache.insert(
+            key,
+            Cached {
+                response,
+                url,
+                expires: now + ttl,
+            },
+        );
+    }
+
+    fn error(&self, error: reqwest::Error) -> anyhow::Error {
+        match error.is_timeout() {
+            true => anyhow!(
+                "the request timed out after {}s",
+                self.limits.timeout.as_secs_f32()
+            ),
+            false => error.into(),
+        }
+    }
+
+    fn too_large(&self, url: &Url) -> anyhow::Error {
+        anyhow!(
+            "the response of {} is larger than {} bytes",
+            url,
+            self.limits.max_response_size
+        )
+    }
+}
@@ -0,0 +1,183 @@
+/*!
+    Storage of the plugins besides their indexes, for cursors, timestamps or tokens : each
+    plugin has its own namespace of keys, kept in a folder named after it with one file per key,
+    and within quotas.
+*/
+
+use crate::package::{decode_hex, encode_hex};
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const PART_EXTENSION: &str = "part";
+
+#[derive(Debug, Clone)]
+pub struct KvLimits {
+    /// Size of a key, in bytes.
+    pub max_key_size: usize,
+    /// Size of a value, in bytes.
+    pub max_value_size: usize,
+    /// Size of all the keys and values of a plugin, in bytes.
+    pub quota: usize,
+}
+
+impl Default for KvLimits {
+    fn default() -> Self {
+        KvLimits {
+            max_key_size: 100,
+            max_value_size: 1024 * 1024,
+            quota: 10 * 1024 * 1024,
+        }
+    }
+}
+
+/// Keys and values of a plugin, all held in memory, written through to its folder if any.
+pub struct KvStore {
+    folder: Option<PathBuf>,
+    limits: KvLimits,
+    values: Mutex<BTreeMap<String, Vec<u8>>>,
+}
+
+impl KvStore {
+    pub fn in_memory(limits: KvLimits) -> KvStore {
+        KvStore {
+            folder: None,
+            limits,
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Reads the namespace of `plugin` in `root`, empty if it doesn't exist yet.
+    pub fn open<T: AsRef<Path>>(root: T, plugin: &str, limits: KvLimits) -> Result<KvStore> {
+        if plugin.is_empty()
+            || plugin.chars().all(|character| character == '.')
+            || plugin.contains(['/', '\\'])
+        {
+            return Err(anyhow!(
+                "no storage can be named after plugin \"{}\"",
+                plugin
+            ));
+        }
+
+        let folder = root.as_ref().join(plugin);
+        let mut values = BTreeMap::new();
+
+        if folder.is_dir() {
+            for file in fs::read_dir(&folder)? {
+                let path = file?.path();
+
+                // Left over by a write that didn't complete
+                if path
+                    .extension()
+                    .map_or(false, |extension| extension == PART_EXTENSION)
+                {
+                    fs::remove_file(&path)?;
+                    continue;
+                }
+
+                let key = path
+                    .file_name()
+                    .and_then(|name| decode_hex(&name.to_string_lossy()))
+                    .and_then(|bytes| String::from_utf8(bytes).ok());
+                if let Some(key) = key {
+                    values.insert(key, fs::read(&path)?);
+                }
+            }
+        }
+
+        Ok(KvStore {
+            folder: Some(folder),
+            limits,
+            values: Mutex::new(values),
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.values.lock().unwrap().get(key).cloned()
+    }
+
+    pub fn set(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        if key.is_empty() || key.len() > self.limits.max_key_size {
+            return Err(anyhow!(
+                "keys have to be between 1 and {} bytes long",
+                self.limits.max_key_size
+            ));
+        }
+
+        if value.len() > self.limits.max_value_size {
+            return Err(anyhow!(
+                "values can't be larger than {} bytes",
+                self.limits.max_value_size
+            ));
+        }
+
+        let mut values = self.values.lock().unwrap();
+        let usage = usage(&values)
+            - values
+                .get(key)
+                .map_or(0, |previous| key.len() + previous.len())
+            + key.len()
+            + value.len();
+        if usage > self.limits.quota {
+            return Err(anyhow!(
+                "the storage of the plugin would exceed its quota of {} bytes",
+                self.limits.quota
+            ));
+        }
+
+        if let Some(folder) = &self.folder {
+            fs::create_dir_all(folder)?;
+
+            let path = folder.join(encode_hex(key.as_bytes()));
+            let part = path.with_extension(PART_EXTENSION);
+            fs::write(&part, &value)?;
+            fs::rename(&part, &path)?;
+        }
+
+        values.insert(String::from(key), value);
+
+        Ok(())
+    }
+
+    /// Removes a key, if it exists.
+    pub fn delete(&self, key: &str) -> Result<()> {
+        let mut values = self.values.lock().unwrap();
+
+        if !values.contains_key(key) {
+            return Ok(());
+        }
+
+        if let Some(folder) = &self.folder {
+            fs::remove_file(folder.join(encode_hex(key.as_bytes())))?;
+        }
+        values.remove(key);
+
+        Ok(())
+    }
+
+    /// Keys starting with `prefix`, sorted.
+    pub fn list_prefix(&self, prefix: &str) -> Vec<String> {
+        self.values
+            .lock()
+            .unwrap()
+            .range(String::from(prefix)..)
+            .map(|(key, _)| key)
+            .take_while(|key| key.starts_with(prefix))
+            .cloned()
+            .collect()
+    }
+
+    /// Size of all the keys and values, counted against the quota.
+    pub fn usage(&self) -> usize {
+        usage(&self.values.lock().unwrap())
+    }
+}
+
+fn usage(values: &BTreeMap<String, Vec<u8>>) -> usize {
+    values
+        .iter()
+        .map(|(key, value)| key.len() + value.len())
+        .sum()
+}
@@ -1,20 +1,51 @@
+#[macro_use]
+extern crate log;
+
+pub mod backend;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+pub mod embedder;
+pub mod grants;
+pub mod http;
+pub mod kv;
+#[cfg(feature = "memory")]
+pub mod memory;
+pub mod package;
 pub mod persistence;
 mod plugins;
+pub mod registry;
+pub mod signature;
 
-use crate::persistence::MichelPersistence;
+use crate::grants::{Capability, Grants};
+use crate::package::{Capabilities, PluginManifest, PluginPackage, ARCHIVE_EXTENSION};
+use crate::persistence::{Index, IndexMetadata, IndexStats, MichelPersistence};
+use crate::registry::{AvailablePlugin, Registry, RegistryVersion};
+use crate::signature::{SignatureConfig, SignaturePolicy, SignatureStatus};
 use anyhow::{anyhow, Result};
+pub use plugins::declared_api_version;
+/// Types of the latest `michel:plugin` world, as the host binds them.
+pub use plugins::wasi::types;
 use plugins::wasi::PluginInstance;
+use semver::Version;
 use serde::{Deserialize, Serialize};
-use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use wasmtime::AsContextMut;
+use std::sync::{Arc, RwLock};
+use std::{env, fs};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Action {
+    pub identifier: String,
+    pub label: String,
+}
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Entry {
     title: String,
     description: String,
+    #[serde(default)]
+    preview: Option<String>,
+    #[serde(default)]
+    actions: Vec<Action>,
 }
 
 pub struct FsAccess {
@@ -36,11 +67,13 @@ pub struct PluginConfig {
     custom: CustomPluginConfig,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PluginInfo {
     identifier: String,
     name: String,
     description: String,
     version: String,
+    schema_version: u32,
     icon: Option<String>,
     url: Option<String>,
 }
@@ -49,19 +82,101 @@ pub struct Plugin<P: MichelPersistence> {
     instance: PluginInstance<P>,
     infos: PluginInfo,
     config: PluginConfig,
+    /// Missing for a bare wasm component, which then declares no capability.
+    manifest: Option<PluginManifest>,
+    icon: Option<Vec<u8>>,
+    signature: SignatureStatus,
+    /// Package or wasm file the plugin was loaded from by an instance.
+    path: Option<PathBuf>,
 }
 
 impl<P: MichelPersistence> Plugin<P> {
+    /// Loads a plugin package, or a bare wasm component.
     pub async fn load_from_path<T: AsRef<Path>>(
         path: T,
-        persistence: Arc<Mutex<P>>,
+        persistence: Arc<P>,
+        grants: Arc<Grants>,
+    ) -> Result<Plugin<P>> {
+        if PluginPackage::is_package(&path) {
+            return Plugin::load_package(PluginPackage::open(path)?, persistence, grants).await;
+        }
+
+        let wasm = fs::read(path)?;
+        let target = plugins::declared_api_version(&wasm)?;
+
+        Plugin::load_from_wasm(&wasm, &target, persistence, grants).await
+    }
+
+    /**
+        Instantiates a package, checking that the plugin is the one its manifest describes,
+        then asks for the capabilities it declares.
+    */
+    pub async fn load_package(
+        package: PluginPackage,
+        persistence: Arc<P>,
+        grants: Arc<Grants>,
     ) -> Result<Plugin<P>> {
-        let instance = PluginInstance::init(path, persistence.clone()).await?;
+        let mut plugin = Plugin::load_from_wasm(
+            &package.wasm,
+            &package.manifest.min_host_api,
+            persistence,
+            grants.clone(),
+        )
+        .await?;
+        let manifest = package.manifest;
+
+        if plugin.infos.identifier != manifest.identifier {
+            return Err(anyhow!(
+                "the manifest describes {}, but the plugin is {}",
+                manifest.identifier,
+                plugin.infos.identifier
+            ));
+        }
+
+        if plugin.infos.version != manifest.version.to_string() {
+            return Err(anyhow!(
+                "the manifest describes version {} of {}, but the plugin is version {}",
+                manifest.version,
+                manifest.identifier,
+                plugin.infos.version
+            ));
+        }
+
+        for capability in manifest.capabilities.list() {
+            grants.request(&manifest.identifier, capability)?;
+        }
+        plugin
+            .instance
+            .declare_domains(manifest.capabilities.network.clone())
+            .await;
+
+        plugin.manifest = Some(manifest);
+        plugin.icon = package.icon;
+
+        Ok(plugin)
+    }
+
+    /// `target` is the version of the `michel:plugin` world the plugin was built against.
+    async fn load_from_wasm(
+        wasm: &[u8],
+        target: &Version,
+        persistence: Arc<P>,
+        grants: Arc<Grants>,
+    ) -> Result<Plugin<P>> {
+        let instance = PluginInstance::init(wasm, target, persistence, grants).await?;
+
+        let infos = PluginInfo::from(instance.get_infos().await?);
+        instance
+            .set_owner(IndexMetadata {
+                plugin: infos.identifier.clone(),
+                plugin_version: infos.version.clone(),
+                schema_version: infos.schema_version,
+            })
+            .await;
 
-        let infos = instance.get_infos().await?;
         Ok(Plugin {
             instance,
-            infos: PluginInfo::from(infos),
+            infos,
             config: PluginConfig {
                 host: PluginHostConfig {
                     fs_access: vec![],
@@ -69,41 +184,146 @@ impl<P: MichelPersistence> Plugin<P> {
                 },
                 custom: CustomPluginConfig,
             },
+            manifest: None,
+            icon: None,
+            signature: SignatureStatus::Unsigned,
+            path: None,
         })
     }
 
     pub async fn index(&self) -> Result<()> {
-        let mut guard = self.instance.store.lock().await;
-        let store = guard.as_context_mut();
-        return match self
-            .instance
-            .bindings
-            .plugin_api()
-            .call_index(store)
-            .await?
-        {
-            Ok(_) => Ok(()),
+        match self.instance.index().await? {
+            Ok(_) => self.instance.flush_indexes().await,
             Err(_) => Err(anyhow!("run index")),
-        };
+        }
+    }
+
+    /**
+        Lets the plugin migrate the indexes written by another version of it, then indexes
+        again if some of them had to be rebuilt.
+    */
+    pub async fn migrate_indexes(&self, persistence: &P) -> Result<()> {
+        let current = self.index_metadata();
+        let mut rebuilt = Vec::new();
+
+        for name in persistence.list_indexes().await? {
+            let previous = match persistence.metadata(Index { name: name.clone() }).await? {
+                Some(metadata) if metadata.plugin == current.plugin => metadata,
+                _ => continue,
+            };
+
+            self.instance.adopt_index(&name).await;
+
+            if previous == current {
+                continue;
+            }
+
+            match self.instance.migrate(&name, previous).await? {
+                types::Migration::Keep => {
+                    persistence
+                        .set_metadata(Index { name }, current.clone())
+                        .await?
+                }
+                types::Migration::Rebuild => {
+                    persistence
+                        .clear_index(Index { name: name.clone() })
+                        .await?;
+                    rebuilt.push(name);
+                }
+            }
+        }
+
+        if rebuilt.is_empty() {
+            return Ok(());
+        }
+
+        // Versions are only updated once rebuilt, so a failed reindex is retried on next load
+        self.index().await?;
+
+        for name in rebuilt {
+            persistence
+                .set_metadata(Index { name }, current.clone())
+                .await?;
+        }
+
+        Ok(())
     }
 
-    pub async fn entries_for_input(&self, input: &str) -> Vec<Entry> {
-        let mut guard = self.instance.store.lock().await;
-        let store = guard.as_context_mut();
+    fn index_metadata(&self) -> IndexMetadata {
+        IndexMetadata {
+            plugin: self.infos.identifier.clone(),
+            plugin_version: self.infos.version.clone(),
+            schema_version: self.infos.schema_version,
+        }
+    }
 
-        return self
+    pub async fn entries_for_input(&self, input: &str) -> Result<Vec<Entry>> {
+        self.entries_for_input_if(input, || true)
+            .await
+            .map(Option::unwrap_or_default)
+    }
+
+    /// Nothing if `wanted` returns false once the plugin is free to answer.
+    pub async fn entries_for_input_if<F: Fn() -> bool>(
+        &self,
+        input: &str,
+        wanted: F,
+    ) -> Result<Option<Vec<Entry>>> {
+        let entries = self
             .instance
-            .bindings
-            .plugin_api()
-            .call_for_input(store, input)
+            .for_input_if(input, wanted)
             .await
-            .expect("fetch entries")
-            .iter()
-            .map(|it| Entry {
-                title: it.title.clone(),
-                description: it.description.clone(),
-            })
-            .collect();
+            .map_err(|error| anyhow!("{} failed to answer : {}", self.identifier(), error))?;
+
+        Ok(entries.map(|entries| entries.into_iter().map(Entry::from).collect()))
+    }
+
+    pub async fn autocomplete(&self, input: &str) -> Result<Option<String>> {
+        self.instance.autocomplete(input).await
+    }
+
+    pub async fn execute_action(&self, entry: Entry, action: &str) -> Result<()> {
+        match self
+            .instance
+            .execute_action(types::Entry::from(entry), action)
+            .await?
+        {
+            Ok(()) => Ok(()),
+            Err(types::Error::InvalidValue(message)) => Err(anyhow!(message)),
+        }
+    }
+
+    pub async fn index_stats(&self) -> Result<Vec<IndexStats>> {
+        self.instance.index_stats().await
+    }
+
+    pub fn infos(&self) -> &PluginInfo {
+        &self.infos
+    }
+
+    pub fn manifest(&self) -> Option<&PluginManifest> {
+        self.manifest.as_ref()
+    }
+
+    /// Capabilities declared in the manifest, none for a bare wasm component.
+    pub fn capabilities(&self) -> Capabilities {
+        self.manifest
+            .as_ref()
+            .map(|manifest| manifest.capabilities.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn icon(&self) -> Option<&[u8]> {
+        self.icon.as_deref()
+    }
+
+    pub fn signature(&self) -> &SignatureStatus {
+        &self.signature
+    }
+
+    /// Version of the `michel:plugin` world the plugin is linked against.
+    pub fn api_version(&self) -> &Version {
+        self.instance.api_version()
     }
 
     pub fn identifier(&self) -> String {
@@ -115,6 +335,9 @@ impl<P: MichelPersistence> Plugin<P> {
     pub fn description(&self) -> String {
         String::from(&self.infos.description)
     }
+    pub fn version(&self) -> String {
+        String::from(&self.infos.version)
+    }
     pub fn can_index(&self) -> bool {
         true
     }
@@ -123,23 +346,130 @@ impl<P: MichelPersistence> Plugin<P> {
     }
 }
 
+impl From<types::Entry> for Entry {
+    fn from(value: types::Entry) -> Self {
+        Entry {
+            title: value.title,
+            description: value.description,
+            preview: value.preview,
+            actions: value
+                .actions
+                .into_iter()
+                .map(|action| Action {
+                    identifier: action.identifier,
+                    label: action.label,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<Entry> for types::Entry {
+    fn from(value: Entry) -> Self {
+        types::Entry {
+            title: value.title,
+            description: value.description,
+            preview: value.preview,
+            actions: value
+                .actions
+                .into_iter()
+                .map(|action| types::Action {
+                    identifier: action.identifier,
+                    label: action.label,
+                })
+                .collect(),
+        }
+    }
+}
+
 pub struct MichelConfig {
     pub name: String,
     pub plugins_path: PathBuf,
+    /// Where capabilities granted to plugins are saved, kept in memory only if unset.
+    pub grants_path: Option<PathBuf>,
+    /// Where plugins keep their keys and values, in memory only if unset.
+    pub kv_path: Option<PathBuf>,
+    pub signatures: SignatureConfig,
+    /// Location of the registry plugins are installed from, see [`Registry::new`].
+    pub registry: Option<String>,
+}
+
+const CONFIG_FOLDER: &str = "michel";
+const PLUGINS_FOLDER: &str = "plugins";
+const DAEMON_SOCKET: &str = "michel.sock";
+const GRANTS_FILE: &str = "grants.toml";
+const KV_FOLDER: &str = "kv";
+const INDEXES_FOLDER: &str = "indexes";
+const SQLITE_FILE: &str = "index.sqlite";
+const CONFIG_FILE: &str = "config.toml";
+const SIGNATURES_FILE: &str = "signatures.toml";
+
+impl MichelConfig {
+    /// `$XDG_CONFIG_HOME/michel`, or `$HOME/michel` when it's not set.
+    pub fn default_folder() -> Result<PathBuf> {
+        env::var("XDG_CONFIG_HOME")
+            .or_else(|_| env::var("HOME"))
+            .map(|path| Path::new(&path).join(CONFIG_FOLDER))
+            .or(Err(anyhow!("no place to find michel config")))
+    }
+
+    pub fn default_plugins_path() -> Result<PathBuf> {
+        MichelConfig::default_folder().map(|folder| folder.join(PLUGINS_FOLDER))
+    }
+
+    pub fn default_grants_path() -> Result<PathBuf> {
+        MichelConfig::default_folder().map(|folder| folder.join(GRANTS_FILE))
+    }
+
+    pub fn default_kv_path() -> Result<PathBuf> {
+        MichelConfig::default_folder().map(|folder| folder.join(KV_FOLDER))
+    }
+
+    /// Indexes of the milli backend, kept across runs so that plugins migrate them on load.
+    pub fn default_indexes_path() -> Result<PathBuf> {
+        MichelConfig::default_folder().map(|folder| folder.join(INDEXES_FOLDER))
+    }
+
+    /// Database of the SQLite backend, holding every index.
+    pub fn default_sqlite_path() -> Result<PathBuf> {
+        MichelConfig::default_folder().map(|folder| folder.join(SQLITE_FILE))
+    }
+
+    /// Settings shared by the frontends, see [`backend::PersistenceConfig`].
+    pub fn default_config_path() -> Result<PathBuf> {
+        MichelConfig::default_folder().map(|folder| folder.join(CONFIG_FILE))
+    }
+
+    pub fn default_signatures_path() -> Result<PathBuf> {
+        MichelConfig::default_folder().map(|folder| folder.join(SIGNATURES_FILE))
+    }
+
+    /// Socket a daemon listens to when frontends are clients of it, instead of embedding Michel.
+    pub fn default_socket_path() -> Result<PathBuf> {
+        MichelConfig::default_folder().map(|folder| folder.join(DAEMON_SOCKET))
+    }
 }
 
 pub struct MichelInstance<P: MichelPersistence> {
-    persistence: Arc<Mutex<P>>,
+    persistence: Arc<P>,
+    grants: Arc<Grants>,
     config: MichelConfig,
-    plugins: Vec<Plugin<P>>,
+    /// Plugins are shared with the calls running on them, so that installing one doesn't wait.
+    plugins: RwLock<Vec<Arc<Plugin<P>>>>,
 }
 
 impl<P: MichelPersistence> MichelInstance<P> {
     pub async fn new(persistence: P, config: MichelConfig) -> Result<MichelInstance<P>> {
-        let mut instance = MichelInstance {
-            persistence: Arc::new(Mutex::new(persistence)),
+        let grants = match &config.grants_path {
+            Some(path) => Grants::load(path)?,
+            None => Grants::in_memory(),
+        };
+
+        let instance = MichelInstance {
+            persistence: Arc::new(persistence),
+            grants: Arc::new(grants),
             config,
-            plugins: vec![],
+            plugins: RwLock::new(vec![]),
         };
 
         instance.refresh_plugins().await?;
@@ -147,45 +477,321 @@ impl<P: MichelPersistence> MichelInstance<P> {
         Ok(instance)
     }
 
-    async fn refresh_plugins(&mut self) -> Result<()> {
-        let paths = fs::read_dir(self.config.plugins_path.as_path()).unwrap();
+    /// A plugin failing to load is left aside, so that it doesn't take the others down with it.
+    async fn refresh_plugins(&self) -> Result<()> {
+        let paths = fs::read_dir(self.config.plugins_path.as_path())?;
 
-        let mut plugins: Vec<Plugin<P>> = Vec::new();
+        let mut plugins = Vec::new();
 
         for path in paths {
             let plugin_path = path?.path();
 
-            let state = Plugin::load_from_path(plugin_path, self.persistence.clone()).await?;
-            plugins.push(state)
+            match self.open_plugin(&plugin_path).await {
+                Ok(Some(plugin)) => plugins.push(Arc::new(plugin)),
+                Ok(None) => {}
+                Err(error) => error!(
+                    "skipping the plugin at {} : {:#}",
+                    plugin_path.to_string_lossy(),
+                    error
+                ),
+            }
         }
 
-        self.plugins = plugins;
+        *self.plugins.write().unwrap() = plugins;
 
         Ok(())
     }
 
-    pub async fn entries_for_input(&self, input: &str) -> Vec<Entry> {
-        println!("Fetching entries for {}", input);
+    /// Loads the package or the bare wasm component at `path`, if it's one.
+    async fn open_plugin(&self, path: &Path) -> Result<Option<Plugin<P>>> {
+        // Anything else, like a folder without manifest, isn't a plugin
+        let package = if PluginPackage::is_package(path) {
+            Some(PluginPackage::open(path)?)
+        } else if path
+            .extension()
+            .map_or(false, |extension| extension == "wasm")
+        {
+            None
+        } else {
+            return Ok(None);
+        };
+
+        self.load_plugin(package, path.to_path_buf()).await
+    }
+
+    /// Loads the plugin at `path`, unless the signature policy refuses it.
+    async fn load_plugin(
+        &self,
+        package: Option<PluginPackage>,
+        path: PathBuf,
+    ) -> Result<Option<Plugin<P>>> {
+        let state = match self.instantiate_plugin(package, path).await? {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+
+        state.migrate_indexes(&self.persistence).await?;
+
+        Ok(Some(state))
+    }
 
+    /// Loads a plugin without touching its indexes, nothing if its signature is refused.
+    async fn instantiate_plugin(
+        &self,
+        package: Option<PluginPackage>,
+        path: PathBuf,
+    ) -> Result<Option<Plugin<P>>> {
+        let signature = match (&package, self.config.signatures.policy) {
+            (_, SignaturePolicy::Ignore) | (None, _) => SignatureStatus::Unsigned,
+            (Some(package), _) => self.config.signatures.verify(package)?,
+        };
+
+        if !matches!(signature, SignatureStatus::Trusted { .. }) {
+            match self.config.signatures.policy {
+                SignaturePolicy::Ignore => {}
+                SignaturePolicy::Warn => {
+                    warn!("{} isn't signed by a trusted key", path.to_string_lossy())
+                }
+                SignaturePolicy::Refuse => {
+                    warn!(
+                        "refusing {}, as it isn't signed by a trusted key",
+                        path.to_string_lossy()
+                    );
+                    return Ok(None);
+                }
+            }
+        }
+
+        let mut state = match package {
+            Some(package) => {
+                Plugin::load_package(package, self.persistence.clone(), self.grants.clone()).await?
+            }
+            None => {
+                Plugin::load_from_path(&path, self.persistence.clone(), self.grants.clone()).await?
+            }
+        };
+        state.signature = signature;
+        state.path = Some(path);
+
+        if let Some(root) = &self.config.kv_path {
+            state.instance.open_kv(root).await?;
+        }
+
+        Ok(Some(state))
+    }
+
+    /// Entries of every plugin, the ones failing to answer being left out.
+    pub async fn entries_for_input(&self, input: &str) -> Result<Vec<Entry>> {
         let mut entries = Vec::new();
 
-        for plugin in &self.plugins {
-            println!("fetching entries for plugin {}", plugin.name());
-            let mut plugin_entries = plugin.entries_for_input(input).await;
-            println!("the entries are {:?}", plugin_entries);
-            entries.append(plugin_entries.as_mut());
+        for plugin in self.plugins() {
+            match plugin.entries_for_input(input).await {
+                Ok(mut plugin_entries) => entries.append(&mut plugin_entries),
+                Err(error) => warn!("{:#}", error),
+            }
         }
 
-        entries
+        Ok(entries)
+    }
+
+    fn registry(&self) -> Result<Registry> {
+        Registry::new(
+            self.config
+                .registry
+                .as_deref()
+                .ok_or(anyhow!("no plugin registry configured"))?,
+        )
     }
 
-    pub fn plugins(&self) -> &Vec<Plugin<P>> {
-        return &self.plugins;
+    /// Plugins of the registry, along with the version installed of each.
+    pub async fn available_plugins(&self) -> Result<Vec<AvailablePlugin>> {
+        let index = self.registry()?.index().await?;
+
+        Ok(index
+            .plugins
+            .into_iter()
+            .map(|plugin| AvailablePlugin {
+                latest: plugin.latest_compatible().cloned(),
+                installed: self
+                    .plugin(plugin.identifier.clone())
+                    .map(|installed| installed.version()),
+                plugin,
+            })
+            .collect())
     }
 
-    pub fn plugin(&self, identifier: String) -> Option<&Plugin<P>> {
+    /// Installs the newest version of a plugin this host can run.
+    pub async fn install(&self, identifier: &str) -> Result<()> {
+        if self.plugin(String::from(identifier)).is_some() {
+            return Err(anyhow!("{} is already installed", identifier));
+        }
+
+        let registry = self.registry()?;
+        let version = latest_compatible(&registry, identifier).await?;
+
+        self.install_version(&registry, identifier, &version, None)
+            .await
+    }
+
+    /// Replaces a plugin by its newest version, returning whether there was one.
+    pub async fn update(&self, identifier: &str) -> Result<bool> {
+        let installed = self
+            .plugin(String::from(identifier))
+            .ok_or(anyhow!("{} isn't installed", identifier))?;
+
+        let registry = self.registry()?;
+        let version = latest_compatible(&registry, identifier).await?;
+
+        if version.version <= Version::parse(&installed.version())? {
+            return Ok(false);
+        }
+
+        self.install_version(&registry, identifier, &version, Some(installed))
+            .await?;
+
+        Ok(true)
+    }
+
+    /**
+        Removes a plugin and its package. Its indexes, grants and storage are kept, should it
+        come back.
+    */
+    pub async fn uninstall(&self, identifier: &str) -> Result<()> {
+        let installed = self
+            .plugin(String::from(identifier))
+            .ok_or(anyhow!("{} isn't installed", identifier))?;
+
+        if let Some(path) = &installed.path {
+            remove_plugin_file(path)?;
+        }
+
+        self.plugins
+            .write()
+            .unwrap()
+            .retain(|plugin| plugin.infos.identifier != identifier);
+
+        Ok(())
+    }
+
+    /**
+        Loads the downloaded package before writing it to the plugins folder, so that a plugin
+        failing to load, or refused, doesn't replace the installed one.
+    */
+    async fn install_version(
+        &self,
+        registry: &Registry,
+        identifier: &str,
+        version: &RegistryVersion,
+        replaced: Option<Arc<Plugin<P>>>,
+    ) -> Result<()> {
+        let (archive, package) = registry.download(version).await?;
+
+        if package.manifest.identifier != identifier {
+            return Err(anyhow!(
+                "the registry package of {} holds {}",
+                identifier,
+                package.manifest.identifier
+            ));
+        }
+
+        let path = self
+            .config
+            .plugins_path
+            .join(format!("{}{}", identifier, ARCHIVE_EXTENSION));
+
+        // Written aside first, so that an interrupted download isn't taken for a plugin
+        let partial = path.with_extension("gz.part");
+        fs::write(&partial, archive)?;
+
+        // Checked before replacing anything, the indexes being left to the installed version
+        let plugin = match self.instantiate_plugin(Some(package), path.clone()).await {
+            Ok(Some(plugin)) => plugin,
+            Ok(None) => {
+                fs::remove_file(&partial)?;
+                return Err(anyhow!(
+                    "{} isn't signed by a trusted key, so it can't be installed",
+                    identifier
+                ));
+            }
+            Err(error) => {
+                fs::remove_file(&partial)?;
+                return Err(error);
+            }
+        };
+
+        fs::rename(&partial, &path)?;
+
+        if let Some(previous) = replaced.as_ref().and_then(|plugin| plugin.path.as_ref()) {
+            if previous != &path {
+                remove_plugin_file(previous)?;
+            }
+        }
+
+        let plugin = Arc::new(plugin);
+        {
+            let mut plugins = self.plugins.write().unwrap();
+            plugins.retain(|plugin| plugin.infos.identifier != identifier);
+            plugins.push(plugin.clone());
+        }
+
+        // Only now that the new version replaced the old one, which can't read them anymore
+        plugin.migrate_indexes(&self.persistence).await
+    }
+
+    pub fn persistence(&self) -> &P {
+        &self.persistence
+    }
+
+    pub fn grants(&self) -> &Grants {
+        &self.grants
+    }
+
+    /**
+        Whether the plugin waits for `capability`, or its manifest declares it, the only
+        capabilities a frontend should offer to grant.
+    */
+    pub fn asked_for(&self, identifier: &str, capability: &Capability) -> bool {
+        let declared = self
+            .plugin(String::from(identifier))
+            .map_or(false, |plugin| {
+                plugin.capabilities().list().contains(capability)
+            });
+
+        declared || self.grants.is_pending(identifier, capability)
+    }
+
+    pub fn plugins(&self) -> Vec<Arc<Plugin<P>>> {
+        self.plugins.read().unwrap().clone()
+    }
+
+    pub fn plugin(&self, identifier: String) -> Option<Arc<Plugin<P>>> {
         self.plugins
+            .read()
+            .unwrap()
             .iter()
             .find(|plugin| plugin.infos.identifier.eq(&identifier))
+            .cloned()
     }
 }
+
+async fn latest_compatible(registry: &Registry, identifier: &str) -> Result<RegistryVersion> {
+    let index = registry.index().await?;
+    let plugin = index
+        .plugin(identifier)
+        .ok_or(anyhow!("{} isn't in the registry", identifier))?;
+
+    plugin.latest_compatible().cloned().ok_or(anyhow!(
+        "no version of {} targets a version of michel:plugin this host supports",
+        identifier
+    ))
+}
+
+fn remove_plugin_file(path: &Path) -> Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
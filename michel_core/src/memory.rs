@@ -0,0 +1,387 @@
+/*!
+    In-memory persistence, without milli nor LMDB. A document matches a query when it contains
+    every word of it, so it's meant for tests : plugin authors can run their plugin against a
+    `MichelInstance` backed by it, then inspect what got indexed from a clone.
+*/
+
+use crate::embedder::cosine_similarity;
+use crate::persistence::{
+    searchable_text, take_vector, timestamp_of, DateFilter, HybridQuery, Index, IndexMetadata,
+    IndexStats, MichelPersistence, PersistedDocument, SortOrder, IDENTIFIER_FIELD,
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use time::OffsetDateTime;
+
+/// Clones share the same indexes.
+#[derive(Clone, Default)]
+pub struct MemoryPersistence {
+    indexes: Arc<RwLock<HashMap<String, MemoryIndex>>>,
+}
+
+#[derive(Default)]
+struct MemoryIndex {
+    documents: Vec<PersistedDocument>,
+    vectors: HashMap<String, Vec<f32>>,
+    updated_at: Option<OffsetDateTime>,
+    metadata: Option<IndexMetadata>,
+}
+
+impl MemoryPersistence {
+    pub fn new() -> MemoryPersistence {
+        MemoryPersistence::default()
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<HashMap<String, MemoryIndex>>> {
+        self.indexes
+            .read()
+            .map_err(|_| anyhow!("memory persistence poisoned"))
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<HashMap<String, MemoryIndex>>> {
+        self.indexes
+            .write()
+            .map_err(|_| anyhow!("memory persistence poisoned"))
+    }
+
+    /// Names of the created indexes, sorted.
+    pub fn indexes(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Documents of an index in insertion order, empty if the index doesn't exist.
+    pub fn documents(&self, index: &str) -> Vec<PersistedDocument> {
+        self.read()
+            .unwrap()
+            .get(index)
+            .map(|index| index.documents.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn document(&self, index: &str, identifier: &str) -> Option<PersistedDocument> {
+        self.documents(index)
+            .into_iter()
+            .find(|document| document_identifier(document) == Some(identifier))
+    }
+
+    /// Embedding pushed along a document, if any.
+    pub fn vector(&self, index: &str, identifier: &str) -> Option<Vec<f32>> {
+        self.read()
+            .unwrap()
+            .get(index)
+            .and_then(|index| index.vectors.get(identifier).cloned())
+    }
+
+    pub fn assert_index_exists(&self, index: &str) {
+        let indexes = self.indexes();
+
+        assert!(
+            indexes.iter().any(|name| name == index),
+            "index {} was not created, existing indexes are {:?}",
+            index,
+            indexes
+        );
+    }
+
+    pub fn assert_document_count(&self, index: &str, count: usize) {
+        self.assert_index_exists(index);
+        let documents = self.documents(index);
+
+        assert_eq!(
+            documents.len(),
+            count,
+            "index {} holds {} documents instead of {}",
+            index,
+            documents.len(),
+            count
+        );
+    }
+
+    pub fn assert_indexed(&self, index: &str, identifier: &str) {
+        self.assert_index_exists(index);
+
+        assert!(
+            self.document(index, identifier).is_some(),
+            "document {} is not in index {}, indexed ones are {:?}",
+            identifier,
+            index,
+            self.documents(index)
+                .iter()
+                .filter_map(document_identifier)
+                .collect::<Vec<&str>>()
+        );
+    }
+
+    pub fn assert_field(
+        &self,
+        index: &str,
+        identifier: &str,
+        field: &str,
+        expected: serde_json::Value,
+    ) {
+        self.assert_indexed(index, identifier);
+        let document = self.document(index, identifier).unwrap();
+
+        assert_eq!(
+            document.get(field),
+            Some(&expected),
+            "unexpected value for field {} of document {} in index {}",
+            field,
+            identifier,
+            index
+        );
+    }
+}
+
+#[async_trait]
+impl MichelPersistence for MemoryPersistence {
+    async fn init_index(&self, name: String) -> Result<()> {
+        let mut indexes = self.write()?;
+
+        if indexes.contains_key(&name) {
+            return Err(anyhow!("index already exists"));
+        }
+
+        indexes.insert(name, MemoryIndex::default());
+
+        Ok(())
+    }
+
+    async fn list_indexes(&self) -> Result<Vec<String>> {
+        Ok(self.indexes())
+    }
+
+    async fn stats(&self, index: Index) -> Result<IndexStats> {
+        let indexes = self.read()?;
+        let memory_index = indexes.get(&index.name).ok_or(anyhow!("index not found"))?;
+
+        let mut field_distribution = BTreeMap::new();
+        for field in memory_index
+            .documents
+            .iter()
+            .flat_map(|document| document.keys())
+        {
+            *field_distribution.entry(field.clone()).or_default() += 1;
+        }
+
+        Ok(IndexStats {
+            name: index.name,
+            document_count: memory_index.documents.len() as u64,
+            field_distribution,
+            size_on_disk: 0,
+            last_update: memory_index.updated_at,
+        })
+    }
+
+    async fn metadata(&self, index: Index) -> Result<Option<IndexMetadata>> {
+        let indexes = self.read()?;
+        let memory_index = indexes.get(&index.name).ok_or(anyhow!("index not found"))?;
+
+        Ok(memory_index.metadata.clone())
+    }
+
+    async fn set_metadata(&self, index: Index, metadata: IndexMetadata) -> Result<()> {
+        let mut indexes = self.write()?;
+        let memory_index = indexes
+            .get_mut(&index.name)
+            .ok_or(anyhow!("index not found"))?;
+
+        memory_index.metadata = Some(metadata);
+
+        Ok(())
+    }
+
+    async fn clear_index(&self, index: Index) -> Result<()> {
+        let mut indexes = self.write()?;
+        let memory_index = indexes
+            .get_mut(&index.name)
+            .ok_or(anyhow!("index not found"))?;
+
+        memory_index.documents.clear();
+        memory_index.vectors.clear();
+        memory_index.updated_at = Some(OffsetDateTime::now_utc());
+
+        Ok(())
+    }
+
+    async fn add_document(&self, index: Index, document: PersistedDocument) -> Result<()> {
+        self.add_documents(index, vec![document]).await
+    }
+
+    async fn add_documents(&self, index: Index, documents: Vec<PersistedDocument>) -> Result<()> {
+        let mut indexes = self.write()?;
+        let memory_index = indexes
+            .get_mut(&index.name)
+            .ok_or(anyhow!("index not created"))?;
+
+        for mut document in documents {
+            let vector = take_vector(&mut document)?;
+            let identifier = document_identifier(&document)
+                .map(String::from)
+                .ok_or(anyhow!("document without identifier"))?;
+
+            if let Some(vector) = vector {
+                memory_index.vectors.insert(identifier.clone(), vector);
+            }
+
+            match memory_index
+                .documents
+                .iter_mut()
+                .find(|existing| document_identifier(existing) == Some(identifier.as_str()))
+            {
+                Some(existing) => *existing = document,
+                None => memory_index.documents.push(document),
+            }
+        }
+
+        memory_index.updated_at = Some(OffsetDateTime::now_utc());
+
+        Ok(())
+    }
+
+    async fn flush(&self, index: Index) -> Result<()> {
+        if !self.read()?.contains_key(&index.name) {
+            return Err(anyhow!("index not found"));
+        }
+
+        Ok(())
+    }
+
+    async fn search_document(
+        &self,
+        index: Index,
+        query: String,
+        limit: Option<u32>,
+    ) -> Result<Vec<PersistedDocument>> {
+        let indexes = self.read()?;
+        let memory_index = indexes.get(&index.name).ok_or(anyhow!("index not found"))?;
+
+        Ok(matching(memory_index, &query)
+            .into_iter()
+            .take(limit_of(limit))
+            .cloned()
+            .collect())
+    }
+
+    async fn search_document_by_date(
+        &self,
+        index: Index,
+        query: String,
+        filter: DateFilter,
+        limit: Option<u32>,
+    ) -> Result<Vec<PersistedDocument>> {
+        let indexes = self.read()?;
+        let memory_index = indexes.get(&index.name).ok_or(anyhow!("index not found"))?;
+
+        filter.validate()?;
+
+        let (after, before) = filter.timestamp_range();
+        let mut documents: Vec<&PersistedDocument> = matching(memory_index, &query)
+            .into_iter()
+            .filter(|document| {
+                let timestamp = timestamp_of(document, &filter.field);
+
+                after.map_or(true, |after| timestamp.map_or(false, |t| t >= after))
+                    && before.map_or(true, |before| timestamp.map_or(false, |t| t <= before))
+            })
+            .collect();
+
+        // Documents without the field come last, as with milli
+        if let Some(order) = &filter.order {
+            documents.sort_by_key(|document| {
+                let timestamp = timestamp_of(document, &filter.field);
+                (
+                    timestamp.is_none(),
+                    match order {
+                        SortOrder::Ascending => timestamp,
+                        SortOrder::Descending => timestamp.map(|timestamp| -timestamp),
+                    },
+                )
+            });
+        }
+
+        Ok(documents
+            .into_iter()
+            .take(limit_of(limit))
+            .cloned()
+            .collect())
+    }
+
+    async fn hybrid_search_document(
+        &self,
+        index: Index,
+        query: HybridQuery,
+        limit: Option<u32>,
+    ) -> Result<Vec<PersistedDocument>> {
+        let indexes = self.read()?;
+        let memory_index = indexes.get(&index.name).ok_or(anyhow!("index not found"))?;
+
+        let limit = limit_of(limit);
+        let candidates = query.candidates(limit);
+
+        let keyword_results: Vec<&str> = matching(memory_index, &query.query)
+            .into_iter()
+            .take(candidates)
+            .filter_map(document_identifier)
+            .collect();
+
+        let mut similarities: Vec<(&str, f32)> = match &query.vector {
+            Some(vector) => memory_index
+                .vectors
+                .iter()
+                .filter(|(_, document_vector)| document_vector.len() == vector.len())
+                .map(|(identifier, document_vector)| {
+                    (
+                        identifier.as_str(),
+                        cosine_similarity(vector, document_vector),
+                    )
+                })
+                .collect(),
+            None => vec![],
+        };
+        similarities.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        similarities.truncate(candidates);
+
+        Ok(query
+            .blend(keyword_results, similarities, limit)
+            .into_iter()
+            .filter_map(|identifier| {
+                memory_index
+                    .documents
+                    .iter()
+                    .find(|document| document_identifier(document) == Some(identifier))
+                    .cloned()
+            })
+            .collect())
+    }
+}
+
+/// Documents containing every word of the query, an empty query matching them all.
+fn matching<'a>(index: &'a MemoryIndex, query: &str) -> Vec<&'a PersistedDocument> {
+    let words: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect();
+
+    index
+        .documents
+        .iter()
+        .filter(|document| {
+            let text = searchable_text(document).to_lowercase();
+            words.iter().all(|word| text.contains(word.as_str()))
+        })
+        .collect()
+}
+
+fn document_identifier(document: &PersistedDocument) -> Option<&str> {
+    document.get(IDENTIFIER_FIELD).and_then(|id| id.as_str())
+}
+
+fn limit_of(limit: Option<u32>) -> usize {
+    limit.map_or(usize::MAX, |limit| limit as usize)
+}
@@ -0,0 +1,367 @@
+/*!
+    Plugin packages : a folder, or a `.tar.gz` archive of it, holding a `michel-plugin.toml`
+    manifest next to the wasm component, an optional icon and an optional signature.
+
+    The manifest declares what the plugin is and what it needs from the host, so that it can
+    be checked, and shown to the user, before any of its code runs.
+
+    ```toml
+    identifier = "files"
+    name = "Files"
+    description = "Search your files"
+    version = "1.2.0"
+    min-host-api = "0.1.0"
+    wasm = "files.wasm"
+    icon = "icon.png"
+
+    [capabilities]
+    fs = [{ path = "~/notes", write = true }]
+    network = ["api.example.com"]
+    clipboard = true
+    indexes = ["files"]
+    ```
+*/
+
+use anyhow::{anyhow, Result};
+use flate2::read::GzDecoder;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "michel-plugin.toml";
+pub const SIGNATURE_FILE: &str = "michel-plugin.sig";
+pub const ARCHIVE_EXTENSION: &str = ".tar.gz";
+const DEFAULT_WASM_FILE: &str = "plugin.wasm";
+
+/// Archives unpacking to more than this are refused, rather than filling the memory.
+pub const MAX_UNPACKED_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Latest version of the `michel:plugin` world, the one the host implements.
+pub const HOST_API_VERSION: Version = Version::new(0, 3, 0);
+
+/// Versions of the world the host links plugins against, older ones through adapters.
+pub const SUPPORTED_API_VERSIONS: [Version; 3] = [
+    Version::new(0, 1, 0),
+    Version::new(0, 2, 0),
+    HOST_API_VERSION,
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct FsCapability {
+    /// A leading `~` stands for the home folder of the user.
+    pub path: PathBuf,
+    /// Whether the plugin may change the folder, which it can only read otherwise.
+    #[serde(default)]
+    pub write: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Capabilities {
+    /// Folders the plugin reads, or writes.
+    #[serde(default)]
+    pub fs: Vec<FsCapability>,
+    /// Domains the plugin fetches from.
+    #[serde(default)]
+    pub network: Vec<String>,
+    #[serde(default)]
+    pub clipboard: bool,
+    /// Indexes the plugin creates.
+    #[serde(default)]
+    pub indexes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PluginManifest {
+    pub identifier: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub version: Version,
+    pub min_host_api: Version,
+    #[serde(default = "default_wasm_file")]
+    pub wasm: PathBuf,
+    pub icon: Option<PathBuf>,
+    #[serde(default)]
+    pub capabilities: Capabilities,
+}
+
+fn default_wasm_file() -> PathBuf {
+    PathBuf::from(DEFAULT_WASM_FILE)
+}
+
+impl PluginManifest {
+    pub fn parse(content: &str) -> Result<PluginManifest> {
+        let manifest: PluginManifest = toml::from_str(content)?;
+        manifest.validate()?;
+
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.identifier.is_empty()
+            || !self.identifier.chars().all(|character| {
+                character.is_ascii_lowercase()
+                    || character.is_ascii_digit()
+                    || matches!(character, '-' | '_' | '.')
+            })
+        {
+            return Err(anyhow!(
+                "invalid plugin identifier \"{}\", only lowercase letters, digits, '-', '_' and '.' are allowed",
+                self.identifier
+            ));
+        }
+
+        if !supports_host_api(&self.min_host_api) {
+            return Err(anyhow!(
+                "{} targets {}",
+                self.identifier,
+                unsupported_api_version(&self.min_host_api)
+            ));
+        }
+
+        for file in std::iter::once(&self.wasm).chain(&self.icon) {
+            if !is_inside_package(file) {
+                return Err(anyhow!(
+                    "{} points outside of the package",
+                    file.to_string_lossy()
+                ));
+            }
+        }
+
+        if self
+            .capabilities
+            .indexes
+            .iter()
+            .any(|index| index.is_empty())
+        {
+            return Err(anyhow!("empty index name in capabilities"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether a plugin built against `min` runs on this host, breaking changes bumping the major.
+pub fn supports_host_api(min: &Version) -> bool {
+    api_version_for(min).is_some()
+}
+
+/// Newest supported version of the world a plugin built against `min` can be linked to.
+pub fn api_version_for(min: &Version) -> Option<Version> {
+    SUPPORTED_API_VERSIONS
+        .iter()
+        .filter(|supported| {
+            // Before 1.0, minor versions break like major ones
+            let compatible = if min.major == 0 {
+                supported.major == 0 && min.minor == supported.minor
+            } else {
+                min.major == supported.major
+            };
+
+            compatible && min <= supported
+        })
+        .max()
+        .cloned()
+}
+
+/// Explains why no supported version of the world fits a plugin built against `version`.
+pub(crate) fn unsupported_api_version(version: &Version) -> String {
+    let supported = SUPPORTED_API_VERSIONS
+        .iter()
+        .map(|supported| format!("michel:plugin@{}", supported))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    format!(
+        "michel:plugin@{}, while this host supports {}",
+        version, supported
+    )
+}
+
+fn is_inside_package(path: &Path) -> bool {
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// A package read and checked, ready to be instantiated.
+pub struct PluginPackage {
+    pub manifest: PluginManifest,
+    pub wasm: Vec<u8>,
+    pub icon: Option<Vec<u8>>,
+    /// SHA-256 over the manifest, the wasm and the icon, which signatures are made of.
+    pub digest: [u8; 32],
+    /// Detached ed25519 signature of the digest, from `michel-plugin.sig`. Empty when the file
+    /// isn't hexadecimal, the package being untrusted rather than unreadable.
+    pub signature: Option<Vec<u8>>,
+}
+
+impl PluginPackage {
+    /// Whether `path` looks like a package rather than a bare wasm component.
+    pub fn is_package<T: AsRef<Path>>(path: T) -> bool {
+        let path = path.as_ref();
+
+        path.join(MANIFEST_FILE).is_file() || path.to_string_lossy().ends_with(ARCHIVE_EXTENSION)
+    }
+
+    /// Reads a package folder or archive, failing on an invalid manifest or a missing file.
+    pub fn open<T: AsRef<Path>>(path: T) -> Result<PluginPackage> {
+        let path = path.as_ref();
+
+        if path.is_dir() {
+            PluginPackage::from_folder(path)
+        } else if path.to_string_lossy().ends_with(ARCHIVE_EXTENSION) {
+            PluginPackage::from_archive(path)
+        } else {
+            Err(anyhow!("{} isn't a plugin package", path.to_string_lossy()))
+        }
+    }
+
+    fn from_folder(path: &Path) -> Result<PluginPackage> {
+        let source = fs::read_to_string(path.join(MANIFEST_FILE))?;
+        let manifest = PluginManifest::parse(&source)?;
+        let read = |file: &Path| {
+            fs::read(path.join(file))
+                .map_err(|error| anyhow!("can't read {} : {}", file.to_string_lossy(), error))
+        };
+
+        let wasm = read(&manifest.wasm)?;
+        let icon = match &manifest.icon {
+            Some(icon) => Some(read(icon)?),
+            None => None,
+        };
+        let signature = match path.join(SIGNATURE_FILE) {
+            signature if signature.is_file() => {
+                Some(String::from_utf8_lossy(&fs::read(signature)?).into_owned())
+            }
+            _ => None,
+        };
+
+        PluginPackage::new(&source, manifest, wasm, icon, signature)
+    }
+
+    fn from_archive(path: &Path) -> Result<PluginPackage> {
+        PluginPackage::from_archive_reader(File::open(path)?)
+    }
+
+    /// Reads a downloaded `.tar.gz` package, before it's written anywhere.
+    pub fn from_archive_bytes(archive: &[u8]) -> Result<PluginPackage> {
+        PluginPackage::from_archive_reader(archive)
+    }
+
+    /// Archives hold the files of the package at their root.
+    fn from_archive_reader<R: Read>(reader: R) -> Result<PluginPackage> {
+        let mut archive = tar::Archive::new(GzDecoder::new(reader));
+        let mut files: HashMap<PathBuf, Vec<u8>> = HashMap::new();
+        let mut unpacked = 0;
+        let too_large = || {
+            anyhow!(
+                "the archive unpacks to more than {} bytes",
+                MAX_UNPACKED_SIZE
+            )
+        };
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            // Refused from the header when it can be, the read being bounded anyway
+            if entry.size() > MAX_UNPACKED_SIZE - unpacked {
+                return Err(too_large());
+            }
+
+            let name = entry
+                .path()?
+                .components()
+                .filter(|component| !matches!(component, Component::CurDir))
+                .collect::<PathBuf>();
+            let mut content = Vec::new();
+            entry
+                .by_ref()
+                .take(MAX_UNPACKED_SIZE - unpacked + 1)
+                .read_to_end(&mut content)?;
+            unpacked += content.len() as u64;
+            if unpacked > MAX_UNPACKED_SIZE {
+                return Err(too_large());
+            }
+            files.insert(name, content);
+        }
+
+        let source = String::from_utf8(
+            files
+                .remove(Path::new(MANIFEST_FILE))
+                .ok_or(anyhow!("no {} in the archive", MANIFEST_FILE))?,
+        )?;
+        let manifest = PluginManifest::parse(&source)?;
+        let mut take = |file: &Path| {
+            files
+                .remove(file)
+                .ok_or(anyhow!("no {} in the archive", file.to_string_lossy()))
+        };
+
+        let wasm = take(&manifest.wasm)?;
+        let icon = match &manifest.icon {
+            Some(icon) => Some(take(icon)?),
+            None => None,
+        };
+        let signature = files
+            .remove(Path::new(SIGNATURE_FILE))
+            .map(|signature| String::from_utf8_lossy(&signature).into_owned());
+
+        PluginPackage::new(&source, manifest, wasm, icon, signature)
+    }
+
+    fn new(
+        source: &str,
+        manifest: PluginManifest,
+        wasm: Vec<u8>,
+        icon: Option<Vec<u8>>,
+        signature: Option<String>,
+    ) -> Result<PluginPackage> {
+        let digest = package_digest(source.as_bytes(), &wasm, icon.as_deref());
+        let signature = signature.map(|signature| decode_hex(signature.trim()).unwrap_or_default());
+
+        Ok(PluginPackage {
+            manifest,
+            wasm,
+            icon,
+            digest,
+            signature,
+        })
+    }
+}
+
+/// Each file is preceded by its length, so that bytes can't move from one file to the next.
+fn package_digest(manifest: &[u8], wasm: &[u8], icon: Option<&[u8]>) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+
+    for file in [Some(manifest), Some(wasm), icon].into_iter().flatten() {
+        hasher.update((file.len() as u64).to_le_bytes());
+        hasher.update(file);
+    }
+
+    hasher.finalize().into()
+}
+
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
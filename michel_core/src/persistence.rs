@@ -1,22 +1,52 @@
 use crate::plugins::wasi::types;
 use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime};
 
 pub type PersistedDocument = serde_json::Map<String, serde_json::Value>;
 
-impl From<types::Document> for PersistedDocument {
-    fn from(value: types::Document) -> Self {
+pub const IDENTIFIER_FIELD: &str = "id";
+pub const VECTOR_FIELD: &str = "_vector";
+/// Keys of the values the host persists for itself, which plugins can't use.
+pub const RESERVED_PREFIX: &str = "_michel_";
+const DATETIME_KEY: &str = "_michel_datetime";
+pub const TIMESTAMP_KEY: &str = "_michel_timestamp";
+const HYBRID_CANDIDATES_FACTOR: usize = 4;
+
+impl TryFrom<types::Document> for PersistedDocument {
+    type Error = anyhow::Error;
+
+    fn try_from(value: types::Document) -> Result<Self> {
         let mut map: PersistedDocument = PersistedDocument::new();
 
         map.insert(
-            "id".to_string(),
-            serde_json::Value::String(String::from(&value.identifier)),
+            IDENTIFIER_FIELD.to_string(),
+            serde_json::Value::String(value.identifier),
         );
 
+        if let Some(embedding) = value.embedding {
+            map.insert(VECTOR_FIELD.to_string(), vector_to_json(embedding)?);
+        }
+
         for field in value.fields {
-            map.insert(field.name, serde_json::Value::from(field.value));
+            if map.contains_key(&field.name)
+                || field.name == VECTOR_FIELD
+                || field.name.starts_with(RESERVED_PREFIX)
+            {
+                return Err(anyhow!("field {} is reserved or defined twice", field.name));
+            }
+
+            let value = serde_json::Value::try_from(field.value)
+                .map_err(|error| anyhow!("invalid value for field {} : {}", field.name, error))?;
+
+            map.insert(field.name, value);
         }
 
-        map
+        Ok(map)
     }
 }
 
@@ -25,25 +55,32 @@ impl TryFrom<&PersistedDocument> for types::Document {
 
     fn try_from(value: &PersistedDocument) -> Result<Self> {
         let identifier = value
-            .get("id")
-            .map(|value| value.as_str())
-            .ok_or(anyhow!("Invalid identifier value"))?
-            .ok_or(anyhow!("No identifier in document"))?;
-
-        let fields: Vec<types::Field> = value
-            .into_iter()
-            .filter(|(field_name, _)| field_name.clone() != "id")
-            .map(|(field_name, value)| (field_name, types::Value::try_from(value.clone())))
-            .filter(|(_field_name, value)| value.is_ok())
-            .map(|(field_name, value)| types::Field {
-                name: field_name.clone(),
-                value: value.unwrap(),
+            .get(IDENTIFIER_FIELD)
+            .ok_or(anyhow!("No identifier in document"))?
+            .as_str()
+            .ok_or(anyhow!("Invalid identifier value"))?;
+
+        let fields = value
+            .iter()
+            .filter(|(field_name, _)| {
+                field_name.as_str() != IDENTIFIER_FIELD && field_name.as_str() != VECTOR_FIELD
+            })
+            .map(|(field_name, value)| {
+                Ok(types::Field {
+                    name: field_name.clone(),
+                    value: types::Value::try_from(value.clone()).map_err(|error| {
+                        anyhow!("invalid value for field {} : {}", field_name, error)
+                    })?,
+                })
             })
-            .collect();
+            .collect::<Result<Vec<types::Field>>>()?;
+
+        let embedding = value.get(VECTOR_FIELD).map(vector_from_json).transpose()?;
 
         return Ok(types::Document {
             identifier: String::from(identifier),
             fields,
+            embedding,
         });
     }
 }
@@ -52,40 +89,657 @@ impl TryFrom<serde_json::Value> for types::Value {
     type Error = anyhow::Error;
 
     fn try_from(value: serde_json::Value) -> Result<Self> {
+        let mut nodes = Vec::new();
+        push_node(&mut nodes, None, value)?;
+
+        let root = nodes.remove(0);
+
+        Ok(match root.value {
+            types::NodeValue::Text(text) => types::Value::Text(text),
+            types::NodeValue::Datetime(date) => types::Value::Datetime(date),
+            types::NodeValue::Number(number) => types::Value::Number(number),
+            types::NodeValue::Float(float) => types::Value::Float(float),
+            types::NodeValue::Boolean(bool) => types::Value::Boolean(bool),
+            types::NodeValue::Null => types::Value::Null,
+            types::NodeValue::List(_) => types::Value::List(nodes),
+            types::NodeValue::Record(_) => types::Value::Record(nodes),
+        })
+    }
+}
+
+impl TryFrom<types::Value> for serde_json::Value {
+    type Error = anyhow::Error;
+
+    fn try_from(value: types::Value) -> Result<Self> {
         match value {
-            serde_json::Value::Bool(value) => Ok(types::Value::Boolean(value)),
-            serde_json::Value::Number(number) => Ok(types::Value::Number(u32::try_from(
-                number.as_u64().ok_or(anyhow!("value is not a number"))?,
-            )?)),
-            serde_json::Value::String(text) => Ok(types::Value::Text(text)),
-            _ => Err(anyhow!("value type not handled")),
+            types::Value::Text(text) => Ok(text.into()),
+            types::Value::Datetime(date) => datetime_to_json(date),
+            types::Value::Number(number) => Ok(number.into()),
+            types::Value::Float(float) => float_to_json(float),
+            types::Value::Boolean(bool) => Ok(bool.into()),
+            types::Value::Null => Ok(serde_json::Value::Null),
+            types::Value::List(nodes) => Ok(serde_json::Value::Array(
+                read_children(&mut nodes.into_iter(), None)?
+                    .into_iter()
+                    .map(|(_, value)| value)
+                    .collect(),
+            )),
+            types::Value::Record(nodes) => {
+                read_record(read_children(&mut nodes.into_iter(), None)?)
+            }
         }
     }
 }
 
-impl From<types::Value> for serde_json::Value {
-    fn from(value: types::Value) -> Self {
+/// Flattens a json value in pre-order, as expected by the `list` and `record` values.
+fn push_node(
+    nodes: &mut Vec<types::Node>,
+    key: Option<String>,
+    value: serde_json::Value,
+) -> Result<()> {
+    let node_value = match value {
+        serde_json::Value::Null => types::NodeValue::Null,
+        serde_json::Value::Bool(value) => types::NodeValue::Boolean(value),
+        serde_json::Value::Number(number) => {
+            if let Some(integer) = number.as_i64() {
+                types::NodeValue::Number(integer)
+            } else if number.is_f64() {
+                types::NodeValue::Float(number.as_f64().ok_or(anyhow!("invalid float"))?)
+            } else {
+                return Err(anyhow!("number {} does not fit in a s64", number));
+            }
+        }
+        serde_json::Value::String(text) => types::NodeValue::Text(text),
+        serde_json::Value::Array(items) => {
+            nodes.push(types::Node {
+                key,
+                value: types::NodeValue::List(u32::try_from(items.len())?),
+            });
+
+            for item in items {
+                push_node(nodes, None, item)?;
+            }
+
+            return Ok(());
+        }
+        serde_json::Value::Object(members) => match datetime_from_json(&members) {
+            Some(date) => types::NodeValue::Datetime(date),
+            None => {
+                nodes.push(types::Node {
+                    key,
+                    value: types::NodeValue::Record(u32::try_from(members.len())?),
+                });
+
+                for (member_key, member) in members {
+                    push_node(nodes, Some(member_key), member)?;
+                }
+
+                return Ok(());
+            }
+        },
+    };
+
+    nodes.push(types::Node {
+        key,
+        value: node_value,
+    });
+
+    Ok(())
+}
+
+/// Reads `count` sibling nodes along with their own children, or every remaining node
+/// if no count is given.
+fn read_children<I: Iterator<Item = types::Node>>(
+    nodes: &mut I,
+    count: Option<u32>,
+) -> Result<Vec<(Option<String>, serde_json::Value)>> {
+    let mut children = Vec::new();
+
+    loop {
+        if count.map_or(false, |count| children.len() as u32 >= count) {
+            break;
+        }
+
+        let node = match (nodes.next(), count) {
+            (Some(node), _) => node,
+            (None, None) => break,
+            (None, Some(_)) => return Err(anyhow!("missing nodes in nested value")),
+        };
+
+        let value = match node.value {
+            types::NodeValue::Text(text) => text.into(),
+            types::NodeValue::Datetime(date) => datetime_to_json(date)?,
+            types::NodeValue::Number(number) => number.into(),
+            types::NodeValue::Float(float) => float_to_json(float)?,
+            types::NodeValue::Boolean(bool) => bool.into(),
+            types::NodeValue::Null => serde_json::Value::Null,
+            types::NodeValue::List(count) => serde_json::Value::Array(
+                read_children(nodes, Some(count))?
+                    .into_iter()
+                    .map(|(_, value)| value)
+                    .collect(),
+            ),
+            types::NodeValue::Record(count) => read_record(read_children(nodes, Some(count))?)?,
+        };
+
+        children.push((node.key, value));
+    }
+
+    Ok(children)
+}
+
+fn read_record(members: Vec<(Option<String>, serde_json::Value)>) -> Result<serde_json::Value> {
+    let mut record = serde_json::Map::new();
+
+    for (key, value) in members {
+        let key = key.ok_or(anyhow!("record member without a key"))?;
+
+        if record.contains_key(&key) {
+            return Err(anyhow!("record member {} defined twice", key));
+        }
+
+        if key.starts_with(RESERVED_PREFIX) {
+            return Err(anyhow!("record member {} is reserved", key));
+        }
+
+        record.insert(key, value);
+    }
+
+    Ok(serde_json::Value::Object(record))
+}
+
+/**
+    Datetimes are persisted as `{"_michel_datetime": "<rfc 3339>", "_michel_timestamp": <unix
+    timestamp>}`, keeping the original value while the timestamp can be filtered and sorted on.
+    Plugins can't use keys with the reserved prefix, so none of their records reads as a datetime.
+*/
+fn datetime_to_json(date: String) -> Result<serde_json::Value> {
+    let timestamp = OffsetDateTime::parse(&date, &Rfc3339)
+        .map_err(|error| anyhow!("{} is not a RFC 3339 datetime : {}", date, error))?
+        .unix_timestamp();
+
+    let mut datetime = serde_json::Map::new();
+    datetime.insert(DATETIME_KEY.to_string(), date.into());
+    datetime.insert(TIMESTAMP_KEY.to_string(), timestamp.into());
+
+    Ok(serde_json::Value::Object(datetime))
+}
+
+fn datetime_from_json(value: &serde_json::Map<String, serde_json::Value>) -> Option<String> {
+    if value.len() != 2 || !value.contains_key(TIMESTAMP_KEY) {
+        return None;
+    }
+
+    value.get(DATETIME_KEY)?.as_str().map(String::from)
+}
+
+/**
+    Paths of the fields of a document holding a datetime, with the keys of nested records joined
+    by dots, e.g. `author.born`. Datetimes in lists, or under keys a path can't name, are left out.
+*/
+pub fn datetime_fields(document: &PersistedDocument) -> Vec<String> {
+    fn collect(
+        record: &serde_json::Map<String, serde_json::Value>,
+        path: &str,
+        fields: &mut Vec<String>,
+    ) {
+        for (key, value) in record {
+            let value = match value.as_object() {
+                Some(value) if check_field_segment(key).is_ok() => value,
+                _ => continue,
+            };
+
+            let field = match path.is_empty() {
+                true => key.clone(),
+                false => format!("{}.{}", path, key),
+            };
+
+            match datetime_from_json(value) {
+                Some(_) => fields.push(field),
+                None => collect(value, &field, fields),
+            }
+        }
+    }
+
+    let mut fields = Vec::new();
+    collect(document, "", &mut fields);
+
+    fields
+}
+
+fn check_field_segment(segment: &str) -> Result<()> {
+    if segment.is_empty() || segment.contains(['.', '"', '\\']) {
+        return Err(anyhow!(
+            "{:?} can't be part of the path of a datetime field",
+            segment
+        ));
+    }
+
+    if segment.starts_with(RESERVED_PREFIX) {
+        return Err(anyhow!("{} is reserved", segment));
+    }
+
+    Ok(())
+}
+
+/// Timestamp of the datetime at `field`, a path as given by `datetime_fields`.
+pub fn timestamp_of(document: &PersistedDocument, field: &str) -> Option<i64> {
+    let mut segments = field.split('.');
+    let mut value = document.get(segments.next()?)?;
+
+    for segment in segments {
+        value = value.get(segment)?;
+    }
+
+    value.get(TIMESTAMP_KEY)?.as_i64()
+}
+
+/// Path of the sortable and filterable timestamp of a datetime field.
+pub fn timestamp_field(field: &str) -> String {
+    format!("{}.{}", field, TIMESTAMP_KEY)
+}
+
+/// Every text and number of a document, nested ones included, as milli searches them all.
+pub fn searchable_text(document: &PersistedDocument) -> String {
+    fn collect(value: &serde_json::Value, words: &mut Vec<String>) {
         match value {
-            types::Value::Text(text) => text.clone().into(),
-            types::Value::Datetime(date) => date.clone().into(),
-            types::Value::Number(number) => number.clone().into(),
-            types::Value::Boolean(bool) => bool.into(),
+            serde_json::Value::String(text) => words.push(text.clone()),
+            serde_json::Value::Number(number) => words.push(number.to_string()),
+            serde_json::Value::Array(items) => items.iter().for_each(|item| collect(item, words)),
+            serde_json::Value::Object(members) => {
+                members.values().for_each(|member| collect(member, words))
+            }
+            _ => {}
         }
     }
+
+    let mut words = Vec::new();
+    document
+        .values()
+        .for_each(|value| collect(value, &mut words));
+
+    words.join(" ")
+}
+
+/// Removes the embedding vector from a document, as it's not meant to be indexed as is.
+pub fn take_vector(document: &mut PersistedDocument) -> Result<Option<Vec<f32>>> {
+    document
+        .remove(VECTOR_FIELD)
+        .map(|vector| vector_from_json(&vector))
+        .transpose()
+}
+
+/// Puts back a vector taken from a document, the way documents carry it when pushed.
+pub fn put_vector(document: &mut PersistedDocument, vector: Vec<f32>) -> Result<()> {
+    document.insert(String::from(VECTOR_FIELD), vector_to_json(vector)?);
+
+    Ok(())
+}
+
+/// Bytes of a vector as the backends store it, its components in little endian one after the other.
+pub fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector
+        .iter()
+        .flat_map(|component| component.to_le_bytes())
+        .collect()
+}
+
+/// Vector stored by [`encode_vector`], trailing bytes that can't make a component being dropped.
+pub fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+fn vector_to_json(vector: Vec<f32>) -> Result<serde_json::Value> {
+    vector
+        .into_iter()
+        .map(|component| float_to_json(f64::from(component)))
+        .collect::<Result<Vec<serde_json::Value>>>()
+        .map(serde_json::Value::Array)
+}
+
+fn vector_from_json(value: &serde_json::Value) -> Result<Vec<f32>> {
+    value
+        .as_array()
+        .ok_or(anyhow!("embedding is not a list"))?
+        .iter()
+        .map(|component| {
+            component
+                .as_f64()
+                .map(|component| component as f32)
+                .ok_or(anyhow!("embedding component is not a number"))
+        })
+        .collect()
+}
+
+fn float_to_json(float: f64) -> Result<serde_json::Value> {
+    serde_json::Number::from_f64(float)
+        .map(serde_json::Value::Number)
+        .ok_or(anyhow!("float {} can't be persisted", float))
 }
 
 pub struct Index {
     pub name: String,
 }
 
+/// Which version of which plugin wrote an index, to migrate it when the plugin gets upgraded.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IndexMetadata {
+    pub plugin: String,
+    pub plugin_version: String,
+    pub schema_version: u32,
+}
+
+impl From<IndexMetadata> for types::IndexMetadata {
+    fn from(value: IndexMetadata) -> Self {
+        types::IndexMetadata {
+            plugin_version: value.plugin_version,
+            schema_version: value.schema_version,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IndexStats {
+    pub name: String,
+    pub document_count: u64,
+    /// How many documents hold each top level field.
+    pub field_distribution: BTreeMap<String, u64>,
+    /// Bytes taken by the index, 0 when it only lives in memory.
+    pub size_on_disk: u64,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_update: Option<OffsetDateTime>,
+}
+
+impl TryFrom<IndexStats> for types::IndexStats {
+    type Error = anyhow::Error;
+
+    fn try_from(value: IndexStats) -> Result<Self> {
+        Ok(types::IndexStats {
+            name: value.name,
+            document_count: value.document_count,
+            field_distribution: value
+                .field_distribution
+                .into_iter()
+                .map(|(field, count)| types::FieldCount { field, count })
+                .collect(),
+            size_on_disk: value.size_on_disk,
+            last_update: value
+                .last_update
+                .map(|last_update| last_update.format(&Rfc3339))
+                .transpose()?,
+        })
+    }
+}
+
+/// Blends keyword and vector similarity, from a `semantic_ratio` of 0 (keyword only) to 1
+/// (vector only). Without a vector, the query is embedded by the persistence if it can.
+pub struct HybridQuery {
+    pub query: String,
+    pub vector: Option<Vec<f32>>,
+    pub semantic_ratio: f32,
+}
+
+impl HybridQuery {
+    /// How many results to fetch from each side before blending them.
+    pub fn candidates(&self, limit: usize) -> usize {
+        limit.saturating_mul(HYBRID_CANDIDATES_FACTOR)
+    }
+
+    /// Ranks documents from their keyword results, scored by rank from 1 for the first one
+    /// down to 0, and their vector similarities.
+    pub fn blend<K: Hash + Eq>(
+        &self,
+        keyword_results: Vec<K>,
+        similarities: Vec<(K, f32)>,
+        limit: usize,
+    ) -> Vec<K> {
+        let semantic_ratio = self.semantic_ratio.clamp(0.0, 1.0);
+        let keyword_count = keyword_results.len() as f32;
+
+        let mut scores: HashMap<K, f32> = HashMap::new();
+
+        for (rank, key) in keyword_results.into_iter().enumerate() {
+            *scores.entry(key).or_default() +=
+                (1.0 - semantic_ratio) * (1.0 - rank as f32 / keyword_count);
+        }
+
+        for (key, similarity) in similarities {
+            *scores.entry(key).or_default() += semantic_ratio * similarity;
+        }
+
+        let mut ranked: Vec<(K, f32)> = scores.into_iter().collect();
+        ranked.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        ranked.truncate(limit);
+
+        ranked.into_iter().map(|(key, _)| key).collect()
+    }
+}
+
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/// Restricts a search to the documents whose datetime field is in a range, bounds included,
+/// and optionally sorts them by this field.
+pub struct DateFilter {
+    pub field: String,
+    pub after: Option<OffsetDateTime>,
+    pub before: Option<OffsetDateTime>,
+    pub order: Option<SortOrder>,
+}
+
+impl DateFilter {
+    pub fn between(field: &str, after: OffsetDateTime, before: OffsetDateTime) -> DateFilter {
+        DateFilter {
+            field: String::from(field),
+            after: Some(after),
+            before: Some(before),
+            order: None,
+        }
+    }
+
+    pub fn since(field: &str, after: OffsetDateTime) -> DateFilter {
+        DateFilter {
+            field: String::from(field),
+            after: Some(after),
+            before: None,
+            order: None,
+        }
+    }
+
+    pub fn until(field: &str, before: OffsetDateTime) -> DateFilter {
+        DateFilter {
+            field: String::from(field),
+            after: None,
+            before: Some(before),
+            order: None,
+        }
+    }
+
+    /// Documents from the last `duration`, e.g. `DateFilter::within_last("modified", Duration::weeks(1))`.
+    pub fn within_last(field: &str, duration: Duration) -> DateFilter {
+        DateFilter::since(field, OffsetDateTime::now_utc() - duration)
+    }
+
+    /// Only sorts the documents by the field, without restricting them.
+    pub fn sorted_by(field: &str, order: SortOrder) -> DateFilter {
+        DateFilter {
+            field: String::from(field),
+            after: None,
+            before: None,
+            order: Some(order),
+        }
+    }
+
+    pub fn sorted(self, order: SortOrder) -> DateFilter {
+        DateFilter {
+            order: Some(order),
+            ..self
+        }
+    }
+
+    pub fn timestamp_field(&self) -> String {
+        timestamp_field(&self.field)
+    }
+
+    /**
+        Checks the field is a path backends can filter on : keys joined by dots, none of them
+        empty, reserved, or holding a quote or a backslash.
+    */
+    pub fn validate(&self) -> Result<()> {
+        self.field
+            .split('.')
+            .try_for_each(check_field_segment)
+            .map_err(|error| anyhow!("invalid date filter field {:?} : {}", self.field, error))
+    }
+
+    pub fn timestamp_range(&self) -> (Option<i64>, Option<i64>) {
+        (
+            self.after.map(|after| after.unix_timestamp()),
+            self.before.map(|before| before.unix_timestamp()),
+        )
+    }
+}
+
+impl TryFrom<types::DateFilter> for DateFilter {
+    type Error = anyhow::Error;
+
+    fn try_from(value: types::DateFilter) -> Result<Self> {
+        let parse = |date: Option<String>| -> Result<Option<OffsetDateTime>> {
+            date.map(|date| {
+                OffsetDateTime::parse(&date, &Rfc3339)
+                    .map_err(|error| anyhow!("{} is not a RFC 3339 datetime : {}", date, error))
+            })
+            .transpose()
+        };
+
+        let filter = DateFilter {
+            field: value.field,
+            after: parse(value.after)?,
+            before: parse(value.before)?,
+            order: value.order.map(|order| match order {
+                types::SortOrder::Ascending => SortOrder::Ascending,
+                types::SortOrder::Descending => SortOrder::Descending,
+            }),
+        };
+        filter.validate()?;
+
+        Ok(filter)
+    }
+}
+
+/**
+    Backends take `&self` everywhere : they are shared between every plugin without a global
+    lock, and are expected to handle concurrent reads and writes themselves.
+*/
+#[async_trait]
 pub trait MichelPersistence: Send + Sync {
-    fn init_index(&mut self, name: String) -> Result<()>;
-    fn add_document(&self, index: Index, document: PersistedDocument) -> Result<()>;
-    fn add_documents(&self, index: Index, documents: Vec<PersistedDocument>) -> Result<()>;
-    fn search_document(
+    async fn init_index(&self, name: String) -> Result<()>;
+    /// Names of the created indexes, sorted.
+    async fn list_indexes(&self) -> Result<Vec<String>>;
+    async fn stats(&self, index: Index) -> Result<IndexStats>;
+    /// `None` until metadata is set, for indexes created before versioning.
+    async fn metadata(&self, index: Index) -> Result<Option<IndexMetadata>>;
+    async fn set_metadata(&self, index: Index, metadata: IndexMetadata) -> Result<()>;
+    /// Removes every document of the index, keeping its metadata.
+    async fn clear_index(&self, index: Index) -> Result<()>;
+    async fn add_document(&self, index: Index, document: PersistedDocument) -> Result<()>;
+    /// Added documents may only become searchable once the index is flushed.
+    async fn add_documents(&self, index: Index, documents: Vec<PersistedDocument>) -> Result<()>;
+    /// Waits for every document added so far to be searchable, failing if some of them
+    /// couldn't be written.
+    async fn flush(&self, index: Index) -> Result<()>;
+    async fn search_document(
         &self,
         index: Index,
         query: String,
         limit: Option<u32>,
     ) -> Result<Vec<PersistedDocument>>;
+    async fn search_document_by_date(
+        &self,
+        index: Index,
+        query: String,
+        filter: DateFilter,
+        limit: Option<u32>,
+    ) -> Result<Vec<PersistedDocument>>;
+    async fn hybrid_search_document(
+        &self,
+        index: Index,
+        query: HybridQuery,
+        limit: Option<u32>,
+    ) -> Result<Vec<PersistedDocument>>;
+}
+
+/// Lets the persistence backend be picked at runtime.
+#[async_trait]
+impl MichelPersistence for Box<dyn MichelPersistence> {
+    async fn init_index(&self, name: String) -> Result<()> {
+        (**self).init_index(name).await
+    }
+
+    async fn list_indexes(&self) -> Result<Vec<String>> {
+        (**self).list_indexes().await
+    }
+
+    async fn stats(&self, index: Index) -> Result<IndexStats> {
+        (**self).stats(index).await
+    }
+
+    async fn metadata(&self, index: Index) -> Result<Option<IndexMetadata>> {
+        (**self).metadata(index).await
+    }
+
+    async fn set_metadata(&self, index: Index, metadata: IndexMetadata) -> Result<()> {
+        (**self).set_metadata(index, metadata).await
+    }
+
+    async fn clear_index(&self, index: Index) -> Result<()> {
+        (**self).clear_index(index).await
+    }
+
+    async fn add_document(&self, index: Index, document: PersistedDocument) -> Result<()> {
+        (**self).add_document(index, document).await
+    }
+
+    async fn add_documents(&self, index: Index, documents: Vec<PersistedDocument>) -> Result<()> {
+        (**self).add_documents(index, documents).await
+    }
+
+    async fn flush(&self, index: Index) -> Result<()> {
+        (**self).flush(index).await
+    }
+
+    async fn search_document(
+        &self,
+        index: Index,
+        query: String,
+        limit: Option<u32>,
+    ) -> Result<Vec<PersistedDocument>> {
+        (**self).search_document(index, query, limit).await
+    }
+
+    async fn search_document_by_date(
+        &self,
+        index: Index,
+        query: String,
+        filter: DateFilter,
+        limit: Option<u32>,
+    ) -> Result<Vec<PersistedDocument>> {
+        (**self)
+            .search_document_by_date(index, query, filter, limit)
+            .await
+    }
+
+    async fn hybrid_search_document(
+        &self,
+        index: Index,
+        query: HybridQuery,
+        limit: Option<u32>,
+    ) -> Result<Vec<PersistedDocument>> {
+        (**self).hybrid_search_document(index, query, limit).await
+    }
 }
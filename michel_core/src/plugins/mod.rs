@@ -1 +1,126 @@
+/*!
+    Plugins target a version of the `michel:plugin` world, which WIT lives in
+    `wit/michel-plugin@<version>`. The host implements the latest one in [`wasi`], and links
+    plugins targeting an older one through an adapter onto it, in a module of that version.
+
+    Once released, a version is frozen : changing the world means adding a new version, and
+    an adapter from the previous one.
+*/
+
+pub mod v0_1;
+pub mod v0_2;
 pub mod wasi;
+
+use crate::package::HOST_API_VERSION;
+use anyhow::{anyhow, Result};
+use semver::Version;
+use wasmparser::{Parser, Payload};
+
+/// Custom section in which a bare component declares the version of the world it targets.
+pub const API_VERSION_SECTION: &str = "michel-plugin-api";
+
+/// Version targeted by the components built before versions were declared.
+const UNDECLARED_API_VERSION: Version = Version::new(0, 1, 0);
+
+/// Interfaces the host provides to plugins, in each version of the world.
+const WORLD_IMPORTS: [(Version, &[&str]); 3] = [
+    (Version::new(0, 1, 0), &["michel-api"]),
+    (v0_2::API_VERSION, &["michel-api"]),
+    (HOST_API_VERSION, &["michel-api", "kv"]),
+];
+
+/// Interface every version of the world expects plugins to export.
+const WORLD_EXPORT: &str = "plugin-api";
+
+/// Version of the world a bare component targets, from its custom section.
+pub fn declared_api_version(wasm: &[u8]) -> Result<Version> {
+    for payload in Parser::new(0).parse_all(wasm) {
+        // Components in the text format can't declare a version
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(_) => break,
+        };
+
+        if let Payload::CustomSection(section) = payload {
+            if section.name() == API_VERSION_SECTION {
+                let version = std::str::from_utf8(section.data())?;
+
+                return Version::parse(version.trim()).map_err(|error| {
+                    anyhow!("invalid {} section : {}", API_VERSION_SECTION, error)
+                });
+            }
+        }
+    }
+
+    Ok(UNDECLARED_API_VERSION)
+}
+
+/**
+    Checks that a component imports and exports the interfaces of the world it's linked
+    against, so that a plugin built against another world is explained rather than failing
+    to link. Components in the text format aren't checked.
+*/
+pub(crate) fn check_world(wasm: &[u8], version: &Version) -> Result<()> {
+    let provided = WORLD_IMPORTS
+        .iter()
+        .find(|(world, _)| world == version)
+        .map(|(_, imports)| *imports)
+        .unwrap_or_default();
+    let known = |name: &str| {
+        WORLD_IMPORTS
+            .iter()
+            .any(|(_, imports)| imports.contains(&name))
+    };
+
+    let (mut imports, mut exports) = (Vec::new(), Vec::new());
+    // Nested modules and components have their own imports and exports
+    let mut depth = 0;
+
+    for payload in Parser::new(0).parse_all(wasm) {
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(_) => return Ok(()),
+        };
+
+        match payload {
+            Payload::ModuleSection { .. } | Payload::ComponentSection { .. } => depth += 1,
+            Payload::End(_) if depth > 0 => depth -= 1,
+            Payload::ComponentImportSection(section) if depth == 0 => {
+                for import in section {
+                    imports.push(String::from(import?.name));
+                }
+            }
+            Payload::ComponentExportSection(section) if depth == 0 => {
+                for export in section {
+                    exports.push(String::from(export?.name));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(import) = imports
+        .iter()
+        .find(|import| known(import) && !provided.contains(&import.as_str()))
+    {
+        return Err(anyhow!(
+            "the plugin imports {}, which michel:plugin@{} doesn't provide",
+            import,
+            version
+        ));
+    }
+
+    if !exports.iter().any(|export| export == WORLD_EXPORT) {
+        return Err(anyhow!(
+            "the plugin exports {}, instead of the {} of michel:plugin@{}",
+            match exports.is_empty() {
+                true => String::from("nothing"),
+                false => exports.join(", "),
+            },
+            WORLD_EXPORT,
+            version
+        ));
+    }
+
+    Ok(())
+}
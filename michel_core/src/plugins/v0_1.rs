@@ -0,0 +1,181 @@
+/*!
+    Adapter for the plugins targeting `michel:plugin@0.1.0`, the world of the first plugins :
+    calls they make are forwarded to the latest version, and values going through are
+    converted from one to the other.
+
+    The world only knows text, datetime, unsigned number and boolean values : fields holding
+    anything else are left out of the documents the plugin finds. Errors of the host can't be
+    returned to such plugins either, so they fail the call instead, the plugin being started
+    again afterwards.
+*/
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use wasmtime::component::bindgen;
+
+bindgen!({
+    world: "plugong",
+    path: "../wit/michel-plugin@0.1.0",
+    async: true
+});
+
+use crate::persistence::MichelPersistence;
+use crate::plugins::wasi::{self, types as latest, MichelApiForPlugins};
+use wasi::michel_api::MichelApi as LatestMichelApi;
+
+impl<P: MichelPersistence> types::Types for MichelApiForPlugins<P> {}
+
+#[async_trait]
+impl<P: MichelPersistence> michel_api::MichelApi for MichelApiForPlugins<P> {
+    /// Removed in 0.2.0.
+    async fn hi(&mut self, _name: String) -> Result<String> {
+        Ok(String::from("yo"))
+    }
+
+    /// Removed in 0.2.0, entries being what `for-input` returns.
+    async fn send_entry_for_input(
+        &mut self,
+        _input: String,
+        _entries: Vec<types::Entry>,
+    ) -> Result<()> {
+        Err(anyhow!(
+            "send-entry-for-input isn't supported, return the entries from for-input"
+        ))
+    }
+
+    async fn new_document_for_index(
+        &mut self,
+        index: String,
+        document: types::Document,
+    ) -> Result<()> {
+        LatestMichelApi::new_document_for_index(self, index, document.into())
+            .await?
+            .map_err(|error| self.trap(error))
+    }
+
+    async fn new_documents_for_index(
+        &mut self,
+        index: String,
+        documents: Vec<types::Document>,
+    ) -> Result<()> {
+        LatestMichelApi::new_documents_for_index(
+            self,
+            index,
+            documents.into_iter().map(Into::into).collect(),
+        )
+        .await?
+        .map_err(|error| self.trap(error))
+    }
+
+    async fn search_in_index(
+        &mut self,
+        index: String,
+        query: String,
+    ) -> Result<Vec<types::Document>> {
+        Ok(LatestMichelApi::search_in_index(self, index, query)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    async fn init_index(&mut self, index: String) -> Result<()> {
+        LatestMichelApi::init_index(self, index)
+            .await?
+            .map_err(|error| self.trap(error))
+    }
+}
+
+impl From<types::Value> for latest::Value {
+    fn from(value: types::Value) -> Self {
+        match value {
+            types::Value::Text(text) => latest::Value::Text(text),
+            types::Value::Datetime(date) => latest::Value::Datetime(date),
+            types::Value::Number(number) => latest::Value::Number(i64::from(number)),
+            types::Value::Boolean(boolean) => latest::Value::Boolean(boolean),
+        }
+    }
+}
+
+/// `None` for the values the world can't represent.
+fn baseline_value(value: latest::Value) -> Option<types::Value> {
+    match value {
+        latest::Value::Text(text) => Some(types::Value::Text(text)),
+        latest::Value::Datetime(date) => Some(types::Value::Datetime(date)),
+        latest::Value::Number(number) => u32::try_from(number).ok().map(types::Value::Number),
+        latest::Value::Boolean(boolean) => Some(types::Value::Boolean(boolean)),
+        latest::Value::Float(_)
+        | latest::Value::Null
+        | latest::Value::List(_)
+        | latest::Value::Record(_) => None,
+    }
+}
+
+impl From<types::Document> for latest::Document {
+    fn from(value: types::Document) -> Self {
+        latest::Document {
+            identifier: value.identifier,
+            fields: value
+                .fields
+                .into_iter()
+                .map(|field| latest::Field {
+                    name: field.name,
+                    value: field.value.into(),
+                })
+                .collect(),
+            embedding: None,
+        }
+    }
+}
+
+impl From<latest::Document> for types::Document {
+    fn from(value: latest::Document) -> Self {
+        types::Document {
+            identifier: value.identifier,
+            fields: value
+                .fields
+                .into_iter()
+                .filter_map(|field| {
+                    Some(types::Field {
+                        name: field.name,
+                        value: baseline_value(field.value)?,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<types::Error> for latest::Error {
+    fn from(value: types::Error) -> Self {
+        match value {
+            types::Error::InvalidValue(message) => latest::Error::InvalidValue(message),
+        }
+    }
+}
+
+/// The documents of such plugins have no versions, so they all are version 0.
+impl From<types::PluginInfo> for latest::PluginInfo {
+    fn from(value: types::PluginInfo) -> Self {
+        latest::PluginInfo {
+            identifier: value.identifier,
+            name: value.name,
+            description: value.description,
+            version: value.version,
+            schema_version: 0,
+            icon: value.icon,
+            url: value.url,
+        }
+    }
+}
+
+impl From<types::Entry> for latest::Entry {
+    fn from(value: types::Entry) -> Self {
+        latest::Entry {
+            title: value.title,
+            description: value.description,
+            preview: value.preview,
+            actions: Vec::new(),
+        }
+    }
+}
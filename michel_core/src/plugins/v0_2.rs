@@ -0,0 +1,360 @@
+/*!
+    Adapter for the plugins targeting `michel:plugin@0.2.0`, the world before plugins could
+    fetch from the network or keep values : calls they make are forwarded to the latest version,
+    which only adds to it, so values convert from one to the other without losing anything.
+*/
+
+use anyhow::Result;
+use async_trait::async_trait;
+use semver::Version;
+use wasmtime::component::bindgen;
+
+bindgen!({
+    world: "plugong",
+    path: "../wit/michel-plugin@0.2.0",
+    async: true
+});
+
+use crate::persistence::MichelPersistence;
+use crate::plugins::wasi::{self, types as latest, MichelApiForPlugins};
+use wasi::michel_api::MichelApi as LatestMichelApi;
+
+/// Version of the world the plugins linked through this module target.
+pub const API_VERSION: Version = Version::new(0, 2, 0);
+
+impl<P: MichelPersistence> types::Types for MichelApiForPlugins<P> {}
+
+#[async_trait]
+impl<P: MichelPersistence> michel_api::MichelApi for MichelApiForPlugins<P> {
+    async fn new_document_for_index(
+        &mut self,
+        index: String,
+        document: types::Document,
+    ) -> Result<Result<(), types::Error>> {
+        Ok(
+            LatestMichelApi::new_document_for_index(self, index, document.into())
+                .await?
+                .map_err(Into::into),
+        )
+    }
+
+    async fn new_documents_for_index(
+        &mut self,
+        index: String,
+        documents: Vec<types::Document>,
+    ) -> Result<Result<(), types::Error>> {
+        Ok(LatestMichelApi::new_documents_for_index(
+            self,
+            index,
+            documents.into_iter().map(Into::into).collect(),
+        )
+        .await?
+        .map_err(Into::into))
+    }
+
+    async fn search_in_index(
+        &mut self,
+        index: String,
+        query: String,
+    ) -> Result<Vec<types::Document>> {
+        Ok(LatestMichelApi::search_in_index(self, index, query)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    async fn search_in_index_by_date(
+        &mut self,
+        index: String,
+        query: String,
+        filter: types::DateFilter,
+    ) -> Result<Result<Vec<types::Document>, types::Error>> {
+        Ok(
+            LatestMichelApi::search_in_index_by_date(self, index, query, filter.into())
+                .await?
+                .map(|documents| documents.into_iter().map(Into::into).collect())
+                .map_err(Into::into),
+        )
+    }
+
+    async fn hybrid_search_in_index(
+        &mut self,
+        index: String,
+        query: String,
+        embedding: Option<Vec<f32>>,
+        semantic_ratio: f32,
+    ) -> Result<Vec<types::Document>> {
+        Ok(
+            LatestMichelApi::hybrid_search_in_index(self, index, query, embedding, semantic_ratio)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        )
+    }
+
+    /// The world can't return the refusal of the index, so it fails the call instead.
+    async fn init_index(&mut self, index: String) -> Result<()> {
+        LatestMichelApi::init_index(self, index)
+            .await?
+            .map_err(|error| self.trap(error))
+    }
+
+    async fn flush_index(&mut self, index: String) -> Result<Result<(), types::Error>> {
+        Ok(LatestMichelApi::flush_index(self, index)
+            .await?
+            .map_err(Into::into))
+    }
+
+    async fn list_indexes(&mut self) -> Result<Vec<String>> {
+        LatestMichelApi::list_indexes(self).await
+    }
+
+    async fn index_stats(
+        &mut self,
+        index: String,
+    ) -> Result<Result<types::IndexStats, types::Error>> {
+        Ok(LatestMichelApi::index_stats(self, index)
+            .await?
+            .map(Into::into)
+            .map_err(Into::into))
+    }
+}
+
+impl From<types::NodeValue> for latest::NodeValue {
+    fn from(value: types::NodeValue) -> Self {
+        match value {
+            types::NodeValue::Text(text) => latest::NodeValue::Text(text),
+            types::NodeValue::Datetime(date) => latest::NodeValue::Datetime(date),
+            types::NodeValue::Number(number) => latest::NodeValue::Number(number),
+            types::NodeValue::Float(float) => latest::NodeValue::Float(float),
+            types::NodeValue::Boolean(boolean) => latest::NodeValue::Boolean(boolean),
+            types::NodeValue::Null => latest::NodeValue::Null,
+            types::NodeValue::List(count) => latest::NodeValue::List(count),
+            types::NodeValue::Record(count) => latest::NodeValue::Record(count),
+        }
+    }
+}
+
+impl From<latest::NodeValue> for types::NodeValue {
+    fn from(value: latest::NodeValue) -> Self {
+        match value {
+            latest::NodeValue::Text(text) => types::NodeValue::Text(text),
+            latest::NodeValue::Datetime(date) => types::NodeValue::Datetime(date),
+            latest::NodeValue::Number(number) => types::NodeValue::Number(number),
+            latest::NodeValue::Float(float) => types::NodeValue::Float(float),
+            latest::NodeValue::Boolean(boolean) => types::NodeValue::Boolean(boolean),
+            latest::NodeValue::Null => types::NodeValue::Null,
+            latest::NodeValue::List(count) => types::NodeValue::List(count),
+            latest::NodeValue::Record(count) => types::NodeValue::Record(count),
+        }
+    }
+}
+
+impl From<types::Node> for latest::Node {
+    fn from(value: types::Node) -> Self {
+        latest::Node {
+            key: value.key,
+            value: value.value.into(),
+        }
+    }
+}
+
+impl From<latest::Node> for types::Node {
+    fn from(value: latest::Node) -> Self {
+        types::Node {
+            key: value.key,
+            value: value.value.into(),
+        }
+    }
+}
+
+impl From<types::Value> for latest::Value {
+    fn from(value: types::Value) -> Self {
+        let nodes = |nodes: Vec<types::Node>| -> Vec<latest::Node> {
+            nodes.into_iter().map(Into::into).collect()
+        };
+
+        match value {
+            types::Value::Text(text) => latest::Value::Text(text),
+            types::Value::Datetime(date) => latest::Value::Datetime(date),
+            types::Value::Number(number) => latest::Value::Number(number),
+            types::Value::Float(float) => latest::Value::Float(float),
+            types::Value::Boolean(boolean) => latest::Value::Boolean(boolean),
+            types::Value::Null => latest::Value::Null,
+            types::Value::List(list) => latest::Value::List(nodes(list)),
+            types::Value::Record(record) => latest::Value::Record(nodes(record)),
+        }
+    }
+}
+
+impl From<latest::Value> for types::Value {
+    fn from(value: latest::Value) -> Self {
+        let nodes = |nodes: Vec<latest::Node>| -> Vec<types::Node> {
+            nodes.into_iter().map(Into::into).collect()
+        };
+
+        match value {
+            latest::Value::Text(text) => types::Value::Text(text),
+            latest::Value::Datetime(date) => types::Value::Datetime(date),
+            latest::Value::Number(number) => types::Value::Number(number),
+            latest::Value::Float(float) => types::Value::Float(float),
+            latest::Value::Boolean(boolean) => types::Value::Boolean(boolean),
+            latest::Value::Null => types::Value::Null,
+            latest::Value::List(list) => types::Value::List(nodes(list)),
+            latest::Value::Record(record) => types::Value::Record(nodes(record)),
+        }
+    }
+}
+
+impl From<types::Document> for latest::Document {
+    fn from(value: types::Document) -> Self {
+        latest::Document {
+            identifier: value.identifier,
+            fields: value
+                .fields
+                .into_iter()
+                .map(|field| latest::Field {
+                    name: field.name,
+                    value: field.value.into(),
+                })
+                .collect(),
+            embedding: value.embedding,
+        }
+    }
+}
+
+impl From<latest::Document> for types::Document {
+    fn from(value: latest::Document) -> Self {
+        types::Document {
+            identifier: value.identifier,
+            fields: value
+                .fields
+                .into_iter()
+                .map(|field| types::Field {
+                    name: field.name,
+                    value: field.value.into(),
+                })
+                .collect(),
+            embedding: value.embedding,
+        }
+    }
+}
+
+impl From<types::DateFilter> for latest::DateFilter {
+    fn from(value: types::DateFilter) -> Self {
+        latest::DateFilter {
+            field: value.field,
+            after: value.after,
+            before: value.before,
+            order: value.order.map(|order| match order {
+                types::SortOrder::Ascending => latest::SortOrder::Ascending,
+                types::SortOrder::Descending => latest::SortOrder::Descending,
+            }),
+        }
+    }
+}
+
+impl From<latest::IndexStats> for types::IndexStats {
+    fn from(value: latest::IndexStats) -> Self {
+        types::IndexStats {
+            name: value.name,
+            document_count: value.document_count,
+            field_distribution: value
+                .field_distribution
+                .into_iter()
+                .map(|count| types::FieldCount {
+                    field: count.field,
+                    count: count.count,
+                })
+                .collect(),
+            size_on_disk: value.size_on_disk,
+            last_update: value.last_update,
+        }
+    }
+}
+
+impl From<types::Error> for latest::Error {
+    fn from(value: types::Error) -> Self {
+        match value {
+            types::Error::InvalidValue(message) => latest::Error::InvalidValue(message),
+        }
+    }
+}
+
+impl From<latest::Error> for types::Error {
+    fn from(value: latest::Error) -> Self {
+        match value {
+            latest::Error::InvalidValue(message) => types::Error::InvalidValue(message),
+        }
+    }
+}
+
+impl From<types::PluginInfo> for latest::PluginInfo {
+    fn from(value: types::PluginInfo) -> Self {
+        latest::PluginInfo {
+            identifier: value.identifier,
+            name: value.name,
+            description: value.description,
+            version: value.version,
+            schema_version: value.schema_version,
+            icon: value.icon,
+            url: value.url,
+        }
+    }
+}
+
+impl From<latest::IndexMetadata> for types::IndexMetadata {
+    fn from(value: latest::IndexMetadata) -> Self {
+        types::IndexMetadata {
+            plugin_version: value.plugin_version,
+            schema_version: value.schema_version,
+        }
+    }
+}
+
+impl From<types::Migration> for latest::Migration {
+    fn from(value: types::Migration) -> Self {
+        match value {
+            types::Migration::Keep => latest::Migration::Keep,
+            types::Migration::Rebuild => latest::Migration::Rebuild,
+        }
+    }
+}
+
+impl From<types::Entry> for latest::Entry {
+    fn from(value: types::Entry) -> Self {
+        latest::Entry {
+            title: value.title,
+            description: value.description,
+            preview: value.preview,
+            actions: value
+                .actions
+                .into_iter()
+                .map(|action| latest::Action {
+                    identifier: action.identifier,
+                    label: action.label,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<latest::Entry> for types::Entry {
+    fn from(value: latest::Entry) -> Self {
+        types::Entry {
+            title: value.title,
+            description: value.description,
+            preview: value.preview,
+            actions: value
+                .actions
+                .into_iter()
+                .map(|action| types::Action {
+                    identifier: action.identifier,
+                    label: action.label,
+                })
+                .collect(),
+        }
+    }
+}
@@ -1,72 +1,270 @@
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use host::WasiCtx;
+use semver::Version;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, MutexGuard};
 use wasi_cap_std_sync::dir::Dir;
 use wasi_cap_std_sync::WasiCtxBuilder;
+use wasi_common::dir::{DirPerms, FilePerms};
 use wasmtime::component::bindgen;
 use wasmtime::component::{Component, Linker};
-use wasmtime::{AsContextMut, Config, Engine, Store};
+use wasmtime::{Config, Engine, Store};
 
 bindgen!({
     world: "plugong",
-    path: "../wit",
+    path: "../wit/michel-plugin@0.3.0",
     async: true
 });
 
-use crate::persistence::{Index, PersistedDocument};
+use crate::grants::{expand_home, Capability, Decision, Grants};
+use crate::http::{HttpClient, HttpLimits, HttpMethod, HttpRequest, HttpResponse};
+use crate::kv::{KvLimits, KvStore};
+use crate::package::{api_version_for, unsupported_api_version, HOST_API_VERSION};
+use crate::persistence::{
+    DateFilter, HybridQuery, Index, IndexMetadata, IndexStats, PersistedDocument,
+};
 use crate::plugins::wasi::michel_api::Document;
+use crate::plugins::{self, v0_1, v0_2};
 use crate::{
     CustomPluginConfig, FsAccess, MichelPersistence, PluginConfig, PluginHostConfig, PluginInfo,
 };
 use async_trait::async_trait;
 
 pub struct MichelApiForPlugins<P: MichelPersistence> {
-    persistence: Arc<Mutex<P>>,
+    persistence: Arc<P>,
+    /// Indexes the plugin initialized, flushed once it's done indexing.
+    indexes: HashSet<String>,
+    /// Recorded on the indexes the plugin creates, known once its infos are fetched.
+    owner: Option<IndexMetadata>,
+    grants: Arc<Grants>,
+    http: Arc<HttpClient>,
+    /// Domains the manifest of the plugin declares, none for a bare component.
+    domains: Vec<String>,
+    /// In memory until the instance gives the plugin a folder.
+    kv: Arc<KvStore>,
+    /// Set when a call of an older world failed the plugin, for it to be started again.
+    trapped: bool,
 }
 
-impl<P: MichelPersistence> types::Types for MichelApiForPlugins<P> {}
+impl<P: MichelPersistence> Clone for MichelApiForPlugins<P> {
+    fn clone(&self) -> Self {
+        MichelApiForPlugins {
+            persistence: self.persistence.clone(),
+            indexes: self.indexes.clone(),
+            owner: self.owner.clone(),
+            grants: self.grants.clone(),
+            http: self.http.clone(),
+            domains: self.domains.clone(),
+            kv: self.kv.clone(),
+            trapped: self.trapped,
+        }
+    }
+}
 
-#[async_trait]
-impl<P: MichelPersistence> michel_api::MichelApi for MichelApiForPlugins<P> {
-    async fn hi(&mut self, name: String) -> Result<String> {
-        Ok(String::from("yo"))
+impl<P: MichelPersistence> MichelApiForPlugins<P> {
+    fn plugin(&self) -> Option<&str> {
+        self.owner.as_ref().map(|owner| owner.plugin.as_str())
     }
 
-    async fn send_entry_for_input(
-        &mut self,
-        input: String,
-        entries: Vec<types::Entry>,
-    ) -> Result<()> {
-        todo!()
+    /**
+        Fails the call of a plugin of an older world, which can't be returned the error. The
+        instance is left unusable, so the plugin is started again on its next call.
+    */
+    pub(super) fn trap(&mut self, error: types::Error) -> anyhow::Error {
+        self.trapped = true;
+
+        match error {
+            types::Error::InvalidValue(message) => anyhow!(message),
+        }
+    }
+
+    /// Folders granted to the plugin, that exist on the host, writable if granted both ways.
+    fn granted_folders(&self) -> Vec<GrantedFolder> {
+        let plugin = match self.plugin() {
+            Some(plugin) => plugin,
+            None => return Vec::new(),
+        };
+
+        let mut folders: Vec<GrantedFolder> = self
+            .grants
+            .granted(plugin)
+            .into_iter()
+            .filter_map(|capability| match capability {
+                Capability::Fs { path, write } => Some(GrantedFolder {
+                    path: expand_home(&path),
+                    write,
+                }),
+                _ => None,
+            })
+            .filter(|folder| folder.path.is_dir())
+            .collect();
+        folders.sort();
+        folders.dedup_by(|folder, kept| {
+            let same = folder.path == kept.path;
+            kept.write |= same && folder.write;
+            same
+        });
+
+        folders
+    }
+
+    /// Declared domains the plugin was granted.
+    fn granted_domains(&self) -> Vec<String> {
+        let plugin = match self.plugin() {
+            Some(plugin) => plugin,
+            None => return Vec::new(),
+        };
+
+        self.domains
+            .iter()
+            .filter(|domain| {
+                self.grants.is_granted(
+                    plugin,
+                    &Capability::Network {
+                        domain: (*domain).clone(),
+                    },
+                )
+            })
+            .cloned()
+            .collect()
+    }
+
+    /**
+        Whether the plugin may read or write an index : one it initialized, or adopted from a
+        previous run, that is still granted to it. Another plugin's index may hold anything.
+    */
+    fn can_use_index(&self, index: &str) -> bool {
+        self.indexes.contains(index)
+            && self.plugin().map_or(false, |plugin| {
+                self.grants.is_granted(
+                    plugin,
+                    &Capability::Index {
+                        name: String::from(index),
+                    },
+                )
+            })
+    }
+
+    async fn flush_indexes(&self) -> Result<()> {
+        for name in &self.indexes {
+            self.persistence.flush(Index { name: name.clone() }).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether the index was created by the plugin, possibly on a previous run.
+    async fn owns_index(&self, index: &str) -> Result<bool> {
+        let owner = match &self.owner {
+            Some(owner) => owner,
+            None => return Ok(false),
+        };
+
+        if !self
+            .persistence
+            .list_indexes()
+            .await?
+            .iter()
+            .any(|name| name == index)
+        {
+            return Ok(false);
+        }
+
+        let metadata = self
+            .persistence
+            .metadata(Index {
+                name: String::from(index),
+            })
+            .await?;
+
+        Ok(metadata.map_or(false, |metadata| metadata.plugin == owner.plugin))
     }
 
+    /// Creates an index for the plugin, unless it owns it from a previous run.
+    async fn create_index(&self, index: &str) -> Result<()> {
+        if self.owns_index(index).await? {
+            return Ok(());
+        }
+
+        self.persistence.init_index(String::from(index)).await?;
+
+        if let Some(owner) = &self.owner {
+            self.persistence
+                .set_metadata(
+                    Index {
+                        name: String::from(index),
+                    },
+                    owner.clone(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn index_stats(&self) -> Result<Vec<IndexStats>> {
+        let mut stats = Vec::new();
+
+        for name in &self.indexes {
+            stats.push(self.persistence.stats(Index { name: name.clone() }).await?);
+        }
+
+        stats.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(stats)
+    }
+}
+
+impl<P: MichelPersistence> types::Types for MichelApiForPlugins<P> {}
+
+#[async_trait]
+impl<P: MichelPersistence> michel_api::MichelApi for MichelApiForPlugins<P> {
     async fn new_document_for_index(
         &mut self,
         index: String,
         document: types::Document,
-    ) -> Result<()> {
-        let persistence = self.persistence.lock().await;
+    ) -> Result<Result<(), types::Error>> {
+        if !self.can_use_index(&index) {
+            return Ok(Err(types::Error::InvalidValue(not_granted(&index))));
+        }
 
-        let _ = persistence.add_document(Index { name: index }, PersistedDocument::from(document));
+        let document = match PersistedDocument::try_from(document) {
+            Ok(document) => document,
+            Err(error) => return Ok(Err(types::Error::InvalidValue(error.to_string()))),
+        };
 
-        Ok(())
+        Ok(self
+            .persistence
+            .add_document(Index { name: index }, document)
+            .await
+            .map_err(|error| types::Error::InvalidValue(error.to_string())))
     }
 
     async fn new_documents_for_index(
         &mut self,
         index: String,
         documents: Vec<Document>,
-    ) -> Result<()> {
-        let persistence = self.persistence.lock().await;
-
-        let _ = persistence.add_documents(
-            Index { name: index },
-            documents.into_iter().map(PersistedDocument::from).collect(),
-        );
+    ) -> Result<Result<(), types::Error>> {
+        if !self.can_use_index(&index) {
+            return Ok(Err(types::Error::InvalidValue(not_granted(&index))));
+        }
 
-        Ok(())
+        let documents = match documents
+            .into_iter()
+            .map(PersistedDocument::try_from)
+            .collect::<Result<Vec<PersistedDocument>>>()
+        {
+            Ok(documents) => documents,
+            Err(error) => return Ok(Err(types::Error::InvalidValue(error.to_string()))),
+        };
+
+        Ok(self
+            .persistence
+            .add_documents(Index { name: index }, documents)
+            .await
+            .map_err(|error| types::Error::InvalidValue(error.to_string())))
     }
 
     async fn search_in_index(
@@ -74,29 +272,207 @@ impl<P: MichelPersistence> michel_api::MichelApi for MichelApiForPlugins<P> {
         index: String,
         query: String,
     ) -> Result<Vec<types::Document>> {
-        let persistence = self.persistence.lock().await;
+        if !self.can_use_index(&index) {
+            warn!("{}", not_granted(&index));
+            return Ok(Vec::new());
+        }
 
-        let vec = persistence
-            .search_document(Index { name: index }, query, 10.into())?
-            .iter()
-            .map(|document| types::Document::try_from(document))
-            .filter_map(|document| document.ok())
-            .collect();
+        Ok(readable(
+            self.persistence
+                .search_document(Index { name: index }, query, 10.into())
+                .await?,
+        ))
+    }
 
-        Ok(vec)
+    async fn search_in_index_by_date(
+        &mut self,
+        index: String,
+        query: String,
+        filter: types::DateFilter,
+    ) -> Result<Result<Vec<types::Document>, types::Error>> {
+        if !self.can_use_index(&index) {
+            return Ok(Err(types::Error::InvalidValue(not_granted(&index))));
+        }
+
+        let filter = match DateFilter::try_from(filter) {
+            Ok(filter) => filter,
+            Err(error) => return Ok(Err(types::Error::InvalidValue(error.to_string()))),
+        };
+
+        Ok(Ok(readable(
+            self.persistence
+                .search_document_by_date(Index { name: index }, query, filter, 10.into())
+                .await?,
+        )))
     }
 
-    async fn init_index(&mut self, index: String) -> Result<()> {
-        let mut persistence = self.persistence.lock().await;
+    async fn hybrid_search_in_index(
+        &mut self,
+        index: String,
+        query: String,
+        embedding: Option<Vec<f32>>,
+        semantic_ratio: f32,
+    ) -> Result<Vec<types::Document>> {
+        if !self.can_use_index(&index) {
+            warn!("{}", not_granted(&index));
+            return Ok(Vec::new());
+        }
 
-        persistence.init_index(index)
+        Ok(readable(
+            self.persistence
+                .hybrid_search_document(
+                    Index { name: index },
+                    HybridQuery {
+                        query,
+                        vector: embedding,
+                        semantic_ratio,
+                    },
+                    10.into(),
+                )
+                .await?,
+        ))
+    }
+
+    /**
+        An index the plugin created on a previous run is reused as is. Fails until the user
+        grants the index to the plugin, the request staying pending meanwhile.
+    */
+    async fn init_index(&mut self, index: String) -> Result<Result<(), types::Error>> {
+        let plugin = self.plugin().ok_or(anyhow!(
+            "indexes can't be initialized before the plugin is loaded"
+        ))?;
+        let capability = Capability::Index {
+            name: index.clone(),
+        };
+
+        let refusal = match self.grants.request(plugin, capability)? {
+            Decision::Granted => None,
+            Decision::Denied => Some(format!("index {} was refused to {}", index, plugin)),
+            Decision::Pending => Some(format!(
+                "index {} waits for the user to grant it to {}",
+                index, plugin
+            )),
+        };
+        if let Some(refusal) = refusal {
+            return Ok(Err(types::Error::InvalidValue(refusal)));
+        }
+
+        if let Err(error) = self.create_index(&index).await {
+            return Ok(Err(types::Error::InvalidValue(error.to_string())));
+        }
+
+        self.indexes.insert(index);
+
+        Ok(Ok(()))
+    }
+
+    async fn flush_index(&mut self, index: String) -> Result<Result<(), types::Error>> {
+        if !self.can_use_index(&index) {
+            return Ok(Err(types::Error::InvalidValue(not_granted(&index))));
+        }
+
+        match self.persistence.flush(Index { name: index }).await {
+            Ok(()) => Ok(Ok(())),
+            Err(error) => Ok(Err(types::Error::InvalidValue(error.to_string()))),
+        }
+    }
+
+    /// Plugins only see their own indexes, the other ones may hold anything.
+    async fn list_indexes(&mut self) -> Result<Vec<String>> {
+        let mut indexes: Vec<String> = self.indexes.iter().cloned().collect();
+        indexes.sort();
+
+        Ok(indexes)
+    }
+
+    async fn index_stats(
+        &mut self,
+        index: String,
+    ) -> Result<Result<types::IndexStats, types::Error>> {
+        if !self.indexes.contains(&index) {
+            return Ok(Err(types::Error::InvalidValue(format!(
+                "index {} doesn't belong to the plugin",
+                index
+            ))));
+        }
+
+        match self.persistence.stats(Index { name: index }).await {
+            Ok(stats) => types::IndexStats::try_from(stats).map(Ok),
+            Err(error) => Ok(Err(types::Error::InvalidValue(error.to_string()))),
+        }
+    }
+
+    async fn http_fetch(
+        &mut self,
+        request: types::HttpRequest,
+    ) -> Result<Result<types::HttpResponse, types::Error>> {
+        let domains = self.granted_domains();
+
+        match self.http.fetch(request.into(), &domains).await {
+            Ok(response) => Ok(Ok(response.into())),
+            Err(error) => Ok(Err(types::Error::InvalidValue(error.to_string()))),
+        }
     }
 }
 
-fn wasi_dir_from_path<P: AsRef<Path>>(path: P) -> Dir {
-    let file = std::fs::File::open(path).unwrap();
+#[async_trait]
+impl<P: MichelPersistence> kv::Kv for MichelApiForPlugins<P> {
+    async fn get(&mut self, key: String) -> Result<Option<Vec<u8>>> {
+        Ok(self.kv.get(&key))
+    }
+
+    async fn set(&mut self, key: String, value: Vec<u8>) -> Result<Result<(), types::Error>> {
+        Ok(self
+            .kv
+            .set(&key, value)
+            .map_err(|error| types::Error::InvalidValue(error.to_string())))
+    }
+
+    async fn delete(&mut self, key: String) -> Result<Result<(), types::Error>> {
+        Ok(self
+            .kv
+            .delete(&key)
+            .map_err(|error| types::Error::InvalidValue(error.to_string())))
+    }
 
-    Dir::from_cap_std(wasi_cap_std_sync::Dir::from_std_file(file))
+    async fn list_prefix(&mut self, prefix: String) -> Result<Vec<String>> {
+        Ok(self.kv.list_prefix(&prefix))
+    }
+}
+
+/// Found documents the plugin can read, a stored one that can't be converted being left out.
+fn readable(documents: Vec<PersistedDocument>) -> Vec<types::Document> {
+    documents
+        .iter()
+        .filter_map(|document| match types::Document::try_from(document) {
+            Ok(document) => Some(document),
+            Err(error) => {
+                warn!("leaving a found document out : {:#}", error);
+                None
+            }
+        })
+        .collect()
+}
+
+fn not_granted(index: &str) -> String {
+    format!(
+        "index {} isn't one the plugin initialized, or isn't granted to it",
+        index
+    )
+}
+
+fn wasi_dir_from_path<P: AsRef<Path>>(path: P) -> Result<Dir> {
+    let file = std::fs::File::open(path)?;
+
+    Ok(Dir::from_cap_std(wasi_cap_std_sync::Dir::from_std_file(
+        file,
+    )))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct GrantedFolder {
+    path: PathBuf,
+    write: bool,
 }
 
 pub struct Ctx<P: MichelPersistence> {
@@ -105,58 +481,395 @@ pub struct Ctx<P: MichelPersistence> {
 }
 
 impl<P: MichelPersistence> Ctx<P> {
-    async fn new(persistence: Arc<Mutex<P>>) -> Ctx<P> {
+    /// Granted folders are preopened at the same path as on the host.
+    fn new(michel: MichelApiForPlugins<P>, folders: &[GrantedFolder]) -> Result<Ctx<P>> {
         let mut wasi = WasiCtxBuilder::new()
             .inherit_stderr()
             .inherit_stdin()
             .build();
 
-        let search_path = wasi_dir_from_path("/home/yohann/devs");
+        for folder in folders {
+            let (dir_perms, file_perms) = match folder.write {
+                true => (DirPerms::all(), FilePerms::all()),
+                false => (DirPerms::READ, FilePerms::READ),
+            };
+
+            wasi.push_preopened_dir(
+                Box::new(wasi_dir_from_path(&folder.path)?),
+                dir_perms,
+                file_perms,
+                &folder.path.to_string_lossy(),
+            )?;
+        }
 
-        wasi.push_preopened_dir(Box::new(search_path), "/home/yohann/devs")
-            .unwrap();
+        Ok(Ctx { wasi, michel })
+    }
+}
+
+/// Exports of the plugin, in the version of the world it targets.
+enum Bindings {
+    V0_1(v0_1::Michel),
+    V0_2(v0_2::Michel),
+    Latest(Michel),
+}
 
-        Ctx {
-            wasi,
-            michel: MichelApiForPlugins { persistence },
+impl Bindings {
+    async fn instantiate<P: MichelPersistence>(
+        version: &Version,
+        store: &mut Store<Ctx<P>>,
+        component: &Component,
+        linker: &Linker<Ctx<P>>,
+    ) -> Result<Bindings> {
+        let context = || format!("can't link the plugin against michel:plugin@{}", version);
+
+        if *version == HOST_API_VERSION {
+            let (bindings, _) = Michel::instantiate_async(store, component, linker)
+                .await
+                .with_context(context)?;
+            return Ok(Bindings::Latest(bindings));
         }
+
+        if *version == v0_2::API_VERSION {
+            let (bindings, _) = v0_2::Michel::instantiate_async(store, component, linker)
+                .await
+                .with_context(context)?;
+            return Ok(Bindings::V0_2(bindings));
+        }
+
+        let (bindings, _) = v0_1::Michel::instantiate_async(store, component, linker)
+            .await
+            .with_context(context)?;
+        Ok(Bindings::V0_1(bindings))
     }
 }
 
+/// A started plugin, with the folders preopened for it.
+struct Running<P: MichelPersistence> {
+    store: Store<Ctx<P>>,
+    bindings: Bindings,
+    folders: Vec<GrantedFolder>,
+}
+
 pub struct PluginInstance<P: MichelPersistence> {
-    pub bindings: Michel,
-    pub store: Arc<Mutex<Store<Ctx<P>>>>,
+    engine: Engine,
+    component: Component,
+    linker: Linker<Ctx<P>>,
+    /// Version of the world the plugin is linked against.
+    api_version: Version,
+    running: Mutex<Running<P>>,
 }
 
 impl<P: MichelPersistence> PluginInstance<P> {
+    /**
+        Locks the plugin to call it. The guest only reads its preopened folders when starting,
+        so it's started again, keeping the host state, when the folders granted to it changed,
+        or after the host failed one of its calls.
+    */
+    async fn lock(&self) -> Result<MutexGuard<'_, Running<P>>> {
+        let mut running = self.running.lock().await;
+        let folders = running.store.data().michel.granted_folders();
+
+        if folders != running.folders || running.store.data().michel.trapped {
+            let mut michel = running.store.data().michel.clone();
+            michel.trapped = false;
+            *running = self.start(michel, folders).await?;
+        }
+
+        Ok(running)
+    }
+
+    async fn start(
+        &self,
+        michel: MichelApiForPlugins<P>,
+        folders: Vec<GrantedFolder>,
+    ) -> Result<Running<P>> {
+        let mut store = Store::new(&self.engine, Ctx::new(michel, &folders)?);
+        let bindings =
+            Bindings::instantiate(&self.api_version, &mut store, &self.component, &self.linker)
+                .await?;
+
+        Ok(Running {
+            store,
+            bindings,
+            folders,
+        })
+    }
+
+    pub fn api_version(&self) -> &Version {
+        &self.api_version
+    }
+
     pub async fn get_infos(&self) -> Result<types::PluginInfo> {
-        let mut guard = self.store.lock().await;
-        let store = guard.as_context_mut();
+        let mut guard = self.lock().await?;
+        let running = &mut *guard;
+
+        match &running.bindings {
+            Bindings::Latest(bindings) => bindings.plugin_api().call_info(&mut running.store).await,
+            Bindings::V0_2(bindings) => Ok(bindings
+                .plugin_api()
+                .call_info(&mut running.store)
+                .await?
+                .into()),
+            Bindings::V0_1(bindings) => Ok(bindings
+                .plugin_api()
+                .call_info(&mut running.store)
+                .await?
+                .into()),
+        }
+    }
+
+    pub async fn index(&self) -> Result<Result<(), types::Error>> {
+        let mut guard = self.lock().await?;
+        let running = &mut *guard;
+
+        match &running.bindings {
+            Bindings::Latest(bindings) => {
+                bindings.plugin_api().call_index(&mut running.store).await
+            }
+            Bindings::V0_2(bindings) => Ok(bindings
+                .plugin_api()
+                .call_index(&mut running.store)
+                .await?
+                .map_err(Into::into)),
+            Bindings::V0_1(bindings) => Ok(bindings
+                .plugin_api()
+                .call_index(&mut running.store)
+                .await?
+                .map_err(Into::into)),
+        }
+    }
+
+    pub async fn for_input(&self, input: &str) -> Result<Vec<types::Entry>> {
+        self.for_input_if(input, || true)
+            .await
+            .map(Option::unwrap_or_default)
+    }
+
+    /**
+        Calls `for-input` unless `wanted` says otherwise once the plugin is free : the query
+        may have been superseded while waiting for the previous call to end.
+    */
+    pub async fn for_input_if<F: Fn() -> bool>(
+        &self,
+        input: &str,
+        wanted: F,
+    ) -> Result<Option<Vec<types::Entry>>> {
+        let mut guard = self.lock().await?;
+        let running = &mut *guard;
+
+        if !wanted() {
+            return Ok(None);
+        }
+
+        let entries = match &running.bindings {
+            Bindings::Latest(bindings) => {
+                bindings
+                    .plugin_api()
+                    .call_for_input(&mut running.store, input)
+                    .await?
+            }
+            Bindings::V0_2(bindings) => bindings
+                .plugin_api()
+                .call_for_input(&mut running.store, input)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            Bindings::V0_1(bindings) => bindings
+                .plugin_api()
+                .call_for_input(&mut running.store, input)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        };
+
+        Ok(Some(entries))
+    }
+
+    pub async fn autocomplete(&self, input: &str) -> Result<Option<String>> {
+        let mut guard = self.lock().await?;
+        let running = &mut *guard;
+
+        match &running.bindings {
+            Bindings::Latest(bindings) => {
+                bindings
+                    .plugin_api()
+                    .call_autocomplete(&mut running.store, input)
+                    .await
+            }
+            Bindings::V0_2(bindings) => {
+                bindings
+                    .plugin_api()
+                    .call_autocomplete(&mut running.store, input)
+                    .await
+            }
+            Bindings::V0_1(bindings) => {
+                bindings
+                    .plugin_api()
+                    .call_autocomplete(&mut running.store, input)
+                    .await
+            }
+        }
+    }
 
-        self.bindings.plugin_api.call_info(store).await
+    pub async fn execute_action(
+        &self,
+        entry: types::Entry,
+        action: &str,
+    ) -> Result<Result<(), types::Error>> {
+        let mut guard = self.lock().await?;
+        let running = &mut *guard;
+
+        match &running.bindings {
+            Bindings::Latest(bindings) => {
+                bindings
+                    .plugin_api()
+                    .call_execute_action(&mut running.store, &entry, action)
+                    .await
+            }
+            Bindings::V0_2(bindings) => Ok(bindings
+                .plugin_api()
+                .call_execute_action(&mut running.store, &entry.into(), action)
+                .await?
+                .map_err(Into::into)),
+            // Entries of such plugins have no actions
+            Bindings::V0_1(_) => Ok(Err(types::Error::InvalidValue(format!(
+                "the plugin has no action {}",
+                action
+            )))),
+        }
     }
 
-    pub async fn init<T: AsRef<Path>>(
-        path: T,
-        persistence: Arc<Mutex<P>>,
+    pub async fn set_owner(&self, owner: IndexMetadata) {
+        self.running.lock().await.store.data_mut().michel.owner = Some(owner);
+    }
+
+    /// Domains the plugin may fetch from, once granted.
+    pub async fn declare_domains(&self, domains: Vec<String>) {
+        self.running.lock().await.store.data_mut().michel.domains = domains;
+    }
+
+    /// Keeps the keys and values of the plugin in its folder of `root`, from now on.
+    pub async fn open_kv(&self, root: &Path) -> Result<()> {
+        let mut running = self.running.lock().await;
+        let michel = &mut running.store.data_mut().michel;
+        let plugin = michel
+            .plugin()
+            .ok_or(anyhow!("the plugin isn't known yet"))?;
+
+        michel.kv = Arc::new(KvStore::open(root, plugin, KvLimits::default())?);
+
+        Ok(())
+    }
+
+    /// Counts an index created on a previous run as initialized by the plugin.
+    pub async fn adopt_index(&self, index: &str) {
+        self.running
+            .lock()
+            .await
+            .store
+            .data_mut()
+            .michel
+            .indexes
+            .insert(String::from(index));
+    }
+
+    pub async fn migrate(&self, index: &str, previous: IndexMetadata) -> Result<types::Migration> {
+        let mut guard = self.lock().await?;
+        let running = &mut *guard;
+        let previous = types::IndexMetadata::from(previous);
+
+        match &running.bindings {
+            Bindings::Latest(bindings) => {
+                bindings
+                    .plugin_api()
+                    .call_migrate(&mut running.store, index, &previous)
+                    .await
+            }
+            Bindings::V0_2(bindings) => Ok(bindings
+                .plugin_api()
+                .call_migrate(&mut running.store, index, &previous.into())
+                .await?
+                .into()),
+            // Such plugins can't tell whether their documents are still valid
+            Bindings::V0_1(_) => Ok(types::Migration::Rebuild),
+        }
+    }
+
+    /// Statistics of the indexes the plugin initialized, sorted by name.
+    pub async fn index_stats(&self) -> Result<Vec<IndexStats>> {
+        let guard = self.running.lock().await;
+
+        guard.store.data().michel.index_stats().await
+    }
+
+    /// Makes every document the plugin pushed so far searchable.
+    pub async fn flush_indexes(&self) -> Result<()> {
+        let guard = self.running.lock().await;
+
+        guard.store.data().michel.flush_indexes().await
+    }
+
+    /**
+        Instantiates a wasm component, in its binary or text format, linking it against the
+        newest supported version of the world compatible with the one it targets.
+    */
+    pub async fn init(
+        wasm: &[u8],
+        target: &Version,
+        persistence: Arc<P>,
+        grants: Arc<Grants>,
     ) -> Result<PluginInstance<P>> {
+        let api_version = api_version_for(target).ok_or(anyhow!(
+            "the plugin targets {}",
+            unsupported_api_version(target)
+        ))?;
+
+        plugins::check_world(wasm, &api_version)?;
+
         let mut config = Config::new();
         config.wasm_component_model(true);
         config.async_support(true);
 
         // Modules can be compiled through either the text or binary format
         let engine = Engine::new(&config)?;
-        let component = Component::from_file(&engine, path)?;
+        let component = Component::new(&engine, wasm)?;
         let mut linker: Linker<Ctx<P>> = Linker::new(&engine);
         host::add_to_linker(&mut linker, |ctx| &mut ctx.wasi)?;
-        Michel::add_to_linker(&mut linker, |ctx| &mut ctx.michel)?;
+        if api_version == HOST_API_VERSION {
+            Michel::add_to_linker(&mut linker, |ctx| &mut ctx.michel)?;
+        } else if api_version == v0_2::API_VERSION {
+            v0_2::Michel::add_to_linker(&mut linker, |ctx| &mut ctx.michel)?;
+        } else {
+            v0_1::Michel::add_to_linker(&mut linker, |ctx| &mut ctx.michel)?;
+        }
 
-        let mut store = Store::new(&engine, Ctx::new(persistence).await);
-        let (bindings, _) = Michel::instantiate_async(&mut store, &component, &linker).await?;
+        let michel = MichelApiForPlugins {
+            persistence,
+            indexes: HashSet::new(),
+            owner: None,
+            grants,
+            http: Arc::new(HttpClient::new(HttpLimits::default())?),
+            domains: Vec::new(),
+            kv: Arc::new(KvStore::in_memory(KvLimits::default())),
+            trapped: false,
+        };
+
+        // Nothing is granted until the plugin is known
+        let mut store = Store::new(&engine, Ctx::new(michel, &[])?);
+        let bindings = Bindings::instantiate(&api_version, &mut store, &component, &linker).await?;
 
         Ok(PluginInstance {
-            bindings,
-            store: Arc::new(Mutex::new(store)),
+            engine,
+            component,
+            linker,
+            api_version,
+            running: Mutex::new(Running {
+                store,
+                bindings,
+                folders: Vec::new(),
+            }),
         })
     }
 }
@@ -168,6 +881,7 @@ impl From<types::PluginInfo> for PluginInfo {
             name: value.name,
             description: value.description,
             version: value.version,
+            schema_version: value.schema_version,
             icon: value.icon,
             url: value.url,
         }
@@ -196,3 +910,31 @@ impl From<types::FsAccessResult> for FsAccess {
         }
     }
 }
+
+impl From<types::HttpRequest> for HttpRequest {
+    fn from(value: types::HttpRequest) -> Self {
+        HttpRequest {
+            method: match value.method {
+                types::HttpMethod::Get => HttpMethod::Get,
+                types::HttpMethod::Head => HttpMethod::Head,
+                types::HttpMethod::Post => HttpMethod::Post,
+                types::HttpMethod::Put => HttpMethod::Put,
+                types::HttpMethod::Patch => HttpMethod::Patch,
+                types::HttpMethod::Delete => HttpMethod::Delete,
+            },
+            url: value.url,
+            headers: value.headers,
+            body: value.body,
+        }
+    }
+}
+
+impl From<HttpResponse> for types::HttpResponse {
+    fn from(value: HttpResponse) -> Self {
+        types::HttpResponse {
+            status: value.status,
+            headers: value.headers,
+            body: value.body,
+        }
+    }
+}
@@ -0,0 +1,160 @@
+/*!
+    Registries plugins are installed from : a static `index.json`, served over HTTP or kept in
+    a local folder, listing the versions of each plugin and where to download their package.
+
+    ```json
+    {
+        "plugins": [
+            {
+                "identifier": "files",
+                "name": "Files",
+                "description": "Search your files",
+                "versions": [
+                    {
+                        "version": "1.2.0",
+                        "min-host-api": "0.1.0",
+                        "package": "files-1.2.0.tar.gz",
+                        "sha256": "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+                    }
+                ]
+            }
+        ]
+    }
+    ```
+
+    Packages are resolved relatively to the index, unless given as a full URL.
+*/
+
+use crate::package::{encode_hex, supports_host_api, PluginPackage};
+use anyhow::{anyhow, Result};
+use reqwest::Url;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const INDEX_FILE: &str = "index.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct RegistryVersion {
+    pub version: Version,
+    pub min_host_api: Version,
+    /// Path or URL of the `.tar.gz` package.
+    pub package: String,
+    /// Hexadecimal SHA-256 of the package archive.
+    pub sha256: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RegistryPlugin {
+    pub identifier: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub versions: Vec<RegistryVersion>,
+}
+
+impl RegistryPlugin {
+    /// Newest version this host can run.
+    pub fn latest_compatible(&self) -> Option<&RegistryVersion> {
+        self.versions
+            .iter()
+            .filter(|version| supports_host_api(&version.min_host_api))
+            .max_by(|first, second| first.version.cmp(&second.version))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RegistryIndex {
+    pub plugins: Vec<RegistryPlugin>,
+}
+
+impl RegistryIndex {
+    pub fn plugin(&self, identifier: &str) -> Option<&RegistryPlugin> {
+        self.plugins
+            .iter()
+            .find(|plugin| plugin.identifier == identifier)
+    }
+}
+
+/// A plugin of the registry, as offered to the user.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AvailablePlugin {
+    pub plugin: RegistryPlugin,
+    /// Newest version this host can run, none if it needs a newer host.
+    pub latest: Option<RegistryVersion>,
+    /// Version installed, if any.
+    pub installed: Option<String>,
+}
+
+pub enum Registry {
+    /// URL of the index.
+    Remote(Url),
+    /// Folder holding `index.json`.
+    Local(PathBuf),
+}
+
+impl Registry {
+    /// An `http(s)://` URL of the index, or a local folder holding it.
+    pub fn new(location: &str) -> Result<Registry> {
+        if location.starts_with("http://") || location.starts_with("https://") {
+            return Ok(Registry::Remote(Url::parse(location)?));
+        }
+
+        let path = Path::new(location);
+        Ok(Registry::Local(match path.file_name() {
+            Some(name) if name == INDEX_FILE => path.parent().unwrap_or(Path::new(".")).into(),
+            _ => path.to_path_buf(),
+        }))
+    }
+
+    pub async fn index(&self) -> Result<RegistryIndex> {
+        let content = match self {
+            Registry::Remote(url) => fetch(url.clone()).await?,
+            Registry::Local(folder) => fs::read(folder.join(INDEX_FILE))?,
+        };
+
+        serde_json::from_slice(&content)
+            .map_err(|error| anyhow!("invalid registry index : {}", error))
+    }
+
+    /// Downloads the package of `version`, checking it's the one the index describes.
+    pub async fn download(&self, version: &RegistryVersion) -> Result<(Vec<u8>, PluginPackage)> {
+        let archive = match (self, Url::parse(&version.package)) {
+            (_, Ok(url)) => fetch(url).await?,
+            (Registry::Remote(index), Err(_)) => fetch(index.join(&version.package)?).await?,
+            (Registry::Local(folder), Err(_)) => fs::read(folder.join(&version.package))?,
+        };
+
+        if encode_hex(&Sha256::digest(&archive)) != version.sha256.to_lowercase() {
+            return Err(anyhow!(
+                "{} doesn't match the checksum of the registry",
+                version.package
+            ));
+        }
+
+        let package = PluginPackage::from_archive_bytes(&archive)?;
+        if package.manifest.version != version.version {
+            return Err(anyhow!(
+                "{} holds version {}, but the registry describes version {}",
+                version.package,
+                package.manifest.version,
+                version.version
+            ));
+        }
+
+        Ok((archive, package))
+    }
+}
+
+async fn fetch(url: Url) -> Result<Vec<u8>> {
+    let response = reqwest::get(url.clone()).await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("fetching {} failed : {}", url, response.status()));
+    }
+
+    Ok(response.bytes().await?.to_vec())
+}
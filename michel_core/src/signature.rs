@@ -0,0 +1,139 @@
+/*!
+    Signatures of plugin packages, so that a team only runs the plugins it trusts.
+
+    A package is signed with an ed25519 key over its digest, the hexadecimal signature going
+    in `michel-plugin.sig` next to the manifest. Keys the host trusts, and what it does with
+    plugins no trusted key signed, live in `signatures.toml` in the config folder :
+
+    ```toml
+    policy = "refuse"
+
+    [[trusted-keys]]
+    name = "team"
+    key = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c"
+    ```
+*/
+
+use crate::package::{decode_hex, encode_hex, PluginPackage, SIGNATURE_FILE};
+use anyhow::{anyhow, Result};
+use ed25519_dalek::{ExpandedSecretKey, PublicKey, SecretKey, Signature, Verifier};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SignaturePolicy {
+    /// Plugins are loaded without checking signatures.
+    #[default]
+    Ignore,
+    /// Plugins no trusted key signed are loaded with a warning.
+    Warn,
+    /// Plugins no trusted key signed aren't loaded.
+    Refuse,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrustedKey {
+    pub name: String,
+    /// Hexadecimal ed25519 public key.
+    pub key: String,
+}
+
+impl TrustedKey {
+    fn public_key(&self) -> Result<PublicKey> {
+        decode_hex(&self.key)
+            .and_then(|key| PublicKey::from_bytes(&key).ok())
+            .ok_or(anyhow!("trusted key {} is malformed", self.name))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct SignatureConfig {
+    #[serde(default)]
+    pub policy: SignaturePolicy,
+    #[serde(default)]
+    pub trusted_keys: Vec<TrustedKey>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum SignatureStatus {
+    /// Signed by the trusted key named `key`.
+    Trusted { key: String },
+    /// A bare wasm component, or a package without signature.
+    Unsigned,
+    /// Signed, but by no trusted key, or altered since.
+    Untrusted,
+}
+
+impl SignatureConfig {
+    /**
+        Reads the config at `path`, ignoring signatures if the file doesn't exist. A malformed
+        trusted key fails here, rather than when checking each plugin.
+    */
+    pub fn load<T: AsRef<Path>>(path: T) -> Result<SignatureConfig> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Ok(SignatureConfig::default());
+        }
+
+        let config: SignatureConfig = toml::from_str(&fs::read_to_string(path)?)?;
+        for trusted in &config.trusted_keys {
+            trusted.public_key()?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn verify(&self, package: &PluginPackage) -> Result<SignatureStatus> {
+        let signature = match &package.signature {
+            Some(signature) => match Signature::try_from(signature.as_slice()) {
+                Ok(signature) => signature,
+                Err(_) => return Ok(SignatureStatus::Untrusted),
+            },
+            None => return Ok(SignatureStatus::Unsigned),
+        };
+
+        for trusted in &self.trusted_keys {
+            let key = trusted.public_key()?;
+
+            if key.verify(&package.digest, &signature).is_ok() {
+                return Ok(SignatureStatus::Trusted {
+                    key: trusted.name.clone(),
+                });
+            }
+        }
+
+        Ok(SignatureStatus::Untrusted)
+    }
+}
+
+/// Signs the package folder at `path` with a hexadecimal secret key, returning its public key.
+pub fn sign_package<T: AsRef<Path>>(path: T, secret_key: &str) -> Result<String> {
+    let path = path.as_ref();
+    let package = PluginPackage::open(path)?;
+
+    let secret = parse_secret_key(secret_key)?;
+    let public = PublicKey::from(&secret);
+    let signature = ExpandedSecretKey::from(&secret).sign(&package.digest, &public);
+
+    fs::write(path.join(SIGNATURE_FILE), encode_hex(&signature.to_bytes()))?;
+
+    Ok(encode_hex(public.as_bytes()))
+}
+
+/// Public key of a hexadecimal ed25519 secret key, to add to the trusted keys.
+pub fn public_key(secret_key: &str) -> Result<String> {
+    Ok(encode_hex(
+        PublicKey::from(&parse_secret_key(secret_key)?).as_bytes(),
+    ))
+}
+
+fn parse_secret_key(secret_key: &str) -> Result<SecretKey> {
+    decode_hex(secret_key.trim())
+        .and_then(|secret| SecretKey::from_bytes(&secret).ok())
+        .ok_or(anyhow!("malformed secret key"))
+}
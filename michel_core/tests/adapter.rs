@@ -0,0 +1,119 @@
+use michel_core::grants::{Capability, Grants};
+use michel_core::memory::MemoryPersistence;
+use michel_core::persistence::{Index, MichelPersistence};
+use michel_core::Plugin;
+use semver::Version;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+#[tokio::test]
+async fn plugins_of_the_first_world_run_through_the_adapter() {
+    let persistence = Arc::new(MemoryPersistence::new());
+    let grants = Arc::new(Grants::in_memory());
+
+    // Declares no version, so it's linked against michel:plugin@0.1.0
+    let plugin = Plugin::load_from_path(
+        fixture("baseline-plugin.wat"),
+        persistence.clone(),
+        grants.clone(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(plugin.api_version(), &Version::new(0, 1, 0));
+    assert_eq!(plugin.identifier(), "baseline");
+    assert_eq!(plugin.version(), "1.0.0");
+
+    let index = Capability::Index {
+        name: String::from("baseline"),
+    };
+    grants.grant("baseline", index).unwrap();
+    plugin.index().await.unwrap();
+
+    // Unsigned numbers of the world are stored as the numbers of the latest one
+    let documents = persistence
+        .search_document(
+            Index {
+                name: String::from("baseline"),
+            },
+            String::new(),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(documents.len(), 1);
+    assert_eq!(documents[0]["title"], json!("Baseline"));
+    assert_eq!(documents[0]["count"], json!(3));
+
+    let entries =
+        serde_json::to_value(plugin.entries_for_input("baseline").await.unwrap()).unwrap();
+    assert_eq!(
+        entries,
+        json!([{
+            "title": "1",
+            "description": "Found in the baseline index",
+            "preview": null,
+            "actions": []
+        }])
+    );
+}
+
+#[tokio::test]
+async fn plugins_waiting_for_their_index_index_once_granted() {
+    let persistence = Arc::new(MemoryPersistence::new());
+    let grants = Arc::new(Grants::in_memory());
+    let plugin = Plugin::load_from_path(
+        fixture("baseline-plugin.wat"),
+        persistence.clone(),
+        grants.clone(),
+    )
+    .await
+    .unwrap();
+
+    // The world can't be returned the refusal, so the indexing fails meanwhile
+    assert!(plugin.index().await.is_err());
+    let index = Capability::Index {
+        name: String::from("baseline"),
+    };
+    assert_eq!(grants.pending().len(), 1);
+    assert_eq!(grants.pending()[0].capability, index);
+
+    grants.grant("baseline", index).unwrap();
+    plugin.index().await.unwrap();
+
+    let documents = persistence
+        .search_document(
+            Index {
+                name: String::from("baseline"),
+            },
+            String::new(),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(documents.len(), 1);
+}
+
+#[tokio::test]
+async fn plugins_of_another_world_are_explained() {
+    // Built before michel:plugin@0.1.0, its plugin-api only exports a `bahouais` function
+    let error = match Plugin::load_from_path(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../file-plugin.wasm"),
+        Arc::new(MemoryPersistence::new()),
+        Arc::new(Grants::in_memory()),
+    )
+    .await
+    {
+        Ok(_) => panic!("a plugin of another world was loaded"),
+        Err(error) => error.to_string(),
+    };
+
+    assert_eq!(error, "can't link the plugin against michel:plugin@0.1.0");
+}
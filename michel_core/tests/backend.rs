@@ -0,0 +1,25 @@
+use michel_core::backend::{PersistenceBackend, PersistenceConfig};
+
+#[test]
+fn persistence_is_read_among_the_keys_of_other_frontends() {
+    let config: PersistenceConfig = toml::from_str(
+        r#"
+            username = "michel"
+            daemon = true
+            persistence = "sqlite"
+            trigram_embedder = true
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.persistence, PersistenceBackend::Sqlite);
+    assert!(config.trigram_embedder);
+}
+
+#[test]
+fn persistence_defaults_to_milli_without_embedder() {
+    let config: PersistenceConfig = toml::from_str(r#"username = "michel""#).unwrap();
+
+    assert_eq!(config.persistence, PersistenceBackend::Milli);
+    assert!(!config.trigram_embedder);
+}
@@ -0,0 +1,185 @@
+;; A plugin built against michel:plugin@0.1.0, the world of the first plugins, written in
+;; the text format so that it doesn't depend on the toolchains of the time. It indexes one
+;; document with a text and a number field, and answers inputs with the identifiers of the
+;; documents the host finds for them.
+(component
+  (import "michel-api" (instance $michel-api
+    (type $value (variant
+      (case "text" string)
+      (case "datetime" string)
+      (case "number" u32)
+      (case "boolean" bool)))
+    (type $field (record (field "name" string) (field "value" $value)))
+    (type $document (record (field "identifier" string) (field "fields" (list $field))))
+    (export "init-index" (func (param "index" string)))
+    (export "new-document-for-index" (func (param "index" string) (param "document" $document)))
+    (export "search-in-index" (func (param "index" string) (param "query" string) (result (list $document))))
+  ))
+
+  ;; Memory and allocator, apart so that imports can be lowered before the plugin is instantiated
+  (core module $Libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 8192))
+    (func (export "realloc") (param i32 i32) (param $align i32) (param $size i32) (result i32)
+      (local $pointer i32)
+      (local.set $pointer
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get $align) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get $align))))
+      (global.set $heap (i32.add (local.get $pointer) (local.get $size)))
+      (local.get $pointer))
+  )
+  (core instance $libc (instantiate $Libc))
+  (alias core export $libc "memory" (core memory $memory))
+  (alias core export $libc "realloc" (core func $realloc))
+
+  (alias export $michel-api "init-index" (func $init-index))
+  (alias export $michel-api "new-document-for-index" (func $new-document-for-index))
+  (alias export $michel-api "search-in-index" (func $search-in-index))
+  (core func $init-index-lowered (canon lower (func $init-index) (memory $memory)))
+  (core func $new-document-for-index-lowered
+    (canon lower (func $new-document-for-index) (memory $memory)))
+  (core func $search-in-index-lowered
+    (canon lower (func $search-in-index) (memory $memory) (realloc $realloc)))
+
+  (core module $Plugin
+    (import "env" "memory" (memory 1))
+    (import "michel-api" "init-index" (func $init-index (param i32 i32)))
+    (import "michel-api" "new-document-for-index"
+      (func $new-document-for-index (param i32 i32 i32 i32 i32 i32)))
+    (import "michel-api" "search-in-index"
+      (func $search-in-index (param i32 i32 i32 i32 i32)))
+
+    (data (i32.const 1024) "baseline")
+    (data (i32.const 1032) "Baseline")
+    (data (i32.const 1040) "Built against michel:plugin@0.1.0")
+    (data (i32.const 1076) "1.0.0")
+    (data (i32.const 1084) "title")
+    (data (i32.const 1092) "count")
+    (data (i32.const 1100) "1")
+    (data (i32.const 1104) "Found in the baseline index")
+
+    ;; Return areas, the options and results left to 0 standing for `none` and `ok`
+    (func (export "info") (result i32)
+      (i32.store (i32.const 256) (i32.const 1024))
+      (i32.store (i32.const 260) (i32.const 8))
+      (i32.store (i32.const 264) (i32.const 1032))
+      (i32.store (i32.const 268) (i32.const 8))
+      (i32.store (i32.const 272) (i32.const 1040))
+      (i32.store (i32.const 276) (i32.const 33))
+      (i32.store (i32.const 280) (i32.const 1076))
+      (i32.store (i32.const 284) (i32.const 5))
+      (i32.const 256))
+
+    ;; Document 1 of the index, with a `title` text and a `count` number
+    (func (export "index") (result i32)
+      (call $init-index (i32.const 1024) (i32.const 8))
+      (i32.store (i32.const 400) (i32.const 1084))
+      (i32.store (i32.const 404) (i32.const 5))
+      (i32.store8 (i32.const 408) (i32.const 0))
+      (i32.store (i32.const 412) (i32.const 1032))
+      (i32.store (i32.const 416) (i32.const 8))
+      (i32.store (i32.const 420) (i32.const 1092))
+      (i32.store (i32.const 424) (i32.const 5))
+      (i32.store8 (i32.const 428) (i32.const 2))
+      (i32.store (i32.const 432) (i32.const 3))
+      (call $new-document-for-index
+        (i32.const 1024) (i32.const 8)
+        (i32.const 1100) (i32.const 1)
+        (i32.const 400) (i32.const 2))
+      (i32.const 320))
+
+    (func (export "config") (result i32)
+      (i32.const 336))
+
+    (func (export "update-config") (param i32 i32) (result i32)
+      (i32.const 320))
+
+    ;; An entry for each document found, titled by its identifier
+    (func (export "for-input") (param $input i32) (param $length i32) (result i32)
+      (local $documents i32)
+      (local $count i32)
+      (local $index i32)
+      (local $document i32)
+      (local $entry i32)
+      (call $search-in-index
+        (i32.const 1024) (i32.const 8)
+        (local.get $input) (local.get $length)
+        (i32.const 392))
+      (local.set $documents (i32.load (i32.const 392)))
+      (local.set $count (i32.load (i32.const 396)))
+      (if (i32.gt_u (local.get $count) (i32.const 16))
+        (then (local.set $count (i32.const 16))))
+      (block $done
+        (loop $next
+          (br_if $done (i32.ge_u (local.get $index) (local.get $count)))
+          (local.set $document
+            (i32.add (local.get $documents) (i32.mul (local.get $index) (i32.const 16))))
+          (local.set $entry
+            (i32.add (i32.const 512) (i32.mul (local.get $index) (i32.const 28))))
+          (i32.store (local.get $entry) (i32.load (local.get $document)))
+          (i32.store offset=4 (local.get $entry) (i32.load offset=4 (local.get $document)))
+          (i32.store offset=8 (local.get $entry) (i32.const 1104))
+          (i32.store offset=12 (local.get $entry) (i32.const 27))
+          (i32.store8 offset=16 (local.get $entry) (i32.const 0))
+          (local.set $index (i32.add (local.get $index) (i32.const 1)))
+          (br $next)))
+      (i32.store (i32.const 352) (i32.const 512))
+      (i32.store (i32.const 356) (local.get $count))
+      (i32.const 352))
+
+    (func (export "autocomplete") (param i32 i32) (result i32)
+      (i32.const 368))
+
+    (func (export "debug") (result i32)
+      (i32.store (i32.const 384) (i32.const 1032))
+      (i32.store (i32.const 388) (i32.const 8))
+      (i32.const 384))
+  )
+  (core instance $plugin (instantiate $Plugin
+    (with "env" (instance (export "memory" (memory $memory))))
+    (with "michel-api" (instance
+      (export "init-index" (func $init-index-lowered))
+      (export "new-document-for-index" (func $new-document-for-index-lowered))
+      (export "search-in-index" (func $search-in-index-lowered))))))
+
+  (type $plugin-info (record
+    (field "identifier" string)
+    (field "name" string)
+    (field "description" string)
+    (field "version" string)
+    (field "icon" (option string))
+    (field "url" (option string))))
+  (type $error (variant (case "invalid-value" string)))
+  (type $fs-access (record (field "host-path" string) (field "fsnotify" bool)))
+  (type $plugin-config (record (field "fs-access" (list $fs-access))))
+  (type $entry (record
+    (field "title" string)
+    (field "description" string)
+    (field "preview" (option string))))
+
+  (func $info (result $plugin-info)
+    (canon lift (core func $plugin "info") (memory $memory)))
+  (func $index (result (result (error $error)))
+    (canon lift (core func $plugin "index") (memory $memory)))
+  (func $config (result $plugin-config)
+    (canon lift (core func $plugin "config") (memory $memory)))
+  (func $update-config (param "config" $plugin-config) (result (result (error $error)))
+    (canon lift (core func $plugin "update-config") (memory $memory) (realloc $realloc)))
+  (func $for-input (param "input" string) (result (list $entry))
+    (canon lift (core func $plugin "for-input") (memory $memory) (realloc $realloc)))
+  (func $autocomplete (param "input" string) (result (option string))
+    (canon lift (core func $plugin "autocomplete") (memory $memory) (realloc $realloc)))
+  (func $debug (result string)
+    (canon lift (core func $plugin "debug") (memory $memory)))
+
+  (instance $plugin-api
+    (export "info" (func $info))
+    (export "index" (func $index))
+    (export "config" (func $config))
+    (export "update-config" (func $update-config))
+    (export "for-input" (func $for-input))
+    (export "autocomplete" (func $autocomplete))
+    (export "debug" (func $debug)))
+  (export "plugin-api" (instance $plugin-api))
+)
@@ -0,0 +1,102 @@
+;; A plugin built against michel:plugin@0.1.0 that loads, but traps when asked for entries or
+;; completions, to check that one failing plugin doesn't take the answers of the others down.
+(component
+  (core module $Libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 8192))
+    (func (export "realloc") (param i32 i32) (param $align i32) (param $size i32) (result i32)
+      (local $pointer i32)
+      (local.set $pointer
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get $align) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get $align))))
+      (global.set $heap (i32.add (local.get $pointer) (local.get $size)))
+      (local.get $pointer))
+  )
+  (core instance $libc (instantiate $Libc))
+  (alias core export $libc "memory" (core memory $memory))
+  (alias core export $libc "realloc" (core func $realloc))
+
+  (core module $Plugin
+    (import "env" "memory" (memory 1))
+
+    (data (i32.const 1024) "failing")
+    (data (i32.const 1032) "Failing")
+    (data (i32.const 1040) "Traps on every input")
+    (data (i32.const 1076) "1.0.0")
+
+    ;; Return areas, the options and results left to 0 standing for `none` and `ok`
+    (func (export "info") (result i32)
+      (i32.store (i32.const 256) (i32.const 1024))
+      (i32.store (i32.const 260) (i32.const 7))
+      (i32.store (i32.const 264) (i32.const 1032))
+      (i32.store (i32.const 268) (i32.const 7))
+      (i32.store (i32.const 272) (i32.const 1040))
+      (i32.store (i32.const 276) (i32.const 20))
+      (i32.store (i32.const 280) (i32.const 1076))
+      (i32.store (i32.const 284) (i32.const 5))
+      (i32.const 256))
+
+    (func (export "index") (result i32)
+      (i32.const 320))
+
+    (func (export "config") (result i32)
+      (i32.const 336))
+
+    (func (export "update-config") (param i32 i32) (result i32)
+      (i32.const 320))
+
+    (func (export "for-input") (param i32 i32) (result i32)
+      (unreachable))
+
+    (func (export "autocomplete") (param i32 i32) (result i32)
+      (unreachable))
+
+    (func (export "debug") (result i32)
+      (i32.store (i32.const 384) (i32.const 1032))
+      (i32.store (i32.const 388) (i32.const 7))
+      (i32.const 384))
+  )
+  (core instance $plugin (instantiate $Plugin
+    (with "env" (instance (export "memory" (memory $memory))))))
+
+  (type $plugin-info (record
+    (field "identifier" string)
+    (field "name" string)
+    (field "description" string)
+    (field "version" string)
+    (field "icon" (option string))
+    (field "url" (option string))))
+  (type $error (variant (case "invalid-value" string)))
+  (type $fs-access (record (field "host-path" string) (field "fsnotify" bool)))
+  (type $plugin-config (record (field "fs-access" (list $fs-access))))
+  (type $entry (record
+    (field "title" string)
+    (field "description" string)
+    (field "preview" (option string))))
+
+  (func $info (result $plugin-info)
+    (canon lift (core func $plugin "info") (memory $memory)))
+  (func $index (result (result (error $error)))
+    (canon lift (core func $plugin "index") (memory $memory)))
+  (func $config (result $plugin-config)
+    (canon lift (core func $plugin "config") (memory $memory)))
+  (func $update-config (param "config" $plugin-config) (result (result (error $error)))
+    (canon lift (core func $plugin "update-config") (memory $memory) (realloc $realloc)))
+  (func $for-input (param "input" string) (result (list $entry))
+    (canon lift (core func $plugin "for-input") (memory $memory) (realloc $realloc)))
+  (func $autocomplete (param "input" string) (result (option string))
+    (canon lift (core func $plugin "autocomplete") (memory $memory) (realloc $realloc)))
+  (func $debug (result string)
+    (canon lift (core func $plugin "debug") (memory $memory)))
+
+  (instance $plugin-api
+    (export "info" (func $info))
+    (export "index" (func $index))
+    (export "config" (func $config))
+    (export "update-config" (func $update-config))
+    (export "for-input" (func $for-input))
+    (export "autocomplete" (func $autocomplete))
+    (export "debug" (func $debug)))
+  (export "plugin-api" (instance $plugin-api))
+)
@@ -0,0 +1,104 @@
+use michel_core::grants::{Capability, Decision, Grants};
+use tempdir::TempDir;
+
+fn index(name: &str) -> Capability {
+    Capability::Index {
+        name: String::from(name),
+    }
+}
+
+#[test]
+fn requests_stay_pending_until_answered() {
+    let grants = Grants::in_memory();
+
+    assert_eq!(
+        grants.request("files", index("files")).unwrap(),
+        Decision::Pending
+    );
+    assert_eq!(
+        grants.request("files", index("files")).unwrap(),
+        Decision::Pending
+    );
+    assert_eq!(grants.pending().len(), 1);
+
+    grants.grant("files", index("files")).unwrap();
+
+    assert!(grants.pending().is_empty());
+    assert_eq!(
+        grants.request("files", index("files")).unwrap(),
+        Decision::Granted
+    );
+    assert!(!grants.is_granted("other", &index("files")));
+}
+
+#[test]
+fn revoking_denies() {
+    let grants = Grants::in_memory();
+    grants.grant("files", Capability::Clipboard).unwrap();
+    grants.deny("files", Capability::Clipboard).unwrap();
+
+    assert_eq!(
+        grants.decision("files", &Capability::Clipboard),
+        Decision::Denied
+    );
+    assert!(grants.granted("files").is_empty());
+    assert!(grants.pending().is_empty());
+}
+
+#[test]
+fn answers_are_saved() {
+    let folder = TempDir::new("grants").unwrap();
+    let path = folder.path().join("grants.toml");
+
+    let grants = Grants::load(&path).unwrap();
+    grants
+        .grant(
+            "files",
+            Capability::Fs {
+                path: "~/Documents".into(),
+                write: true,
+            },
+        )
+        .unwrap();
+    grants.grant("files", index("files")).unwrap();
+    grants
+        .deny(
+            "files",
+            Capability::Network {
+                domain: String::from("example.com"),
+            },
+        )
+        .unwrap();
+
+    let reloaded = Grants::load(&path).unwrap();
+    assert_eq!(reloaded.granted("files"), grants.granted("files"));
+    assert_eq!(
+        reloaded.decision(
+            "files",
+            &Capability::Network {
+                domain: String::from("example.com")
+            }
+        ),
+        Decision::Denied
+    );
+}
+
+#[test]
+fn pending_requests_are_saved() {
+    let folder = TempDir::new("grants").unwrap();
+    let path = folder.path().join("grants.toml");
+
+    // The plugin runs in one process, the user answers from another
+    Grants::load(&path)
+        .unwrap()
+        .request("files", index("files"))
+        .unwrap();
+
+    let answering = Grants::load(&path).unwrap();
+    assert!(answering.is_pending("files", &index("files")));
+    answering.grant("files", index("files")).unwrap();
+
+    let reloaded = Grants::load(&path).unwrap();
+    assert!(reloaded.pending().is_empty());
+    assert!(reloaded.is_granted("files", &index("files")));
+}
@@ -0,0 +1,235 @@
+use michel_core::http::{HttpClient, HttpLimits, HttpRequest};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/**
+    Stand-in for the servers plugins fetch from, answering each path with a canned response.
+    `/count` answers the number of requests it got so far, to tell cached responses apart, and
+    `/headers` the names of the headers it was sent.
+*/
+async fn serve() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let count = count.clone();
+
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match stream.read(&mut buffer).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split(' ').nth(1).unwrap_or_default();
+                let redirect = |host: &str, path: &str| {
+                    format!("Location: http://{}:{}{}\r\n", host, address.port(), path)
+                };
+                let (headers, body) = match path {
+                    "/count" => {
+                        let count = count.fetch_add(1, Ordering::SeqCst) + 1;
+                        (String::new(), count.to_string())
+                    }
+                    "/private" => (
+                        String::from("Cache-Control: no-store\r\n"),
+                        count.fetch_add(1, Ordering::SeqCst).to_string(),
+                    ),
+                    "/large" => (String::new(), "a".repeat(4096)),
+                    "/slow" => {
+                        tokio::time::sleep(Duration::from_secs(2)).await;
+                        (String::new(), String::from("late"))
+                    }
+                    "/headers" => (
+                        String::from("Cache-Control: no-store\r\n"),
+                        request
+                            .lines()
+                            .skip(1)
+                            .filter_map(|line| line.split_once(':'))
+                            .map(|(name, _)| name.to_lowercase())
+                            .collect::<Vec<String>>()
+                            .join(","),
+                    ),
+                    "/elsewhere" => (redirect("localhost", "/count"), String::new()),
+                    "/here" => (redirect("127.0.0.1", "/headers"), String::new()),
+                    "/away" => (redirect("localhost", "/headers"), String::new()),
+                    _ => (String::new(), String::from("hello")),
+                };
+                let status = match path {
+                    "/elsewhere" | "/here" | "/away" => "302 Found",
+                    _ => "200 OK",
+                };
+
+                let response = format!(
+                    "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    headers,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+
+    address
+}
+
+fn client() -> HttpClient {
+    HttpClient::new(HttpLimits {
+        timeout: Duration::from_millis(500),
+        max_response_size: 1024,
+        ..HttpLimits::default()
+    })
+    .unwrap()
+}
+
+fn granted() -> Vec<String> {
+    vec![String::from("127.0.0.1")]
+}
+
+#[tokio::test]
+async fn granted_domains_are_fetched() {
+    let address = serve().await;
+
+    let response = client()
+        .fetch(
+            HttpRequest::get(format!("http://{}/hello", address)),
+            &granted(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"hello");
+}
+
+#[tokio::test]
+async fn other_domains_are_refused() {
+    let address = serve().await;
+    let client = client();
+
+    let refused = client
+        .fetch(
+            HttpRequest::get(format!("http://localhost:{}/hello", address.port())),
+            &granted(),
+        )
+        .await;
+    assert!(refused.is_err());
+
+    // Redirects are checked as well
+    let redirected = client
+        .fetch(
+            HttpRequest::get(format!("http://{}/elsewhere", address)),
+            &granted(),
+        )
+        .await;
+    assert!(redirected.is_err());
+}
+
+#[tokio::test]
+async fn limits_are_enforced() {
+    let address = serve().await;
+    let client = client();
+
+    let large = client
+        .fetch(
+            HttpRequest::get(format!("http://{}/large", address)),
+            &granted(),
+        )
+        .await
+        .unwrap_err();
+    assert!(large.to_string().contains("larger than"), "{}", large);
+
+    let slow = client
+        .fetch(
+            HttpRequest::get(format!("http://{}/slow", address)),
+            &granted(),
+        )
+        .await
+        .unwrap_err();
+    assert!(slow.to_string().contains("timed out"), "{}", slow);
+}
+
+#[tokio::test]
+async fn responses_are_cached() {
+    let address = serve().await;
+    let client = client();
+    let domains = granted();
+    let fetch = |path: &str| {
+        client.fetch(
+            HttpRequest::get(format!("http://{}{}", address, path)),
+            &domains,
+        )
+    };
+
+    let first = fetch("/count").await.unwrap();
+    let second = fetch("/count").await.unwrap();
+    assert_eq!(first.body, second.body);
+
+    let first = fetch("/private").await.unwrap();
+    let second = fetch("/private").await.unwrap();
+    assert_ne!(first.body, second.body);
+}
+
+#[tokio::test]
+async fn revoked_domains_are_not_served_from_the_cache() {
+    let address = serve().await;
+    let client = client();
+    let request = || HttpRequest::get(format!("http://{}/count", address));
+
+    client.fetch(request(), &granted()).await.unwrap();
+
+    let revoked = client.fetch(request(), &[]).await.unwrap_err();
+    assert!(
+        revoked
+            .to_string()
+            .contains("not a domain the plugin was granted"),
+        "{}",
+        revoked
+    );
+}
+
+#[tokio::test]
+async fn credentials_only_follow_redirects_to_the_same_origin() {
+    let address = serve().await;
+    let client = client();
+    let domains = vec![String::from("127.0.0.1"), String::from("localhost")];
+    let fetch = |path: &str| {
+        let mut request = HttpRequest::get(format!("http://{}{}", address, path));
+        request.headers = ["Authorization", "Cookie", "Proxy-Authorization", "X-Plugin"]
+            .iter()
+            .map(|name| (String::from(*name), String::from("secret")))
+            .collect();
+
+        client.fetch(request, &domains)
+    };
+    let sent = |body: Vec<u8>| -> Vec<String> {
+        String::from_utf8(body)
+            .unwrap()
+            .split(',')
+            .map(String::from)
+            .collect()
+    };
+
+    let same = sent(fetch("/here").await.unwrap().body);
+    for name in ["authorization", "cookie", "proxy-authorization", "x-plugin"] {
+        assert!(same.iter().any(|sent| sent == name), "{:?}", same);
+    }
+
+    let other = sent(fetch("/away").await.unwrap().body);
+    for name in ["authorization", "cookie", "proxy-authorization"] {
+        assert!(!other.iter().any(|sent| sent == name), "{:?}", other);
+    }
+    assert!(other.iter().any(|sent| sent == "x-plugin"), "{:?}", other);
+}
@@ -0,0 +1,71 @@
+use michel_core::grants::{Capability, Grants};
+use michel_core::memory::MemoryPersistence;
+use michel_core::persistence::{Index, IndexMetadata, MichelPersistence};
+use michel_core::Plugin;
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+
+fn baseline_index() -> Index {
+    Index {
+        name: String::from("baseline"),
+    }
+}
+
+#[tokio::test]
+async fn plugins_only_search_their_own_indexes() {
+    let persistence = Arc::new(MemoryPersistence::new());
+
+    // Named like the index of the baseline plugin, but written by another one
+    persistence
+        .init_index(String::from("baseline"))
+        .await
+        .unwrap();
+    persistence
+        .set_metadata(
+            baseline_index(),
+            IndexMetadata {
+                plugin: String::from("other"),
+                plugin_version: String::from("1.0.0"),
+                schema_version: 0,
+            },
+        )
+        .await
+        .unwrap();
+    persistence
+        .add_document(
+            baseline_index(),
+            json!({"id": "secret", "title": "Another plugin's document"})
+                .as_object()
+                .cloned()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    persistence.flush(baseline_index()).await.unwrap();
+
+    // Even granted, the index can't be searched as the plugin didn't initialize it
+    let grants = Arc::new(Grants::in_memory());
+    grants
+        .grant(
+            "baseline",
+            Capability::Index {
+                name: String::from("baseline"),
+            },
+        )
+        .unwrap();
+    let plugin = Plugin::load_from_path(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/baseline-plugin.wat"),
+        persistence.clone(),
+        grants,
+    )
+    .await
+    .unwrap();
+    plugin.migrate_indexes(&persistence).await.unwrap();
+
+    assert!(plugin.entries_for_input("").await.unwrap().is_empty());
+    assert!(plugin.entries_for_input("secret").await.unwrap().is_empty());
+
+    // Nor taken over
+    assert!(plugin.index().await.is_err());
+}
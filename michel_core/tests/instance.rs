@@ -0,0 +1,89 @@
+use michel_core::grants::Capability;
+use michel_core::memory::MemoryPersistence;
+use michel_core::package::MANIFEST_FILE;
+use michel_core::signature::SignatureConfig;
+use michel_core::{MichelConfig, MichelInstance};
+use std::fs;
+use std::path::Path;
+use tempdir::TempDir;
+
+#[tokio::test]
+async fn broken_plugins_are_skipped() {
+    let folder = TempDir::new("plugins").unwrap();
+    let plugins_path = folder.path().to_path_buf();
+
+    let invalid_manifest = plugins_path.join("invalid");
+    fs::create_dir(&invalid_manifest).unwrap();
+    fs::write(invalid_manifest.join(MANIFEST_FILE), "identifier = ").unwrap();
+    fs::write(plugins_path.join("truncated.tar.gz"), b"\x1f\x8b").unwrap();
+    fs::write(plugins_path.join("garbage.wasm"), b"not a component").unwrap();
+    fs::write(plugins_path.join("notes.txt"), b"not a plugin either").unwrap();
+
+    let michel = MichelInstance::new(
+        MemoryPersistence::new(),
+        MichelConfig {
+            name: String::from("Michel"),
+            plugins_path,
+            grants_path: None,
+            kv_path: None,
+            signatures: SignatureConfig::default(),
+            registry: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert!(michel.plugins().is_empty());
+}
+
+#[tokio::test]
+async fn failing_plugins_are_left_out_of_the_entries() {
+    let folder = TempDir::new("plugins").unwrap();
+    let plugins_path = folder.path().to_path_buf();
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+
+    fs::copy(
+        fixtures.join("baseline-plugin.wat"),
+        plugins_path.join("baseline.wasm"),
+    )
+    .unwrap();
+    fs::copy(
+        fixtures.join("failing-plugin.wat"),
+        plugins_path.join("failing.wasm"),
+    )
+    .unwrap();
+
+    let michel = MichelInstance::new(
+        MemoryPersistence::new(),
+        MichelConfig {
+            name: String::from("Michel"),
+            plugins_path,
+            grants_path: None,
+            kv_path: None,
+            signatures: SignatureConfig::default(),
+            registry: None,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(michel.plugins().len(), 2);
+
+    michel
+        .grants()
+        .grant(
+            "baseline",
+            Capability::Index {
+                name: String::from("baseline"),
+            },
+        )
+        .unwrap();
+    michel
+        .plugin(String::from("baseline"))
+        .unwrap()
+        .index()
+        .await
+        .unwrap();
+
+    let entries = michel.entries_for_input("baseline").await.unwrap();
+    assert_eq!(entries.len(), 1);
+}
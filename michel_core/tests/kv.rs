@@ -0,0 +1,49 @@
+use michel_core::kv::{KvLimits, KvStore};
+use tempdir::TempDir;
+
+#[test]
+fn values_are_kept_from_a_run_to_the_next() {
+    let root = TempDir::new("kv").unwrap();
+
+    let store = KvStore::open(root.path(), "files", KvLimits::default()).unwrap();
+    store.set("cursor", b"42".to_vec()).unwrap();
+    store.set("token/github", b"secret".to_vec()).unwrap();
+    store.set("token/gitlab", b"other".to_vec()).unwrap();
+    store.delete("token/gitlab").unwrap();
+
+    let store = KvStore::open(root.path(), "files", KvLimits::default()).unwrap();
+    assert_eq!(store.get("cursor"), Some(b"42".to_vec()));
+    assert_eq!(store.list_prefix("token/"), vec!["token/github"]);
+    assert_eq!(store.get("token/gitlab"), None);
+
+    // Plugins don't see the keys of one another
+    let other = KvStore::open(root.path(), "notes", KvLimits::default()).unwrap();
+    assert_eq!(other.get("cursor"), None);
+}
+
+#[test]
+fn quotas_are_enforced() {
+    let store = KvStore::in_memory(KvLimits {
+        max_key_size: 8,
+        max_value_size: 16,
+        quota: 32,
+    });
+
+    assert!(store.set("a very long key", Vec::new()).is_err());
+    assert!(store.set("large", vec![0; 17]).is_err());
+
+    store.set("first", vec![0; 16]).unwrap();
+    assert!(store.set("second", vec![0; 16]).is_err());
+
+    // Replacing a value only counts the difference
+    store.set("first", vec![1; 16]).unwrap();
+    assert_eq!(store.usage(), 21);
+}
+
+#[test]
+fn storage_stays_in_its_folder() {
+    let root = TempDir::new("kv").unwrap();
+
+    assert!(KvStore::open(root.path(), "..", KvLimits::default()).is_err());
+    assert!(KvStore::open(root.path(), "../files", KvLimits::default()).is_err());
+}
@@ -0,0 +1,3 @@
+use michel_core::memory::MemoryPersistence;
+
+michel_core::persistence_conformance_tests!(MemoryPersistence::new());
@@ -0,0 +1,175 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use michel_core::grants::Capability;
+use michel_core::package::{
+    api_version_for, PluginManifest, PluginPackage, HOST_API_VERSION, MANIFEST_FILE,
+    MAX_UNPACKED_SIZE,
+};
+use semver::Version;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use tempdir::TempDir;
+
+const WASM: &[u8] = b"\0asm";
+
+fn manifest(min_host_api: &str, wasm: &str) -> String {
+    format!(
+        r#"
+identifier = "files"
+name = "Files"
+version = "1.2.0"
+min-host-api = "{}"
+wasm = "{}"
+
+[capabilities]
+fs = [{{ path = "~/Documents", write = true }}]
+network = ["api.example.com"]
+indexes = ["files"]
+"#,
+        min_host_api, wasm
+    )
+}
+
+fn write_package(folder: &Path, manifest: &str) {
+    fs::write(folder.join(MANIFEST_FILE), manifest).unwrap();
+    fs::write(folder.join("files.wasm"), WASM).unwrap();
+}
+
+#[test]
+fn folder_package_is_read() {
+    let folder = TempDir::new("package").unwrap();
+    write_package(
+        folder.path(),
+        &manifest(&HOST_API_VERSION.to_string(), "files.wasm"),
+    );
+
+    assert!(PluginPackage::is_package(folder.path()));
+
+    let package = PluginPackage::open(folder.path()).unwrap();
+    assert_eq!(package.manifest.identifier, "files");
+    assert_eq!(package.manifest.capabilities.indexes, vec!["files"]);
+    assert!(!package.manifest.capabilities.clipboard);
+    assert_eq!(package.wasm, WASM);
+    assert!(package.icon.is_none());
+}
+
+#[test]
+fn archive_package_is_read() {
+    let folder = TempDir::new("package").unwrap();
+    let content = folder.path().join("content");
+    fs::create_dir(&content).unwrap();
+    write_package(
+        &content,
+        &manifest(&HOST_API_VERSION.to_string(), "files.wasm"),
+    );
+
+    let archive_path = folder.path().join("files.tar.gz");
+    let mut archive = tar::Builder::new(GzEncoder::new(
+        File::create(&archive_path).unwrap(),
+        Compression::default(),
+    ));
+    archive.append_dir_all(".", &content).unwrap();
+    archive.into_inner().unwrap().finish().unwrap();
+
+    let package = PluginPackage::open(&archive_path).unwrap();
+    assert_eq!(package.manifest.identifier, "files");
+    assert_eq!(package.wasm, WASM);
+}
+
+#[test]
+fn newer_host_api_is_refused() {
+    assert!(PluginManifest::parse(&manifest("0.99.0", "files.wasm")).is_err());
+    assert!(PluginManifest::parse(&manifest("2.0.0", "files.wasm")).is_err());
+}
+
+#[test]
+fn older_world_versions_are_linked() {
+    assert_eq!(
+        api_version_for(&Version::new(0, 1, 0)),
+        Some(Version::new(0, 1, 0))
+    );
+    assert_eq!(
+        api_version_for(&Version::new(0, 2, 0)),
+        Some(Version::new(0, 2, 0))
+    );
+    assert_eq!(api_version_for(&HOST_API_VERSION), Some(HOST_API_VERSION));
+    assert!(PluginManifest::parse(&manifest("0.1.0", "files.wasm")).is_ok());
+    assert!(PluginManifest::parse(&manifest("0.2.0", "files.wasm")).is_ok());
+}
+
+#[test]
+fn unsupported_world_version_is_explained() {
+    let error = PluginManifest::parse(&manifest("0.99.0", "files.wasm"))
+        .unwrap_err()
+        .to_string();
+
+    assert!(error.contains("michel:plugin@0.99.0"));
+    assert!(error.contains(&format!("michel:plugin@{}", HOST_API_VERSION)));
+}
+
+#[test]
+fn files_outside_of_the_package_are_refused() {
+    assert!(
+        PluginManifest::parse(&manifest(&HOST_API_VERSION.to_string(), "../files.wasm")).is_err()
+    );
+    assert!(
+        PluginManifest::parse(&manifest(&HOST_API_VERSION.to_string(), "/files.wasm")).is_err()
+    );
+}
+
+#[test]
+fn missing_wasm_is_refused() {
+    let folder = TempDir::new("package").unwrap();
+    fs::write(
+        folder.path().join(MANIFEST_FILE),
+        manifest(&HOST_API_VERSION.to_string(), "other.wasm"),
+    )
+    .unwrap();
+
+    assert!(PluginPackage::open(folder.path()).is_err());
+}
+
+#[test]
+fn unknown_capabilities_are_refused() {
+    let content = format!(
+        "{}camera = true\n",
+        manifest(&HOST_API_VERSION.to_string(), "files.wasm")
+    );
+
+    assert!(PluginManifest::parse(&content).is_err());
+}
+
+#[test]
+fn oversized_archives_are_refused() {
+    // Only the header of the entry is written, announcing more than the limit
+    let mut header = tar::Header::new_gnu();
+    header.set_path("files.wasm").unwrap();
+    header.set_size(MAX_UNPACKED_SIZE + 1);
+    header.set_cksum();
+
+    let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    archive.append(&header, std::io::empty()).unwrap();
+    let bytes = archive.into_inner().unwrap().finish().unwrap();
+
+    let error = PluginPackage::from_archive_bytes(&bytes).err().unwrap();
+    assert!(
+        error.to_string().contains("unpacks to more than"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn folders_are_read_only_unless_writable() {
+    let content =
+        manifest(&HOST_API_VERSION.to_string(), "files.wasm").replace(", write = true", "");
+
+    let manifest = PluginManifest::parse(&content).unwrap();
+    assert_eq!(
+        manifest.capabilities.list()[0],
+        Capability::Fs {
+            path: PathBuf::from("~/Documents"),
+            write: false,
+        }
+    );
+}
@@ -0,0 +1,112 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use michel_core::memory::MemoryPersistence;
+use michel_core::package::{HOST_API_VERSION, MANIFEST_FILE};
+use michel_core::registry::{Registry, INDEX_FILE};
+use michel_core::signature::SignatureConfig;
+use michel_core::{MichelConfig, MichelInstance};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use tempdir::TempDir;
+
+fn package(version: &str) -> Vec<u8> {
+    let manifest = format!(
+        "identifier = \"files\"\nname = \"Files\"\nversion = \"{}\"\nmin-host-api = \"{}\"\nwasm = \"files.wasm\"\n",
+        version, HOST_API_VERSION
+    );
+
+    let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (name, content) in [
+        (MANIFEST_FILE, manifest.as_bytes()),
+        ("files.wasm", b"\0asm"),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive.append_data(&mut header, name, content).unwrap();
+    }
+
+    archive.into_inner().unwrap().finish().unwrap()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// A registry with versions 1.0.0 and 1.1.0 of `files`, and a 2.0.0 needing a newer host.
+fn write_registry(folder: &Path, checksum: Option<&str>) {
+    let (old, new) = (package("1.0.0"), package("1.1.0"));
+    fs::write(folder.join("files-1.0.0.tar.gz"), &old).unwrap();
+    fs::write(folder.join("files-1.1.0.tar.gz"), &new).unwrap();
+
+    let index = serde_json::json!({
+        "plugins": [{
+            "identifier": "files",
+            "name": "Files",
+            "versions": [
+                { "version": "1.0.0", "min-host-api": HOST_API_VERSION.to_string(), "package": "files-1.0.0.tar.gz", "sha256": hex(&old) },
+                { "version": "1.1.0", "min-host-api": HOST_API_VERSION.to_string(), "package": "files-1.1.0.tar.gz", "sha256": checksum.map(String::from).unwrap_or_else(|| hex(&new)) },
+                { "version": "2.0.0", "min-host-api": "99.0.0", "package": "files-2.0.0.tar.gz", "sha256": "" }
+            ]
+        }]
+    });
+    fs::write(folder.join(INDEX_FILE), index.to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn newest_compatible_version_is_downloaded() {
+    let folder = TempDir::new("registry").unwrap();
+    write_registry(folder.path(), None);
+
+    let registry = Registry::new(&folder.path().to_string_lossy()).unwrap();
+    let index = registry.index().await.unwrap();
+    let latest = index.plugin("files").unwrap().latest_compatible().unwrap();
+    assert_eq!(latest.version.to_string(), "1.1.0");
+
+    let (_, package) = registry.download(latest).await.unwrap();
+    assert_eq!(package.manifest.identifier, "files");
+    assert_eq!(package.manifest.version.to_string(), "1.1.0");
+}
+
+#[tokio::test]
+async fn package_not_matching_checksum_is_refused() {
+    let folder = TempDir::new("registry").unwrap();
+    write_registry(folder.path(), Some(&hex(b"something else")));
+
+    let registry = Registry::new(&folder.path().join(INDEX_FILE).to_string_lossy()).unwrap();
+    let index = registry.index().await.unwrap();
+    let latest = index.plugin("files").unwrap().latest_compatible().unwrap();
+
+    assert!(registry.download(latest).await.is_err());
+}
+
+#[tokio::test]
+async fn packages_failing_to_load_are_not_installed() {
+    let folder = TempDir::new("registry").unwrap();
+    write_registry(folder.path(), None);
+    let plugins = TempDir::new("plugins").unwrap();
+
+    let michel = MichelInstance::new(
+        MemoryPersistence::new(),
+        MichelConfig {
+            name: String::from("Michel"),
+            plugins_path: plugins.path().to_path_buf(),
+            grants_path: None,
+            kv_path: None,
+            signatures: SignatureConfig::default(),
+            registry: Some(folder.path().to_string_lossy().into_owned()),
+        },
+    )
+    .await
+    .unwrap();
+
+    // The packaged wasm is only a header, so the plugin can't be instantiated
+    assert!(michel.install("files").await.is_err());
+    assert!(michel.plugins().is_empty());
+    assert_eq!(fs::read_dir(plugins.path()).unwrap().count(), 0);
+}
@@ -0,0 +1,107 @@
+use michel_core::package::{PluginPackage, HOST_API_VERSION, MANIFEST_FILE, SIGNATURE_FILE};
+use michel_core::signature::{
+    public_key, sign_package, SignatureConfig, SignaturePolicy, SignatureStatus, TrustedKey,
+};
+use std::fs;
+use std::path::Path;
+use tempdir::TempDir;
+
+const SECRET_KEY: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+const OTHER_SECRET_KEY: &str = "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb";
+
+fn write_package(folder: &Path) {
+    fs::write(
+        folder.join(MANIFEST_FILE),
+        format!(
+            "identifier = \"files\"\nname = \"Files\"\nversion = \"1.0.0\"\nmin-host-api = \"{}\"\n",
+            HOST_API_VERSION
+        ),
+    )
+    .unwrap();
+    fs::write(folder.join("plugin.wasm"), b"\0asm").unwrap();
+}
+
+fn trusting(secret_key: &str) -> SignatureConfig {
+    SignatureConfig {
+        policy: SignaturePolicy::Refuse,
+        trusted_keys: vec![TrustedKey {
+            name: String::from("team"),
+            key: public_key(secret_key).unwrap(),
+        }],
+    }
+}
+
+#[test]
+fn signed_package_is_trusted() {
+    let folder = TempDir::new("signature").unwrap();
+    write_package(folder.path());
+
+    let public = sign_package(folder.path(), SECRET_KEY).unwrap();
+    assert_eq!(public, public_key(SECRET_KEY).unwrap());
+
+    let package = PluginPackage::open(folder.path()).unwrap();
+    assert_eq!(
+        trusting(SECRET_KEY).verify(&package).unwrap(),
+        SignatureStatus::Trusted {
+            key: String::from("team")
+        }
+    );
+    assert_eq!(
+        trusting(OTHER_SECRET_KEY).verify(&package).unwrap(),
+        SignatureStatus::Untrusted
+    );
+}
+
+#[test]
+fn altered_package_is_untrusted() {
+    let folder = TempDir::new("signature").unwrap();
+    write_package(folder.path());
+    sign_package(folder.path(), SECRET_KEY).unwrap();
+
+    fs::write(folder.path().join("plugin.wasm"), b"\0asm\x01").unwrap();
+
+    let package = PluginPackage::open(folder.path()).unwrap();
+    assert_eq!(
+        trusting(SECRET_KEY).verify(&package).unwrap(),
+        SignatureStatus::Untrusted
+    );
+}
+
+#[test]
+fn package_without_signature_is_unsigned() {
+    let folder = TempDir::new("signature").unwrap();
+    write_package(folder.path());
+
+    let package = PluginPackage::open(folder.path()).unwrap();
+    assert_eq!(
+        trusting(SECRET_KEY).verify(&package).unwrap(),
+        SignatureStatus::Unsigned
+    );
+}
+
+#[test]
+fn unreadable_signature_is_untrusted() {
+    let folder = TempDir::new("signature").unwrap();
+    write_package(folder.path());
+    fs::write(folder.path().join(SIGNATURE_FILE), "not hexadecimal").unwrap();
+
+    let package = PluginPackage::open(folder.path()).unwrap();
+    assert_eq!(
+        trusting(SECRET_KEY).verify(&package).unwrap(),
+        SignatureStatus::Untrusted
+    );
+}
+
+#[test]
+fn malformed_trusted_keys_are_refused_on_load() {
+    let folder = TempDir::new("signature").unwrap();
+    let path = folder.path().join("signatures.toml");
+    fs::write(
+        &path,
+        "policy = \"refuse\"\n\n[[trusted-keys]]\nname = \"team\"\nkey = \"3d40\"\n",
+    )
+    .unwrap();
+
+    let error = SignatureConfig::load(&path).unwrap_err();
+    assert!(error.to_string().contains("team"), "{}", error);
+}
@@ -0,0 +1,149 @@
+use michel_core::persistence::{
+    datetime_fields, decode_vector, encode_vector, timestamp_of, PersistedDocument,
+};
+use michel_core::types::{Document, Field, Node, NodeValue, Value};
+use serde_json::json;
+
+fn node(key: Option<&str>, value: NodeValue) -> Node {
+    Node {
+        key: key.map(String::from),
+        value,
+    }
+}
+
+fn round_trip(value: serde_json::Value) -> serde_json::Value {
+    let flattened = Value::try_from(value).unwrap();
+
+    serde_json::Value::try_from(flattened).unwrap()
+}
+
+#[test]
+fn nested_values_round_trip() {
+    let values = [
+        json!([1, [2, [3, []]], {"a": [true, null]}]),
+        json!({"tags": ["rust", "wasm"], "author": {"name": "Ada", "links": {"site": "ada.dev"}}}),
+        json!({"empty": {}, "list": [], "float": 1.5}),
+        json!([[[["deep"]]]]),
+    ];
+
+    for value in values {
+        assert_eq!(round_trip(value.clone()), value);
+    }
+}
+
+#[test]
+fn nested_values_are_flattened_in_pre_order() {
+    let value = Value::try_from(json!({"a": [1, {"b": "c"}], "d": false})).unwrap();
+
+    let nodes = match value {
+        Value::Record(nodes) => nodes,
+        _ => panic!("a json object is a record"),
+    };
+    let expected = [
+        node(Some("a"), NodeValue::List(2)),
+        node(None, NodeValue::Number(1)),
+        node(None, NodeValue::Record(1)),
+        node(Some("b"), NodeValue::Text(String::from("c"))),
+        node(Some("d"), NodeValue::Boolean(false)),
+    ];
+
+    assert_eq!(nodes.len(), expected.len());
+    for (node, expected) in nodes.iter().zip(expected.iter()) {
+        assert_eq!(node.key, expected.key);
+        assert_eq!(format!("{:?}", node.value), format!("{:?}", expected.value));
+    }
+}
+
+#[test]
+fn malformed_nodes_are_refused() {
+    // The list announces more children than given
+    let missing = Value::List(vec![node(None, NodeValue::List(3))]);
+    assert!(serde_json::Value::try_from(missing).is_err());
+
+    let unnamed = Value::Record(vec![node(None, NodeValue::Null)]);
+    assert!(serde_json::Value::try_from(unnamed).is_err());
+
+    let twice = Value::Record(vec![
+        node(Some("a"), NodeValue::Null),
+        node(Some("a"), NodeValue::Null),
+    ]);
+    assert!(serde_json::Value::try_from(twice).is_err());
+
+    // Keys the host uses for datetimes can't be faked
+    let reserved = Value::Record(vec![
+        node(
+            Some("_michel_datetime"),
+            NodeValue::Text(String::from("2023-01-01T00:00:00Z")),
+        ),
+        node(Some("_michel_timestamp"), NodeValue::Number(1672531200)),
+    ]);
+    assert!(serde_json::Value::try_from(reserved).is_err());
+}
+
+#[test]
+fn datetimes_are_found_in_nested_records() {
+    let document = Document {
+        identifier: String::from("1"),
+        fields: vec![
+            Field {
+                name: String::from("modified"),
+                value: Value::Datetime(String::from("2023-01-01T00:00:00Z")),
+            },
+            Field {
+                name: String::from("author"),
+                value: Value::Record(vec![
+                    node(
+                        Some("born"),
+                        NodeValue::Datetime(String::from("1815-12-10T00:00:00Z")),
+                    ),
+                    node(Some("name"), NodeValue::Text(String::from("Ada"))),
+                ]),
+            },
+        ],
+        embedding: None,
+    };
+
+    let persisted = PersistedDocument::try_from(document).unwrap();
+    let mut fields = datetime_fields(&persisted);
+    fields.sort();
+
+    assert_eq!(fields, vec!["author.born", "modified"]);
+    assert_eq!(timestamp_of(&persisted, "modified"), Some(1672531200));
+    assert_eq!(timestamp_of(&persisted, "author.born"), Some(-4861728000));
+}
+
+#[test]
+fn documents_round_trip() {
+    let document = Document {
+        identifier: String::from("1"),
+        fields: vec![Field {
+            name: String::from("author"),
+            value: Value::try_from(json!({"name": "Ada", "books": [{"title": "Notes"}]})).unwrap(),
+        }],
+        embedding: None,
+    };
+
+    let persisted = PersistedDocument::try_from(document).unwrap();
+    assert_eq!(
+        persisted["author"],
+        json!({"name": "Ada", "books": [{"title": "Notes"}]})
+    );
+
+    let document = Document::try_from(&persisted).unwrap();
+    assert_eq!(document.identifier, "1");
+    assert_eq!(
+        serde_json::Value::try_from(document.fields[0].value.clone()).unwrap(),
+        persisted["author"]
+    );
+}
+
+#[test]
+fn vectors_are_stored_in_one_byte_format() {
+    let vector = vec![1.0, -0.5, 0.25];
+    let bytes = encode_vector(&vector);
+
+    // Stored vectors outlive the host that wrote them, so the layout is pinned
+    assert_eq!(&bytes[..4], &[0, 0, 128, 63]);
+    assert_eq!(bytes.len(), 12);
+    assert_eq!(decode_vector(&bytes), vector);
+}
@@ -0,0 +1,193 @@
+/*!
+    Where the desktop app gets its entries from : a running daemon, so that indexing goes on
+    while the app is closed, or else its own embedded instance.
+*/
+
+use crate::config::DesktopConfig;
+use crate::{Michel, PLUGINS_FOLDER};
+use anyhow::{anyhow, Result};
+use michel_core::backend::Backends;
+use michel_core::grants::{Capability, CapabilityRequest};
+use michel_core::persistence::IndexStats;
+use michel_core::registry::AvailablePlugin;
+use michel_core::signature::SignatureConfig;
+use michel_core::{Entry, MichelConfig, MichelInstance};
+use michel_index::MilliPersistence;
+use michel_index_sqlite::SqlitePersistence;
+use michel_server::api::DisplayedPlugin;
+#[cfg(unix)]
+use michel_server::client::Client;
+
+pub enum Backend {
+    Embedded(Michel),
+    #[cfg(unix)]
+    Daemon(Client),
+}
+
+impl Backend {
+    /// Connects to the daemon if the config asks for it, embedding Michel if none answers.
+    pub async fn load(config: &DesktopConfig) -> Result<Backend> {
+        #[cfg(unix)]
+        if config.daemon() {
+            let socket = match config.daemon_socket() {
+                Some(socket) => socket.to_path_buf(),
+                None => MichelConfig::default_socket_path()?,
+            };
+
+            match Client::connect(&socket).await {
+                Ok(client) => {
+                    info!("connected to the daemon on {}", socket.to_string_lossy());
+                    return Ok(Backend::Daemon(client));
+                }
+                Err(error) => warn!("no daemon to connect to, embedding michel : {}", error),
+            }
+        }
+
+        Ok(Backend::Embedded(embedded(config).await?))
+    }
+
+    pub async fn entries_for_input(&self, input: &str) -> Result<Vec<Entry>> {
+        match self {
+            Backend::Embedded(michel) => michel.entries_for_input(input).await,
+            #[cfg(unix)]
+            Backend::Daemon(client) => Ok(client
+                .query(input)
+                .await?
+                .into_iter()
+                .flat_map(|results| results.entries)
+                .collect()),
+        }
+    }
+
+    pub async fn plugins(&self) -> Result<Vec<DisplayedPlugin>> {
+        match self {
+            Backend::Embedded(michel) => Ok(michel
+                .plugins()
+                .iter()
+                .map(|plugin| DisplayedPlugin::from(plugin.as_ref()))
+                .collect()),
+            #[cfg(unix)]
+            Backend::Daemon(client) => client.plugins().await,
+        }
+    }
+
+    /// Waits for the indexing to end, then returns the stats of the indexes of the plugin.
+    pub async fn index_plugin(&self, identifier: String) -> Result<Vec<IndexStats>> {
+        match self {
+            Backend::Embedded(michel) => {
+                let plugin = michel
+                    .plugin(identifier.clone())
+                    .ok_or(anyhow!("no plugin {}", identifier))?;
+                plugin.index().await?;
+
+                plugin.index_stats().await
+            }
+            #[cfg(unix)]
+            Backend::Daemon(client) => client.index_plugin(&identifier).await,
+        }
+    }
+
+    pub async fn plugin_indexes(&self, identifier: String) -> Result<Vec<IndexStats>> {
+        match self {
+            Backend::Embedded(michel) => {
+                michel
+                    .plugin(identifier.clone())
+                    .ok_or(anyhow!("no plugin {}", identifier))?
+                    .index_stats()
+                    .await
+            }
+            #[cfg(unix)]
+            Backend::Daemon(client) => client.plugin_indexes(&identifier).await,
+        }
+    }
+
+    pub async fn pending_capabilities(&self) -> Result<Vec<CapabilityRequest>> {
+        match self {
+            Backend::Embedded(michel) => Ok(michel.grants().pending()),
+            #[cfg(unix)]
+            Backend::Daemon(client) => client.pending_capabilities().await,
+        }
+    }
+
+    pub async fn granted_capabilities(&self, identifier: String) -> Result<Vec<Capability>> {
+        match self {
+            Backend::Embedded(michel) => Ok(michel.grants().granted(&identifier)),
+            #[cfg(unix)]
+            Backend::Daemon(client) => client.granted_capabilities(&identifier).await,
+        }
+    }
+
+    pub async fn grant_capability(&self, identifier: String, capability: Capability) -> Result<()> {
+        match self {
+            Backend::Embedded(michel) if !michel.asked_for(&identifier, &capability) => {
+                Err(anyhow!("{} didn't ask for this capability", identifier))
+            }
+            Backend::Embedded(michel) => michel.grants().grant(&identifier, capability),
+            #[cfg(unix)]
+            Backend::Daemon(client) => client.grant_capability(&identifier, capability).await,
+        }
+    }
+
+    /// Refuses a pending capability, or revokes a granted one.
+    pub async fn deny_capability(&self, identifier: String, capability: Capability) -> Result<()> {
+        match self {
+            Backend::Embedded(michel) => michel.grants().deny(&identifier, capability),
+            #[cfg(unix)]
+            Backend::Daemon(client) => client.deny_capability(&identifier, capability).await,
+        }
+    }
+
+    /// Plugins of the registry, from the daemon's config when connected to one.
+    pub async fn available_plugins(&self) -> Result<Vec<AvailablePlugin>> {
+        match self {
+            Backend::Embedded(michel) => michel.available_plugins().await,
+            #[cfg(unix)]
+            Backend::Daemon(client) => client.available_plugins().await,
+        }
+    }
+
+    pub async fn install_plugin(&self, identifier: String) -> Result<()> {
+        match self {
+            Backend::Embedded(michel) => michel.install(&identifier).await,
+            #[cfg(unix)]
+            Backend::Daemon(client) => client.install_plugin(&identifier).await,
+        }
+    }
+
+    /// Returns whether a newer version was installed.
+    pub async fn update_plugin(&self, identifier: String) -> Result<bool> {
+        match self {
+            Backend::Embedded(michel) => michel.update(&identifier).await,
+            #[cfg(unix)]
+            Backend::Daemon(client) => client.update_plugin(&identifier).await,
+        }
+    }
+
+    pub async fn uninstall_plugin(&self, identifier: String) -> Result<()> {
+        match self {
+            Backend::Embedded(michel) => michel.uninstall(&identifier).await,
+            #[cfg(unix)]
+            Backend::Daemon(client) => client.uninstall_plugin(&identifier).await,
+        }
+    }
+}
+
+async fn embedded(config: &DesktopConfig) -> Result<Michel> {
+    let persistence = config.persistence().open(&Backends {
+        milli: MilliPersistence::open_backend,
+        sqlite: SqlitePersistence::open_backend,
+    })?;
+
+    MichelInstance::new(
+        persistence,
+        MichelConfig {
+            name: "Michel".to_string(),
+            plugins_path: PLUGINS_FOLDER.clone(),
+            grants_path: Some(MichelConfig::default_grants_path()?),
+            kv_path: Some(MichelConfig::default_kv_path()?),
+            signatures: SignatureConfig::load(MichelConfig::default_signatures_path()?)?,
+            registry: config.registry().map(String::from),
+        },
+    )
+    .await
+}
@@ -1,5 +1,6 @@
 use anyhow::{anyhow, Result};
 use lazy_static::lazy_static;
+use michel_core::backend::PersistenceConfig;
 use serde::{Deserialize, Serialize};
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -16,17 +17,49 @@ const MICHEL_CONFIG_FILENAME: &str = "config.toml";
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct DesktopConfig {
     username: String,
+    /// Shared with the other frontends, so that they all search the same indexes
+    #[serde(flatten)]
+    persistence: PersistenceConfig,
+    /// Connect to a running daemon instead of embedding Michel, if one answers
+    #[serde(default)]
+    daemon: bool,
+    /// Socket of the daemon, the default one of `michel_server --daemon` if unset
+    #[serde(default)]
+    daemon_socket: Option<PathBuf>,
+    /// URL, or local folder, of the registry index plugins are browsed from
+    #[serde(default)]
+    registry: Option<String>,
 }
 
 impl Default for DesktopConfig {
     fn default() -> Self {
         DesktopConfig {
             username: String::from("michel"),
+            persistence: PersistenceConfig::default(),
+            daemon: false,
+            daemon_socket: None,
+            registry: None,
         }
     }
 }
 
 impl DesktopConfig {
+    pub fn persistence(&self) -> &PersistenceConfig {
+        &self.persistence
+    }
+
+    pub fn daemon(&self) -> bool {
+        self.daemon
+    }
+
+    pub fn daemon_socket(&self) -> Option<&Path> {
+        self.daemon_socket.as_deref()
+    }
+
+    pub fn registry(&self) -> Option<&str> {
+        self.registry.as_deref()
+    }
+
     pub fn load() -> Result<DesktopConfig> {
         let config_file_content = fs::read_to_string(MICHEL_CONFIG_PATH.as_path())?;
         Ok(toml::from_str(&config_file_content)?)
@@ -0,0 +1,80 @@
+use crate::backend::Backend;
+use michel_core::grants::{Capability, CapabilityRequest};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Wry};
+
+const PENDING_POLL_INTERVAL: Duration = Duration::from_secs(2);
+pub const CAPABILITIES_REQUESTED_EVENT: &str = "capabilities-requested";
+
+/**
+    Opens the settings, where the consent dialog is, each time plugins ask for capabilities
+    the user wasn't asked about yet. Polled, as requests may come from the daemon.
+*/
+pub fn watch_pending_capabilities(app: AppHandle<Wry>) {
+    tauri::async_runtime::spawn(async move {
+        let mut known: Vec<CapabilityRequest> = Vec::new();
+
+        loop {
+            let pending = match app.state::<Backend>().pending_capabilities().await {
+                Ok(pending) => pending,
+                Err(error) => {
+                    warn!("can't fetch pending capabilities : {}", error);
+                    Vec::new()
+                }
+            };
+
+            if pending.iter().any(|request| !known.contains(request)) {
+                crate::show_settings(&app);
+                let _ = app.emit_all(CAPABILITIES_REQUESTED_EVENT, &pending);
+            }
+
+            known = pending;
+            tokio::time::sleep(PENDING_POLL_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_pending_capabilities(
+    michel: tauri::State<'_, Backend>,
+) -> Result<Vec<CapabilityRequest>, String> {
+    michel
+        .pending_capabilities()
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+pub async fn get_granted_capabilities(
+    identifier: String,
+    michel: tauri::State<'_, Backend>,
+) -> Result<Vec<Capability>, String> {
+    michel
+        .granted_capabilities(identifier)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+pub async fn grant_capability(
+    identifier: String,
+    capability: Capability,
+    michel: tauri::State<'_, Backend>,
+) -> Result<(), String> {
+    michel
+        .grant_capability(identifier, capability)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+pub async fn deny_capability(
+    identifier: String,
+    capability: Capability,
+    michel: tauri::State<'_, Backend>,
+) -> Result<(), String> {
+    michel
+        .deny_capability(identifier, capability)
+        .await
+        .map_err(|error| error.to_string())
+}
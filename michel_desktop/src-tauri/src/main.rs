@@ -10,9 +10,11 @@ use std::fs::File;
 use std::path::{Path, PathBuf};
 use std::{env, fs, process};
 
+use crate::backend::Backend;
+use crate::config::DesktopConfig;
 use anyhow::{anyhow, Result};
-use michel_core::{MichelConfig, MichelInstance};
-use michel_index::MilliPersistence;
+use michel_core::persistence::MichelPersistence;
+use michel_core::MichelInstance;
 use serde::de::IntoDeserializer;
 use tauri::CustomMenuItem;
 use tauri::{AppHandle, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu, Wry};
@@ -27,10 +29,15 @@ lazy_static! {
 const MICHEL_CONFIG_FOLDER: &str = "michel";
 //const MICHEL_CONFIG_FILENAME: &str = "config.toml";
 
+mod backend;
 mod config;
+mod consent;
+mod registry;
 mod search_bar;
 mod settings;
 
+pub type Michel = MichelInstance<Box<dyn MichelPersistence>>;
+
 const PLUGINS_CONFIG_FILENAME: &str = "plugins.toml";
 const PLUGINS_FOLDER_NAME: &str = "plugins";
 
@@ -126,14 +133,9 @@ async fn main() -> Result<()> {
 
     let tray = setup_system_tray();
 
-    let instance = MichelInstance::new(
-        MilliPersistence::new()?,
-        MichelConfig {
-            name: "Michel".to_string(),
-            plugins_path: PLUGINS_FOLDER.clone(),
-        },
-    )
-    .await?;
+    let config = DesktopConfig::load().unwrap_or_default();
+
+    let backend = Backend::load(&config).await?;
 
     tauri::Builder::default()
         .plugin(
@@ -148,13 +150,23 @@ async fn main() -> Result<()> {
             app.set_activation_policy(tauri::ActivationPolicy::Accessory);
 
             search_bar::register_search_shortcut(app.handle());
+            consent::watch_pending_capabilities(app.handle());
 
             Ok(())
         })
-        .manage(instance)
+        .manage(backend)
         .invoke_handler(tauri::generate_handler![
             settings::get_plugins_list,
             settings::run_plugin_index,
+            settings::get_index_stats,
+            consent::get_pending_capabilities,
+            consent::get_granted_capabilities,
+            consent::grant_capability,
+            consent::deny_capability,
+            registry::get_available_plugins,
+            registry::install_plugin,
+            registry::update_plugin,
+            registry::uninstall_plugin,
             search_bar::fetch_entries_for_input
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,46 @@
+use crate::backend::Backend;
+use michel_core::registry::AvailablePlugin;
+
+#[tauri::command]
+pub async fn get_available_plugins(
+    michel: tauri::State<'_, Backend>,
+) -> Result<Vec<AvailablePlugin>, String> {
+    michel
+        .available_plugins()
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+pub async fn install_plugin(
+    identifier: String,
+    michel: tauri::State<'_, Backend>,
+) -> Result<(), String> {
+    michel
+        .install_plugin(identifier)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+/// Returns whether a newer version was installed.
+#[tauri::command]
+pub async fn update_plugin(
+    identifier: String,
+    michel: tauri::State<'_, Backend>,
+) -> Result<bool, String> {
+    michel
+        .update_plugin(identifier)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+pub async fn uninstall_plugin(
+    identifier: String,
+    michel: tauri::State<'_, Backend>,
+) -> Result<(), String> {
+    michel
+        .uninstall_plugin(identifier)
+        .await
+        .map_err(|error| error.to_string())
+}
@@ -1,5 +1,5 @@
-use michel_core::{Entry, MichelInstance};
-use michel_index::MilliPersistence;
+use crate::backend::Backend;
+use michel_core::Entry;
 use tauri::{AppHandle, GlobalShortcutManager, Manager, Wry};
 
 pub fn register_search_shortcut(app: AppHandle<Wry>) {
@@ -23,7 +23,10 @@ pub fn register_search_shortcut(app: AppHandle<Wry>) {
 #[tauri::command]
 pub async fn fetch_entries_for_input(
     input: String,
-    michel: tauri::State<'_, MichelInstance<MilliPersistence>>,
+    michel: tauri::State<'_, Backend>,
 ) -> Result<Vec<Entry>, String> {
-    Ok(michel.entries_for_input(&input).await)
+    michel
+        .entries_for_input(&input)
+        .await
+        .map_err(|error| error.to_string())
 }
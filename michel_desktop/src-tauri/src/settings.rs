@@ -1,15 +1,12 @@
-use anyhow::{anyhow, Result};
-use michel_core::MichelInstance;
-use michel_index::MilliPersistence;
+use crate::backend::Backend;
+use michel_core::persistence::IndexStats;
+use michel_server::api::DisplayedPlugin;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize)]
-pub struct DisplayedPlugin {
+pub struct PluginIndexStats {
     identifier: String,
-    name: String,
-    description: String,
-    can_index: bool,
-    enabled: bool,
+    indexes: Vec<IndexStats>,
 }
 
 #[derive(Deserialize)]
@@ -18,30 +15,41 @@ pub struct RunPluginIndex {
 }
 
 #[tauri::command]
-pub fn get_plugins_list(
-    michel: tauri::State<MichelInstance<MilliPersistence>>,
-) -> Vec<DisplayedPlugin> {
-    println!("wallah");
-    michel
-        .plugins()
-        .iter()
-        .map(|plugin| DisplayedPlugin {
-            identifier: plugin.identifier(),
-            name: plugin.name(),
-            description: plugin.description(),
-            can_index: plugin.can_index(),
-            enabled: plugin.enabled(),
-        })
-        .collect()
+pub async fn get_plugins_list(
+    michel: tauri::State<'_, Backend>,
+) -> Result<Vec<DisplayedPlugin>, String> {
+    michel.plugins().await.map_err(|error| error.to_string())
 }
 
 #[tauri::command]
 pub async fn run_plugin_index(
     identifier: String,
-    michel: tauri::State<'_, MichelInstance<MilliPersistence>>,
+    michel: tauri::State<'_, Backend>,
 ) -> Result<String, String> {
-    let plugin = michel.plugin(identifier).ok_or("deso".to_string())?;
-    let result = plugin.index().await;
+    michel
+        .index_plugin(identifier)
+        .await
+        .map_err(|error| error.to_string())?;
 
     Ok(String::from("okbro"))
 }
+
+#[tauri::command]
+pub async fn get_index_stats(
+    michel: tauri::State<'_, Backend>,
+) -> Result<Vec<PluginIndexStats>, String> {
+    let plugins = michel.plugins().await.map_err(|error| error.to_string())?;
+    let mut stats = Vec::new();
+
+    for plugin in plugins {
+        stats.push(PluginIndexStats {
+            indexes: michel
+                .plugin_indexes(plugin.identifier.clone())
+                .await
+                .map_err(|error| error.to_string())?,
+            identifier: plugin.identifier,
+        });
+    }
+
+    Ok(stats)
+}
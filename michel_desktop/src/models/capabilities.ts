@@ -0,0 +1,12 @@
+export function describeCapability(capability: Capability): string {
+    switch (capability.kind) {
+        case "fs":
+            return `${capability.write ? "Read and write" : "Read"} files in ${capability.path}`;
+        case "network":
+            return `Fetch from ${capability.domain}`;
+        case "clipboard":
+            return "Use the clipboard";
+        case "index":
+            return `Create the index ${capability.name}`;
+    }
+}
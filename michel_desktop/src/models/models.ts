@@ -2,6 +2,7 @@ interface PluginInfo {
     identifier: string,
     name: string,
     description: string,
+    version: string,
     can_index: boolean,
     enable: boolean
 }
@@ -9,4 +10,47 @@ interface PluginInfo {
 interface Entry {
     title: string,
     description: string
-}
\ No newline at end of file
+}
+interface IndexStats {
+    name: string,
+    document_count: number,
+    field_distribution: Record<string, number>,
+    size_on_disk: number,
+    last_update: string | null
+}
+
+interface PluginIndexStats {
+    identifier: string,
+    indexes: IndexStats[]
+}
+
+type Capability =
+    | { kind: "fs", path: string, write: boolean }
+    | { kind: "network", domain: string }
+    | { kind: "clipboard" }
+    | { kind: "index", name: string }
+
+interface CapabilityRequest {
+    plugin: string,
+    capability: Capability
+}
+
+interface RegistryVersion {
+    version: string,
+    "min-host-api": string,
+    package: string,
+    sha256: string
+}
+
+interface RegistryPlugin {
+    identifier: string,
+    name: string,
+    description: string,
+    versions: RegistryVersion[]
+}
+
+interface AvailablePlugin {
+    plugin: RegistryPlugin,
+    latest: RegistryVersion | null,
+    installed: string | null
+}
@@ -0,0 +1,69 @@
+use anyhow::{anyhow, Result};
+use michel_core::persistence::Index;
+use michel_index::dump::{self, DumpManifest};
+use michel_index::MilliPersistence;
+use std::env;
+use std::future::Future;
+use std::process;
+
+const USAGE: &str = "usage:
+    michel-index <verify|manifest> <dump folder>
+    michel-index export <indexes folder> <index> <dump folder> [--snapshot]
+    michel-index import <indexes folder> <dump folder> [<index>]";
+
+/**
+    Checks a dump or a snapshot before carrying it to another machine, printing its manifest.
+    Also exports the indexes of a folder, and imports dumps into it, while nothing else has
+    the indexes opened.
+*/
+fn main() {
+    if let Err(error) = run(env::args().skip(1).collect()) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let manifest = match args.as_slice() {
+        ["verify", path] => dump::verify(path)?,
+        ["manifest", path] => dump::read_manifest(path)?,
+        ["export", root, index, path] => export(root, index, path, false)?,
+        ["export", root, index, path, "--snapshot"] => export(root, index, path, true)?,
+        ["import", root, path] => import(root, path, None)?,
+        ["import", root, path, index] => import(root, path, Some(index))?,
+        _ => return Err(anyhow!(USAGE)),
+    };
+
+    println!("{}", serde_json::to_string_pretty(&manifest)?);
+
+    Ok(())
+}
+
+fn export(root: &str, index: &str, path: &str, snapshot: bool) -> Result<DumpManifest> {
+    let persistence = MilliPersistence::open(root)?;
+    let index = Index {
+        name: String::from(index),
+    };
+
+    block_on(async {
+        match snapshot {
+            true => persistence.export_snapshot(index, path).await,
+            false => persistence.export_dump(index, path).await,
+        }
+    })
+}
+
+fn import(root: &str, path: &str, index: Option<&str>) -> Result<DumpManifest> {
+    let persistence = MilliPersistence::open(root)?;
+
+    block_on(persistence.import(path, index.map(String::from)))
+}
+
+fn block_on<T, F: Future<Output = Result<T>>>(future: F) -> Result<T> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(future)
+}
@@ -0,0 +1,358 @@
+/*!
+    Backups of an index, to keep them or move them to another machine.
+
+    A dump is portable : a folder holding `documents.ndjson`, one document per line with its
+    embedding, and a manifest describing the index settings and the plugin version that wrote it. A snapshot is a compacted copy of
+    the LMDB envs of the index, quicker to restore but only readable by the same milli version.
+    Both manifests hold the SHA-256 of every file, checked before anything gets imported.
+*/
+
+use crate::writer::declare_fields;
+use crate::{documents_by_ids, Document, MilliPersistence, VECTORS_FOLDER};
+use anyhow::{anyhow, Result};
+use michel_core::persistence::{
+    put_vector, Index, IndexMetadata, MichelPersistence, IDENTIFIER_FIELD,
+};
+use milli::heed::CompactionOption;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::task;
+
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+const DOCUMENTS_FILE: &str = "documents.ndjson";
+const INDEX_FOLDER: &str = "index";
+const LMDB_DATA_FILE: &str = "data.mdb";
+const IMPORT_BATCH_SIZE: usize = 1000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DumpKind {
+    Documents,
+    Snapshot,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DumpManifest {
+    pub format_version: u32,
+    pub kind: DumpKind,
+    pub index: String,
+    pub document_count: u64,
+    pub filterable_fields: BTreeSet<String>,
+    pub sortable_fields: BTreeSet<String>,
+    /// Plugin that wrote the index, so that it adopts the imported one rather than failing to
+    /// create it again.
+    #[serde(default)]
+    pub metadata: Option<IndexMetadata>,
+    /// SHA-256 of every file of the dump, by path relative to it.
+    pub checksums: BTreeMap<String, String>,
+}
+
+/// Reads the manifest of a dump or a snapshot, without checking its files.
+pub fn read_manifest<T: AsRef<Path>>(path: T) -> Result<DumpManifest> {
+    let manifest: DumpManifest = serde_json::from_reader(BufReader::new(File::open(
+        path.as_ref().join(MANIFEST_FILE),
+    )?))?;
+
+    if manifest.format_version != FORMAT_VERSION {
+        return Err(anyhow!(
+            "unsupported dump format version {}",
+            manifest.format_version
+        ));
+    }
+
+    Ok(manifest)
+}
+
+/// Checks every file of a dump or a snapshot against the checksums of its manifest.
+pub fn verify<T: AsRef<Path>>(path: T) -> Result<DumpManifest> {
+    let manifest = read_manifest(&path)?;
+
+    for (file, expected) in &manifest.checksums {
+        let checksum = checksum(&path.as_ref().join(file))
+            .map_err(|error| anyhow!("can't read {} : {}", file, error))?;
+
+        if &checksum != expected {
+            return Err(anyhow!("checksum mismatch for {}", file));
+        }
+    }
+
+    Ok(manifest)
+}
+
+impl MilliPersistence {
+    /// Writes every document of the index, with its embedding, to the `path` folder.
+    pub async fn export_dump<T: AsRef<Path>>(&self, index: Index, path: T) -> Result<DumpManifest> {
+        let name = index.name.clone();
+        self.flush(Index { name: name.clone() }).await?;
+        let metadata = self.metadata(Index { name: name.clone() }).await?;
+        let index = self.get_index(index)?.ok_or(anyhow!("index not found"))?;
+        let path = path.as_ref().to_path_buf();
+
+        task::spawn_blocking(move || {
+            fs::create_dir_all(&path)?;
+
+            let milli_index = &index.milli;
+            let rtxn = milli_index.read_txn()?;
+            let documents_ids = milli_index.documents_ids(&rtxn)?.into_iter().collect();
+            let documents = documents_by_ids(milli_index, &rtxn, documents_ids)?;
+            let mut vectors = index.vectors.all()?;
+
+            let mut writer = BufWriter::new(File::create(path.join(DOCUMENTS_FILE))?);
+            for mut document in documents {
+                let vector = document
+                    .get(IDENTIFIER_FIELD)
+                    .and_then(|id| id.as_str())
+                    .and_then(|identifier| vectors.remove(identifier));
+
+                if let Some(vector) = vector {
+                    put_vector(&mut document, vector)?;
+                }
+
+                serde_json::to_writer(&mut writer, &document)?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+
+            let manifest = DumpManifest {
+                format_version: FORMAT_VERSION,
+                kind: DumpKind::Documents,
+                index: name,
+                document_count: milli_index.number_of_documents(&rtxn)?,
+                filterable_fields: milli_index.filterable_fields(&rtxn)?.into_iter().collect(),
+                sortable_fields: milli_index.sortable_fields(&rtxn)?.into_iter().collect(),
+                metadata,
+                checksums: checksums(&path, &[PathBuf::from(DOCUMENTS_FILE)])?,
+            };
+
+            write_manifest(&path, &manifest)?;
+
+            Ok(manifest)
+        })
+        .await?
+    }
+
+    /// Copies the LMDB envs of the index to the `path` folder.
+    pub async fn export_snapshot<T: AsRef<Path>>(
+        &self,
+        index: Index,
+        path: T,
+    ) -> Result<DumpManifest> {
+        let name = index.name.clone();
+        self.flush(Index { name: name.clone() }).await?;
+        let metadata = self.metadata(Index { name: name.clone() }).await?;
+        let index = self.get_index(index)?.ok_or(anyhow!("index not found"))?;
+        let path = path.as_ref().to_path_buf();
+
+        task::spawn_blocking(move || {
+            let index_file = Path::new(INDEX_FOLDER).join(LMDB_DATA_FILE);
+            let vectors_file = Path::new(VECTORS_FOLDER).join(LMDB_DATA_FILE);
+            fs::create_dir_all(path.join(INDEX_FOLDER))?;
+            fs::create_dir_all(path.join(VECTORS_FOLDER))?;
+
+            let milli_index = &index.milli;
+            milli_index
+                .env
+                .copy_to_path(path.join(&index_file), CompactionOption::Enabled)?;
+            index.vectors.copy_to(path.join(&vectors_file))?;
+
+            let rtxn = milli_index.read_txn()?;
+            let manifest = DumpManifest {
+                format_version: FORMAT_VERSION,
+                kind: DumpKind::Snapshot,
+                index: name,
+                document_count: milli_index.number_of_documents(&rtxn)?,
+                filterable_fields: milli_index.filterable_fields(&rtxn)?.into_iter().collect(),
+                sortable_fields: milli_index.sortable_fields(&rtxn)?.into_iter().collect(),
+                metadata,
+                checksums: checksums(&path, &[index_file, vectors_file])?,
+            };
+
+            write_manifest(&path, &manifest)?;
+
+            Ok(manifest)
+        })
+        .await?
+    }
+
+    /**
+        Creates an index from a dump or a snapshot, named like the exported one unless `name`
+        is given. Nothing is imported if a checksum doesn't match, and the index is removed
+        again if its documents can't all be imported.
+    */
+    pub async fn import<T: AsRef<Path>>(
+        &self,
+        path: T,
+        name: Option<String>,
+    ) -> Result<DumpManifest> {
+        let path = path.as_ref().to_path_buf();
+        let manifest = task::spawn_blocking({
+            let path = path.clone();
+            move || verify(path)
+        })
+        .await??;
+        let name = name.unwrap_or_else(|| manifest.index.clone());
+
+        if self.has_index(&name)? {
+            return Err(anyhow!("index already exists"));
+        }
+
+        match manifest.kind {
+            DumpKind::Documents => self.init_index(name.clone()).await?,
+            DumpKind::Snapshot => self.restore_snapshot(&path, &name).await?,
+        }
+
+        if let Err(error) = self.fill_imported(&path, &name, &manifest).await {
+            self.remove_index(&name)?;
+            return Err(error);
+        }
+
+        Ok(manifest)
+    }
+
+    /**
+        Writes the documents of a dump to the index created for it, along with the plugin
+        that owns it, then checks their count.
+    */
+    async fn fill_imported(&self, path: &Path, name: &str, manifest: &DumpManifest) -> Result<()> {
+        if manifest.kind == DumpKind::Documents {
+            self.import_documents(path, name, manifest).await?;
+        }
+
+        if let Some(metadata) = &manifest.metadata {
+            self.set_metadata(
+                Index {
+                    name: String::from(name),
+                },
+                metadata.clone(),
+            )
+            .await?;
+        }
+
+        let stats = self
+            .stats(Index {
+                name: String::from(name),
+            })
+            .await?;
+        if stats.document_count != manifest.document_count {
+            return Err(anyhow!(
+                "imported {} documents instead of {}",
+                stats.document_count,
+                manifest.document_count
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn import_documents(
+        &self,
+        path: &Path,
+        name: &str,
+        manifest: &DumpManifest,
+    ) -> Result<()> {
+        let index = self
+            .get_index(Index {
+                name: String::from(name),
+            })?
+            .ok_or(anyhow!("index not found"))?;
+        let filterable = manifest.filterable_fields.iter().cloned().collect();
+        let sortable = manifest.sortable_fields.iter().cloned().collect();
+        task::spawn_blocking(move || declare_fields(&index.milli, filterable, sortable)).await??;
+
+        let mut lines = BufReader::new(File::open(path.join(DOCUMENTS_FILE))?).lines();
+        loop {
+            let batch = lines
+                .by_ref()
+                .take(IMPORT_BATCH_SIZE)
+                .map(|line| Ok(serde_json::from_str::<Document>(&line?)?))
+                .collect::<Result<Vec<Document>>>()?;
+
+            if batch.is_empty() {
+                break;
+            }
+
+            self.add_documents(
+                Index {
+                    name: String::from(name),
+                },
+                batch,
+            )
+            .await?;
+        }
+
+        self.flush(Index {
+            name: String::from(name),
+        })
+        .await
+    }
+
+    async fn restore_snapshot(&self, path: &Path, name: &str) -> Result<()> {
+        // Discarded unless the snapshot opens as an index
+        let directory = Arc::new(self.new_directory(name)?);
+
+        let source = path.to_path_buf();
+        let destination = directory.path.clone();
+        let copied = task::spawn_blocking(move || -> Result<()> {
+            fs::copy(
+                source.join(INDEX_FOLDER).join(LMDB_DATA_FILE),
+                destination.join(LMDB_DATA_FILE),
+            )?;
+            fs::create_dir_all(destination.join(VECTORS_FOLDER))?;
+            fs::copy(
+                source.join(VECTORS_FOLDER).join(LMDB_DATA_FILE),
+                destination.join(VECTORS_FOLDER).join(LMDB_DATA_FILE),
+            )?;
+
+            Ok(())
+        })
+        .await?;
+
+        let index = copied.and_then(|_| self.open_index(name, directory.clone()));
+        match index {
+            Ok(index) => self.insert_index(String::from(name), index),
+            Err(error) => {
+                directory.discard();
+                Err(error)
+            }
+        }
+    }
+}
+
+fn write_manifest(path: &Path, manifest: &DumpManifest) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path.join(MANIFEST_FILE))?);
+    serde_json::to_writer_pretty(&mut writer, manifest)?;
+    writer.flush().map_err(Into::into)
+}
+
+fn checksums(root: &Path, files: &[PathBuf]) -> Result<BTreeMap<String, String>> {
+    files
+        .iter()
+        .map(|file| {
+            // Forward slashes, so dumps made on Windows verify elsewhere
+            let key = file
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            Ok((key, checksum(&root.join(file))?))
+        })
+        .collect()
+}
+
+fn checksum(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
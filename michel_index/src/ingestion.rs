@@ -0,0 +1,156 @@
+use crate::writer::IndexWriter;
+use crate::Document;
+use anyhow::{anyhow, Result};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{self, Instant};
+
+/// How documents pushed to an index are coalesced before being written to milli.
+#[derive(Clone, Copy, Debug)]
+pub struct IngestionConfig {
+    /// Documents written at most by a single milli update.
+    pub batch_size: usize,
+    /// How long to wait for more documents before writing a batch.
+    pub batch_window: Duration,
+    /// Pushes waiting to be batched, beyond which pushing documents waits.
+    pub queue_capacity: usize,
+}
+
+impl Default for IngestionConfig {
+    fn default() -> Self {
+        IngestionConfig {
+            batch_size: 1000,
+            batch_window: Duration::from_millis(200),
+            queue_capacity: 64,
+        }
+    }
+}
+
+enum Ingestion {
+    Documents(Vec<Document>),
+    Flush(oneshot::Sender<Result<()>>),
+}
+
+/**
+    Queue in front of the writer of an index. Documents pushed one by one are gathered into
+    batches, as each milli update comes with a heavy fixed cost. The queue is bounded, so a
+    plugin pushing faster than milli writes ends up waiting.
+*/
+#[derive(Clone)]
+pub(crate) struct IngestionQueue {
+    sender: mpsc::Sender<Ingestion>,
+}
+
+impl IngestionQueue {
+    /// The batching task stops once every clone of the queue is dropped.
+    pub fn spawn(writer: IndexWriter, config: IngestionConfig) -> IngestionQueue {
+        let (sender, mut receiver) = mpsc::channel::<Ingestion>(config.queue_capacity.max(1));
+
+        tokio::spawn(async move {
+            // Write failures are kept until the next flush, as pushes don't wait for writes
+            let mut failures: Vec<anyhow::Error> = Vec::new();
+
+            while let Some(ingestion) = receiver.recv().await {
+                let mut pushes = Vec::new();
+                let mut flushes = Vec::new();
+                gather(ingestion, &mut pushes, &mut flushes);
+
+                let deadline = Instant::now() + config.batch_window;
+                while flushes.is_empty() && batch_size(&pushes) < config.batch_size {
+                    match time::timeout_at(deadline, receiver.recv()).await {
+                        Ok(Some(ingestion)) => gather(ingestion, &mut pushes, &mut flushes),
+                        _ => break,
+                    }
+                }
+
+                failures.append(&mut write(&writer, pushes).await);
+
+                for flush in flushes {
+                    let _ = flush.send(report(std::mem::take(&mut failures)));
+                }
+            }
+        });
+
+        IngestionQueue { sender }
+    }
+
+    /// Waits only if the queue is full, the documents being written later on.
+    pub async fn push(&self, documents: Vec<Document>) -> Result<()> {
+        self.sender
+            .send(Ingestion::Documents(documents))
+            .await
+            .map_err(|_| anyhow!("index ingestion stopped"))
+    }
+
+    /// Waits for every document pushed so far to be written.
+    pub async fn flush(&self) -> Result<()> {
+        let (reply, response) = oneshot::channel();
+
+        self.sender
+            .send(Ingestion::Flush(reply))
+            .await
+            .map_err(|_| anyhow!("index ingestion stopped"))?;
+
+        response
+            .await
+            .map_err(|_| anyhow!("index ingestion stopped"))?
+    }
+}
+
+fn gather(
+    ingestion: Ingestion,
+    pushes: &mut Vec<Vec<Document>>,
+    flushes: &mut Vec<oneshot::Sender<Result<()>>>,
+) {
+    match ingestion {
+        Ingestion::Documents(documents) => pushes.push(documents),
+        Ingestion::Flush(reply) => flushes.push(reply),
+    }
+}
+
+fn batch_size(pushes: &[Vec<Document>]) -> usize {
+    pushes.iter().map(Vec::len).sum()
+}
+
+/**
+    Writes the pushes as a single batch. If it fails, they are written again one by one, so
+    that a bad document only fails the push it came with rather than every coalesced one.
+*/
+async fn write(writer: &IndexWriter, mut pushes: Vec<Vec<Document>>) -> Vec<anyhow::Error> {
+    if pushes.len() <= 1 {
+        return match pushes.pop() {
+            Some(documents) => writer.write(documents).await.err().into_iter().collect(),
+            None => Vec::new(),
+        };
+    }
+
+    if writer.write(pushes.concat()).await.is_ok() {
+        return Vec::new();
+    }
+
+    let mut failures = Vec::new();
+    for documents in pushes {
+        if let Err(error) = writer.write(documents).await {
+            failures.push(error);
+        }
+    }
+
+    failures
+}
+
+/// Every write failure since the previous flush, in the order of the pushes.
+fn report(failures: Vec<anyhow::Error>) -> Result<()> {
+    match failures.len() {
+        0 => Ok(()),
+        1 => Err(failures.into_iter().next().unwrap()),
+        count => Err(anyhow!(
+            "{} pushes failed to be written : {}",
+            count,
+            failures
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join(" ; ")
+        )),
+    }
+}
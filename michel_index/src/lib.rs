@@ -1,11 +1,28 @@
+pub mod dump;
+mod ingestion;
+mod vectors;
+mod writer;
+
+use crate::ingestion::IngestionQueue;
+use crate::vectors::VectorStore;
+use crate::writer::IndexWriter;
 use anyhow::{anyhow, Result};
-use michel_core::persistence::{Index, MichelPersistence};
-use milli::documents::{DocumentsBatchBuilder, DocumentsBatchReader};
-use milli::{heed, update, Search, SearchResult};
-use std::collections::HashMap;
-use std::io::Cursor;
-use tempdir;
+use async_trait::async_trait;
+use michel_core::package::{decode_hex, encode_hex};
+use michel_core::persistence::{
+    DateFilter, HybridQuery, Index, IndexMetadata, IndexStats, MichelPersistence, SortOrder,
+};
+use milli::{heed, update, AscDesc, Member, Search, SearchResult};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 use tempdir::TempDir;
+use tokio::task;
+
+pub use crate::ingestion::IngestionConfig;
+pub use michel_core::embedder::{Embedder, TrigramEmbedder};
 
 pub(crate) type Document = serde_json::Map<String, serde_json::Value>;
 
@@ -13,147 +30,485 @@ const MAX_OS_PAGE_SIZE: usize = 16_777_216;
 const MAX_POSSIBLE_SIZE: usize = 2_000_000_000;
 const MAX_MAP_SIZE: usize = MAX_POSSIBLE_SIZE - (MAX_POSSIBLE_SIZE % MAX_OS_PAGE_SIZE);
 
+const VECTORS_FOLDER: &str = "vectors";
+
 /**
     Big inspiration (with consent from the owner) here : https://github.com/GregoryConrad/mimir/blob/main/packages/mimir/native/src/embedded_milli/v1.rs
+
+    Searches run in blocking tasks on their own read transaction, so they happen concurrently,
+    while pushed documents are batched then written by the writer thread of their index.
 */
 
 pub struct MilliPersistence {
-    indexes: HashMap<String, milli::Index>,
+    indexes: RwLock<HashMap<String, MilliIndex>>,
+    /// Folder holding a folder per index, which outlive the process. Temporary ones without it.
+    root: Option<PathBuf>,
+    embedder: Option<Arc<dyn Embedder>>,
+    ingestion: IngestionConfig,
+}
+
+/// Cheap to clone, every field being a handle.
+#[derive(Clone)]
+struct MilliIndex {
+    milli: milli::Index,
+    vectors: Arc<VectorStore>,
+    queue: IngestionQueue,
+    directory: Arc<IndexDirectory>,
+}
+
+/// Folder of the envs of an index, removed along with the last handle of the index if discarded.
+struct IndexDirectory {
+    path: PathBuf,
+    discarded: AtomicBool,
+}
+
+impl IndexDirectory {
+    fn temporary(name: &str) -> Result<IndexDirectory> {
+        let directory = IndexDirectory::durable(TempDir::new(name)?.into_path());
+        directory.discard();
+
+        Ok(directory)
+    }
+
+    fn durable(path: PathBuf) -> IndexDirectory {
+        IndexDirectory {
+            path,
+            discarded: AtomicBool::new(false),
+        }
+    }
+
+    fn discard(&self) {
+        self.discarded.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for IndexDirectory {
+    fn drop(&mut self) {
+        if self.discarded.load(Ordering::SeqCst) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
 }
 
 impl MilliPersistence {
+    /// Indexes are kept in temporary folders, removed when the persistence is dropped.
     pub fn new() -> Result<MilliPersistence> {
         Ok(MilliPersistence {
-            indexes: HashMap::new(),
+            indexes: RwLock::new(HashMap::new()),
+            root: None,
+            embedder: None,
+            ingestion: IngestionConfig::default(),
         })
     }
 
-    fn get_index(&self, index: michel_core::persistence::Index) -> Option<&milli::Index> {
-        return self.indexes.get(&index.name);
+    /**
+        Indexes are kept in `root`, and the ones found there are opened on first use, so that
+        they are searchable again when the process starts over.
+    */
+    pub fn open<T: AsRef<Path>>(root: T) -> Result<MilliPersistence> {
+        fs::create_dir_all(&root)?;
+
+        Ok(MilliPersistence {
+            root: Some(root.as_ref().to_path_buf()),
+            ..MilliPersistence::new()?
+        })
     }
-}
 
-impl MichelPersistence for MilliPersistence {
-    fn init_index(&mut self, index_name: String) -> Result<()> {
-        if self.indexes.contains_key(&index_name) {
-            return Err(anyhow!("index already exists"));
+    /// Opens the indexes of `root` as the backend chosen in the shared config, see [`Backends`].
+    ///
+    /// [`Backends`]: michel_core::backend::Backends
+    pub fn open_backend(
+        root: &Path,
+        embedder: Option<Box<dyn Embedder>>,
+    ) -> Result<Box<dyn MichelPersistence>> {
+        let persistence = MilliPersistence::open(root)?;
+
+        Ok(match embedder {
+            Some(embedder) => Box::new(persistence.with_embedder(embedder)),
+            None => Box::new(persistence),
+        })
+    }
+
+    pub fn with_ingestion(mut self, ingestion: IngestionConfig) -> MilliPersistence {
+        self.ingestion = ingestion;
+        self
+    }
+
+    /// Embeds the documents pushed without an embedding, and the hybrid search queries.
+    pub fn with_embedder<E: Embedder + 'static>(mut self, embedder: E) -> MilliPersistence {
+        self.embedder = Some(Arc::new(embedder));
+        self
+    }
+
+    /// Folder of an index in the root, named in hexadecimal as index names can hold anything.
+    fn durable_path(&self, name: &str) -> Option<PathBuf> {
+        self.root
+            .as_ref()
+            .map(|root| root.join(encode_hex(name.as_bytes())))
+    }
+
+    /// Folder for a new index, durable when the persistence has a root.
+    fn new_directory(&self, name: &str) -> Result<IndexDirectory> {
+        match self.durable_path(name) {
+            Some(path) => {
+                fs::create_dir_all(&path)?;
+                Ok(IndexDirectory::durable(path))
+            }
+            None => IndexDirectory::temporary(name),
+        }
+    }
+
+    /// Index already opened, or else found in the root and opened now.
+    fn get_index(&self, index: Index) -> Result<Option<MilliIndex>> {
+        if let Some(opened) = self
+            .indexes
+            .read()
+            .map_err(|_| anyhow!("indexes poisoned"))?
+            .get(&index.name)
+        {
+            return Ok(Some(opened.clone()));
         }
 
-        let path = TempDir::new(&index_name)?;
+        let path = match self.durable_path(&index.name) {
+            Some(path) if path.is_dir() => path,
+            _ => return Ok(None),
+        };
+
+        // Opened while holding the lock, as an env can't be opened twice at once
+        let mut indexes = self
+            .indexes
+            .write()
+            .map_err(|_| anyhow!("indexes poisoned"))?;
+
+        if let Some(opened) = indexes.get(&index.name) {
+            return Ok(Some(opened.clone()));
+        }
+
+        let opened = self.open_index(&index.name, Arc::new(IndexDirectory::durable(path)))?;
+        indexes.insert(index.name, opened.clone());
+
+        Ok(Some(opened))
+    }
+
+    fn has_index(&self, name: &str) -> Result<bool> {
+        let indexes = self
+            .indexes
+            .read()
+            .map_err(|_| anyhow!("indexes poisoned"))?;
 
-        std::fs::create_dir_all(&path)?;
+        Ok(indexes.contains_key(name)
+            || self.durable_path(name).map_or(false, |path| path.is_dir()))
+    }
 
+    /// Opens the milli index and the vectors found in `directory`, creating them if missing.
+    fn open_index(&self, name: &str, directory: Arc<IndexDirectory>) -> Result<MilliIndex> {
         let mut options = heed::EnvOpenOptions::new();
         options.map_size(MAX_MAP_SIZE);
 
-        let index = milli::Index::new(options, &path).map_err(anyhow::Error::from)?;
+        let index = milli::Index::new(options, &directory.path).map_err(anyhow::Error::from)?;
+        let vectors = Arc::new(VectorStore::open(directory.path.join(VECTORS_FOLDER))?);
+        let writer =
+            IndexWriter::spawn(name, index.clone(), vectors.clone(), self.embedder.clone())?;
+        let queue = IngestionQueue::spawn(writer, self.ingestion);
+
+        Ok(MilliIndex {
+            milli: index,
+            vectors,
+            queue,
+            directory,
+        })
+    }
+
+    /// Registers an index opened beforehand, unless one with the same name got created meanwhile.
+    fn insert_index(&self, name: String, index: MilliIndex) -> Result<()> {
+        let mut indexes = self
+            .indexes
+            .write()
+            .map_err(|_| anyhow!("indexes poisoned"))?;
 
-        self.indexes.insert(index_name, index);
+        if indexes.contains_key(&name) {
+            return Err(anyhow!("index already exists"));
+        }
+
+        indexes.insert(name, index);
 
         Ok(())
     }
 
-    fn add_document(
-        &self,
-        index: michel_core::persistence::Index,
-        document: Document,
-    ) -> Result<()> {
-        let milli_index = self.get_index(index).ok_or(anyhow!("index not created"))?;
+    /// Forgets an index, its folder being removed once the searches still running on it end.
+    pub(crate) fn remove_index(&self, name: &str) -> Result<()> {
+        let index = self
+            .indexes
+            .write()
+            .map_err(|_| anyhow!("indexes poisoned"))?
+            .remove(name);
+
+        match (index, self.durable_path(name)) {
+            (Some(index), _) => index.directory.discard(),
+            (None, Some(path)) if path.is_dir() => fs::remove_dir_all(path)?,
+            (None, _) => {}
+        }
 
-        // Create a batch builder to convert json_documents into milli's format
-        let mut builder = DocumentsBatchBuilder::new(Vec::new());
+        Ok(())
+    }
+}
 
-        builder.append_json_object(&document)?;
+#[async_trait]
+impl MichelPersistence for MilliPersistence {
+    async fn init_index(&self, index_name: String) -> Result<()> {
+        if self.has_index(&index_name)? {
+            return Err(anyhow!("index already exists"));
+        }
+
+        let index = self.open_index(&index_name, Arc::new(self.new_directory(&index_name)?))?;
+
+        self.insert_index(index_name, index)
+    }
+
+    async fn list_indexes(&self) -> Result<Vec<String>> {
+        let mut names: BTreeSet<String> = self
+            .indexes
+            .read()
+            .map_err(|_| anyhow!("indexes poisoned"))?
+            .keys()
+            .cloned()
+            .collect();
+
+        // Along with the ones of the root not opened yet
+        if let Some(root) = &self.root {
+            for entry in fs::read_dir(root)? {
+                let name = decode_hex(&entry?.file_name().to_string_lossy())
+                    .and_then(|name| String::from_utf8(name).ok());
+                names.extend(name);
+            }
+        }
+
+        Ok(names.into_iter().collect())
+    }
+
+    async fn stats(&self, index: Index) -> Result<IndexStats> {
+        let name = index.name.clone();
+        let index = self.get_index(index)?.ok_or(anyhow!("index not found"))?;
+
+        task::spawn_blocking(move || {
+            let milli_index = &index.milli;
+            let rtxn = milli_index.read_txn()?;
+
+            Ok(IndexStats {
+                name,
+                document_count: milli_index.number_of_documents(&rtxn)?,
+                field_distribution: milli_index.field_distribution(&rtxn)?,
+                size_on_disk: milli_index.on_disk_size()? + index.vectors.size_on_disk()?,
+                last_update: Some(milli_index.updated_at(&rtxn)?),
+            })
+        })
+        .await?
+    }
+
+    async fn metadata(&self, index: Index) -> Result<Option<IndexMetadata>> {
+        let index = self.get_index(index)?.ok_or(anyhow!("index not found"))?;
+
+        task::spawn_blocking(move || index.vectors.metadata()).await?
+    }
+
+    async fn set_metadata(&self, index: Index, metadata: IndexMetadata) -> Result<()> {
+        let index = self.get_index(index)?.ok_or(anyhow!("index not found"))?;
+
+        task::spawn_blocking(move || index.vectors.put_metadata(&metadata)).await?
+    }
 
-        // Flush the contents of the builder and retreive the buffer to make a batch reader
-        let buff = builder.into_inner()?;
-        let reader = DocumentsBatchReader::from_reader(Cursor::new(buff))?;
+    async fn clear_index(&self, index: Index) -> Result<()> {
+        let name = index.name.clone();
+        let index = self.get_index(index)?.ok_or(anyhow!("index not found"))?;
 
-        // Create the configs needed for the batch document addition
-        let indexer_config = update::IndexerConfig::default();
-        let indexing_config = update::IndexDocumentsConfig::default();
+        // Documents still queued would otherwise be written after the clear
+        self.flush(Index { name }).await?;
 
-        // Make an index write transaction with a batch step to index the new documents
-        let mut wtxn = milli_index.write_txn()?;
-        let (builder, indexing_result) = update::IndexDocuments::new(
-            &mut wtxn,
-            milli_index,
-            &indexer_config,
-            indexing_config,
-            |_| (),
-            || false,
-        )?
-        .add_documents(reader)?;
-        indexing_result?; // check to make sure there is no UserError
-        builder.execute()?;
+        task::spawn_blocking(move || {
+            let milli_index = &index.milli;
 
-        wtxn.commit().map_err(Into::into)
+            let mut wtxn = milli_index.write_txn()?;
+            update::ClearDocuments::new(&mut wtxn, milli_index).execute()?;
+            wtxn.commit()?;
+
+            index.vectors.clear_vectors()
+        })
+        .await?
     }
 
-    fn add_documents(
+    async fn add_document(
+        &self,
+        index: michel_core::persistence::Index,
+        document: Document,
+    ) -> Result<()> {
+        self.add_documents(index, vec![document]).await
+    }
+
+    async fn add_documents(
         &self,
         index: Index,
         documents: Vec<michel_core::persistence::PersistedDocument>,
     ) -> Result<()> {
-        let milli_index = self.get_index(index).ok_or(anyhow!("index not created"))?;
+        let index = self.get_index(index)?.ok_or(anyhow!("index not created"))?;
 
-        // Create a batch builder to convert json_documents into milli's format
-        let mut builder = DocumentsBatchBuilder::new(Vec::new());
+        index.queue.push(documents).await
+    }
 
-        for document in documents {
-            builder.append_json_object(&document)?
-        }
+    async fn flush(&self, index: Index) -> Result<()> {
+        let index = self.get_index(index)?.ok_or(anyhow!("index not found"))?;
 
-        // Flush the contents of the builder and retreive the buffer to make a batch reader
-        let buff = builder.into_inner()?;
-        let reader = DocumentsBatchReader::from_reader(Cursor::new(buff))?;
+        index.queue.flush().await
+    }
 
-        // Create the configs needed for the batch document addition
-        let indexer_config = update::IndexerConfig::default();
-        let indexing_config = update::IndexDocumentsConfig::default();
+    async fn search_document(
+        &self,
+        index: michel_core::persistence::Index,
+        query: String,
+        limit: Option<u32>,
+    ) -> Result<Vec<Document>> {
+        let index = self.get_index(index)?.ok_or(anyhow!("index not found"))?;
+
+        task::spawn_blocking(move || {
+            // Create the search
+            let milli_index = &index.milli;
 
-        // Make an index write transaction with a batch step to index the new documents
-        let mut wtxn = milli_index.write_txn()?;
-        let (builder, indexing_result) = update::IndexDocuments::new(
-            &mut wtxn,
-            milli_index,
-            &indexer_config,
-            indexing_config,
-            |_| (),
-            || false,
-        )?
-        .add_documents(reader)?;
-        indexing_result?; // check to make sure there is no UserError
-        builder.execute()?;
+            let rtxn = milli_index.read_txn()?;
+            let mut search = Search::new(&rtxn, milli_index);
 
-        wtxn.commit().map_err(Into::into)
+            // Configure the search based on given parameters
+            search.query(&query);
+            search.limit(limit.unwrap_or(u32::MAX).try_into()?);
+
+            documents_for_search(milli_index, &rtxn, &search)
+        })
+        .await?
     }
 
-    fn search_document(
+    async fn search_document_by_date(
         &self,
-        index: michel_core::persistence::Index,
+        index: Index,
         query: String,
+        filter: DateFilter,
         limit: Option<u32>,
     ) -> Result<Vec<Document>> {
-        // Create the search
-        let milli_index = self.get_index(index).ok_or(anyhow!("index not found"))?;
+        let index = self.get_index(index)?.ok_or(anyhow!("index not found"))?;
+
+        task::spawn_blocking(move || {
+            let milli_index = &index.milli;
+            let expression = date_filter_expression(&filter)?;
+
+            let rtxn = milli_index.read_txn()?;
+            let mut search = Search::new(&rtxn, milli_index);
 
-        let rtxn = milli_index.read_txn()?;
-        let mut search = Search::new(&rtxn, milli_index);
+            search.query(&query);
+            search.limit(limit.unwrap_or(u32::MAX).try_into()?);
 
-        // Configure the search based on given parameters
-        search.query(&query);
-        search.limit(limit.unwrap_or(u32::MAX).try_into()?);
+            if let Some(condition) = milli::Filter::from_str(&expression)? {
+                search.filter(condition);
+            }
 
-        // Get the documents based on the search results
-        let SearchResult { documents_ids, .. } = search.execute()?;
-        let fields_ids_map = milli_index.fields_ids_map(&rtxn)?;
+            if let Some(order) = &filter.order {
+                let member = Member::Field(filter.timestamp_field());
+                search.sort_criteria(vec![match order {
+                    SortOrder::Ascending => AscDesc::Asc(member),
+                    SortOrder::Descending => AscDesc::Desc(member),
+                }]);
+            }
 
-        milli_index
-            .documents(&rtxn, documents_ids)?
-            .iter()
-            .map(|(_id, doc)| milli::all_obkv_to_json(*doc, &fields_ids_map))
-            .map(|r| r.map_err(anyhow::Error::from))
-            .collect()
+            documents_for_search(milli_index, &rtxn, &search)
+        })
+        .await?
     }
+
+    async fn hybrid_search_document(
+        &self,
+        index: Index,
+        query: HybridQuery,
+        limit: Option<u32>,
+    ) -> Result<Vec<Document>> {
+        let index = self.get_index(index)?.ok_or(anyhow!("index not found"))?;
+        let embedder = self.embedder.clone();
+
+        task::spawn_blocking(move || {
+            let milli_index = &index.milli;
+
+            let limit: usize = limit.unwrap_or(u32::MAX).try_into()?;
+            let candidates = query.candidates(limit);
+
+            let query_vector = match &query.vector {
+                Some(vector) => Some(vector.clone()),
+                None => embedder
+                    .map(|embedder| embedder.embed(&query.query))
+                    .transpose()?,
+            };
+
+            let rtxn = milli_index.read_txn()?;
+
+            let mut search = Search::new(&rtxn, milli_index);
+            search.query(&query.query);
+            search.limit(candidates);
+
+            let SearchResult { documents_ids, .. } = search.execute()?;
+
+            let mut similarities = Vec::new();
+            if let Some(vector) = query_vector {
+                let external_ids = milli_index.external_documents_ids(&rtxn)?;
+
+                for (identifier, similarity) in index.vectors.nearest(&vector, candidates)? {
+                    if let Some(id) = external_ids.get(&identifier) {
+                        similarities.push((id, similarity));
+                    }
+                }
+            }
+
+            documents_by_ids(
+                milli_index,
+                &rtxn,
+                query.blend(documents_ids, similarities, limit),
+            )
+        })
+        .await?
+    }
+}
+
+fn documents_for_search(
+    milli_index: &milli::Index,
+    rtxn: &heed::RoTxn,
+    search: &Search,
+) -> Result<Vec<Document>> {
+    // Get the documents based on the search results
+    let SearchResult { documents_ids, .. } = search.execute()?;
+
+    documents_by_ids(milli_index, rtxn, documents_ids)
+}
+
+fn documents_by_ids(
+    milli_index: &milli::Index,
+    rtxn: &heed::RoTxn,
+    documents_ids: Vec<u32>,
+) -> Result<Vec<Document>> {
+    let fields_ids_map = milli_index.fields_ids_map(rtxn)?;
+
+    milli_index
+        .documents(rtxn, documents_ids)?
+        .iter()
+        .map(|(_id, doc)| milli::all_obkv_to_json(*doc, &fields_ids_map))
+        .map(|r| r.map_err(anyhow::Error::from))
+        .collect()
+}
+
+/**
+    Builds the milli filter of a date range, empty when the range has no bounds. The field is
+    validated first, as a quote in it would end the quoted name and inject a filter.
+*/
+fn date_filter_expression(filter: &DateFilter) -> Result<String> {
+    filter.validate()?;
+    let field = filter.timestamp_field();
+
+    Ok(match filter.timestamp_range() {
+        (Some(after), Some(before)) => format!("\"{}\" {} TO {}", field, after, before),
+        (Some(after), None) => format!("\"{}\" >= {}", field, after),
+        (None, Some(before)) => format!("\"{}\" <= {}", field, before),
+        (None, None) => String::new(),
+    })
 }
@@ -0,0 +1,128 @@
+use crate::MAX_MAP_SIZE;
+use anyhow::Result;
+use michel_core::embedder::cosine_similarity;
+use michel_core::persistence::{decode_vector, encode_vector, IndexMetadata};
+use milli::heed;
+use milli::heed::types::{ByteSlice, Str};
+use std::collections::HashMap;
+use std::path::Path;
+
+const VECTORS_DATABASE: &str = "vectors";
+const METADATA_DATABASE: &str = "metadata";
+const METADATA_KEY: &str = "index";
+
+/**
+    Embedding vectors of an index, by document identifier. milli doesn't handle vectors, so
+    they live in their own LMDB env next to the index, and are compared by brute force.
+
+    The env also keeps the metadata of the index, milli having no place for it either, so that
+    snapshots carry it.
+*/
+pub(crate) struct VectorStore {
+    env: heed::Env,
+    vectors: heed::Database<Str, ByteSlice>,
+    metadata: heed::Database<Str, ByteSlice>,
+}
+
+impl VectorStore {
+    pub fn open<T: AsRef<Path>>(path: T) -> Result<VectorStore> {
+        std::fs::create_dir_all(&path)?;
+
+        let mut options = heed::EnvOpenOptions::new();
+        options.map_size(MAX_MAP_SIZE);
+        options.max_dbs(2);
+
+        let env = options.open(path)?;
+        let vectors = env.create_database(Some(VECTORS_DATABASE))?;
+        let metadata = env.create_database(Some(METADATA_DATABASE))?;
+
+        Ok(VectorStore {
+            env,
+            vectors,
+            metadata,
+        })
+    }
+
+    pub fn metadata(&self) -> Result<Option<IndexMetadata>> {
+        let rtxn = self.env.read_txn()?;
+
+        self.metadata
+            .get(&rtxn, METADATA_KEY)?
+            .map(|bytes| serde_json::from_slice(bytes).map_err(Into::into))
+            .transpose()
+    }
+
+    pub fn put_metadata(&self, metadata: &IndexMetadata) -> Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        self.metadata
+            .put(&mut wtxn, METADATA_KEY, &serde_json::to_vec(metadata)?)?;
+
+        wtxn.commit().map_err(Into::into)
+    }
+
+    pub fn clear_vectors(&self) -> Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        self.vectors.clear(&mut wtxn)?;
+
+        wtxn.commit().map_err(Into::into)
+    }
+
+    pub fn size_on_disk(&self) -> Result<u64> {
+        self.env.real_disk_size().map_err(Into::into)
+    }
+
+    pub fn put_vectors(&self, vectors: Vec<(String, Vec<f32>)>) -> Result<()> {
+        if vectors.is_empty() {
+            return Ok(());
+        }
+
+        let mut wtxn = self.env.write_txn()?;
+
+        for (identifier, vector) in vectors {
+            self.vectors
+                .put(&mut wtxn, &identifier, &encode_vector(&vector))?;
+        }
+
+        wtxn.commit().map_err(Into::into)
+    }
+
+    pub fn all(&self) -> Result<HashMap<String, Vec<f32>>> {
+        let rtxn = self.env.read_txn()?;
+
+        self.vectors
+            .iter(&rtxn)?
+            .map(|entry| {
+                let (identifier, bytes) = entry?;
+                Ok((String::from(identifier), decode_vector(bytes)))
+            })
+            .collect()
+    }
+
+    /// Writes a compacted copy of the env to `path`, a file.
+    pub fn copy_to<T: AsRef<Path>>(&self, path: T) -> Result<()> {
+        self.env
+            .copy_to_path(path, heed::CompactionOption::Enabled)?;
+
+        Ok(())
+    }
+
+    /// Identifiers of the `limit` documents closest to the query, with their cosine similarity.
+    pub fn nearest(&self, query: &[f32], limit: usize) -> Result<Vec<(String, f32)>> {
+        let rtxn = self.env.read_txn()?;
+
+        let mut similarities = Vec::new();
+        for entry in self.vectors.iter(&rtxn)? {
+            let (identifier, bytes) = entry?;
+            let vector = decode_vector(bytes);
+
+            if vector.len() == query.len() {
+                similarities.push((String::from(identifier), cosine_similarity(query, &vector)));
+            }
+        }
+
+        similarities.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        similarities.truncate(limit);
+
+        Ok(similarities)
+    }
+}
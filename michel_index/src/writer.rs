@@ -0,0 +1,163 @@
+use crate::vectors::VectorStore;
+use crate::Document;
+use anyhow::{anyhow, Result};
+use michel_core::embedder::{document_text, Embedder};
+use michel_core::persistence::{datetime_fields, take_vector, timestamp_field, IDENTIFIER_FIELD};
+use milli::documents::{DocumentsBatchBuilder, DocumentsBatchReader};
+use milli::update;
+use std::collections::HashSet;
+use std::io::Cursor;
+use std::sync::Arc;
+use std::thread;
+use tokio::sync::{mpsc, oneshot};
+
+struct WriteRequest {
+    documents: Vec<Document>,
+    reply: oneshot::Sender<Result<()>>,
+}
+
+/**
+    Every write of an index goes through its own thread, one at a time, so milli write
+    transactions neither block the async runtime nor the searches running meanwhile.
+*/
+#[derive(Clone)]
+pub(crate) struct IndexWriter {
+    sender: mpsc::UnboundedSender<WriteRequest>,
+}
+
+impl IndexWriter {
+    /// The thread stops once every clone of the writer is dropped.
+    pub fn spawn(
+        name: &str,
+        milli_index: milli::Index,
+        vectors: Arc<VectorStore>,
+        embedder: Option<Arc<dyn Embedder>>,
+    ) -> Result<IndexWriter> {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<WriteRequest>();
+
+        thread::Builder::new()
+            .name(format!("michel-index-{}", name))
+            .spawn(move || {
+                while let Some(request) = receiver.blocking_recv() {
+                    let result = write_documents(
+                        &milli_index,
+                        &vectors,
+                        embedder.as_deref(),
+                        request.documents,
+                    );
+
+                    let _ = request.reply.send(result);
+                }
+            })?;
+
+        Ok(IndexWriter { sender })
+    }
+
+    pub async fn write(&self, documents: Vec<Document>) -> Result<()> {
+        let (reply, response) = oneshot::channel();
+
+        self.sender
+            .send(WriteRequest { documents, reply })
+            .map_err(|_| anyhow!("index writer stopped"))?;
+
+        response
+            .await
+            .map_err(|_| anyhow!("index writer stopped"))?
+    }
+}
+
+fn write_documents(
+    milli_index: &milli::Index,
+    vector_store: &VectorStore,
+    embedder: Option<&dyn Embedder>,
+    documents: Vec<Document>,
+) -> Result<()> {
+    declare_datetime_fields(milli_index, &documents)?;
+
+    // Create a batch builder to convert json_documents into milli's format
+    let mut builder = DocumentsBatchBuilder::new(Vec::new());
+    let mut vectors = Vec::new();
+
+    for mut document in documents {
+        let vector = match take_vector(&mut document)? {
+            Some(vector) => Some(vector),
+            None => embedder
+                .map(|embedder| embedder.embed(&document_text(&document)))
+                .transpose()?,
+        };
+
+        if let (Some(vector), Some(identifier)) = (
+            vector,
+            document.get(IDENTIFIER_FIELD).and_then(|id| id.as_str()),
+        ) {
+            vectors.push((String::from(identifier), vector));
+        }
+
+        builder.append_json_object(&document)?
+    }
+
+    // Flush the contents of the builder and retreive the buffer to make a batch reader
+    let buff = builder.into_inner()?;
+    let reader = DocumentsBatchReader::from_reader(Cursor::new(buff))?;
+
+    // Create the configs needed for the batch document addition
+    let indexer_config = update::IndexerConfig::default();
+    let indexing_config = update::IndexDocumentsConfig::default();
+
+    // Make an index write transaction with a batch step to index the new documents
+    let mut wtxn = milli_index.write_txn()?;
+    let (builder, indexing_result) = update::IndexDocuments::new(
+        &mut wtxn,
+        milli_index,
+        &indexer_config,
+        indexing_config,
+        |_| (),
+        || false,
+    )?
+    .add_documents(reader)?;
+    indexing_result?; // check to make sure there is no UserError
+    builder.execute()?;
+
+    wtxn.commit()?;
+
+    vector_store.put_vectors(vectors)
+}
+
+/// Datetime timestamps have to be declared filterable and sortable before being queried.
+fn declare_datetime_fields(milli_index: &milli::Index, documents: &[Document]) -> Result<()> {
+    let timestamp_fields: HashSet<String> = documents
+        .iter()
+        .flat_map(datetime_fields)
+        .map(|field| timestamp_field(&field))
+        .collect();
+
+    declare_fields(milli_index, timestamp_fields.clone(), timestamp_fields)
+}
+
+/// Makes fields filterable and sortable on top of the ones already declared.
+pub(crate) fn declare_fields(
+    milli_index: &milli::Index,
+    filterable: HashSet<String>,
+    sortable: HashSet<String>,
+) -> Result<()> {
+    let rtxn = milli_index.read_txn()?;
+    let mut filterable_fields = milli_index.filterable_fields(&rtxn)?;
+    let mut sortable_fields = milli_index.sortable_fields(&rtxn)?;
+    drop(rtxn);
+
+    if filterable.is_subset(&filterable_fields) && sortable.is_subset(&sortable_fields) {
+        return Ok(());
+    }
+
+    filterable_fields.extend(filterable);
+    sortable_fields.extend(sortable);
+
+    let indexer_config = update::IndexerConfig::default();
+    let mut wtxn = milli_index.write_txn()?;
+    let mut settings = update::Settings::new(&mut wtxn, milli_index, &indexer_config);
+    settings.set_filterable_fields(filterable_fields);
+    settings.set_sortable_fields(sortable_fields);
+    settings.execute(|_| (), || false)?;
+
+    wtxn.commit().map_err(Into::into)
+}
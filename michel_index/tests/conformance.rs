@@ -0,0 +1,3 @@
+use michel_index::MilliPersistence;
+
+michel_core::persistence_conformance_tests!(MilliPersistence::new().unwrap());
@@ -0,0 +1,219 @@
+use michel_core::conformance::block_on;
+use michel_core::grants::{Capability, Grants};
+use michel_core::persistence::{Index, MichelPersistence, PersistedDocument};
+use michel_core::Plugin;
+use michel_index::dump::{self, DumpKind};
+use michel_index::MilliPersistence;
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempdir::TempDir;
+
+fn index(name: &str) -> Index {
+    Index {
+        name: String::from(name),
+    }
+}
+
+async fn exported_persistence() -> MilliPersistence {
+    let persistence = MilliPersistence::new().unwrap();
+    persistence.init_index(String::from("notes")).await.unwrap();
+    persistence
+        .add_documents(
+            index("notes"),
+            vec![
+                json!({"id": "1", "title": "groceries", "_vector": [1.0, 0.0]}),
+                json!({"id": "2", "title": "holidays"}),
+            ]
+            .into_iter()
+            .map(|document| document.as_object().cloned().unwrap())
+            .collect(),
+        )
+        .await
+        .unwrap();
+
+    persistence
+}
+
+async fn everything(persistence: &MilliPersistence, name: &str) -> Vec<PersistedDocument> {
+    persistence
+        .search_document(index(name), String::new(), None)
+        .await
+        .unwrap()
+}
+
+#[test]
+fn dump_round_trips() {
+    block_on(async {
+        let persistence = exported_persistence().await;
+        let folder = TempDir::new("dump").unwrap();
+
+        let manifest = persistence
+            .export_dump(index("notes"), folder.path())
+            .await
+            .unwrap();
+        persistence
+            .import(folder.path(), Some(String::from("imported")))
+            .await
+            .unwrap();
+
+        assert_eq!(manifest.kind, DumpKind::Documents);
+        assert_eq!(manifest.document_count, 2);
+        assert_eq!(
+            everything(&persistence, "imported").await,
+            everything(&persistence, "notes").await
+        );
+    });
+}
+
+#[test]
+fn snapshot_round_trips() {
+    block_on(async {
+        let persistence = exported_persistence().await;
+        let folder = TempDir::new("snapshot").unwrap();
+
+        let manifest = persistence
+            .export_snapshot(index("notes"), folder.path())
+            .await
+            .unwrap();
+        let restored = MilliPersistence::new().unwrap();
+        restored.import(folder.path(), None).await.unwrap();
+
+        assert_eq!(manifest.kind, DumpKind::Snapshot);
+        assert_eq!(
+            everything(&restored, "notes").await,
+            everything(&persistence, "notes").await
+        );
+    });
+}
+
+#[test]
+fn corrupted_dump_is_refused() {
+    block_on(async {
+        let persistence = exported_persistence().await;
+        let folder = TempDir::new("corrupted").unwrap();
+
+        persistence
+            .export_dump(index("notes"), folder.path())
+            .await
+            .unwrap();
+        fs::write(folder.path().join("documents.ndjson"), "{\"id\": \"3\"}\n").unwrap();
+
+        assert!(dump::verify(folder.path()).is_err());
+        assert!(persistence
+            .import(folder.path(), Some(String::from("corrupted")))
+            .await
+            .is_err());
+        assert!(persistence.stats(index("corrupted")).await.is_err());
+    });
+}
+
+#[test]
+fn indexes_of_a_root_are_found_again() {
+    block_on(async {
+        let root = TempDir::new("indexes").unwrap();
+        let folder = TempDir::new("dump").unwrap();
+
+        exported_persistence()
+            .await
+            .export_dump(index("notes"), folder.path())
+            .await
+            .unwrap();
+        MilliPersistence::open(root.path())
+            .unwrap()
+            .import(folder.path(), None)
+            .await
+            .unwrap();
+
+        // As another process would, once the import is over
+        let persistence = MilliPersistence::open(root.path()).unwrap();
+        assert_eq!(persistence.list_indexes().await.unwrap(), vec!["notes"]);
+        assert_eq!(everything(&persistence, "notes").await.len(), 2);
+        assert!(persistence.init_index(String::from("notes")).await.is_err());
+    });
+}
+
+#[test]
+fn incomplete_import_is_removed() {
+    block_on(async {
+        let root = TempDir::new("indexes").unwrap();
+        let folder = TempDir::new("dump").unwrap();
+
+        exported_persistence()
+            .await
+            .export_dump(index("notes"), folder.path())
+            .await
+            .unwrap();
+
+        // The manifest announces a document the dump doesn't hold
+        let manifest = fs::read_to_string(folder.path().join("manifest.json")).unwrap();
+        fs::write(
+            folder.path().join("manifest.json"),
+            manifest.replace("\"document_count\": 2", "\"document_count\": 3"),
+        )
+        .unwrap();
+
+        let persistence = MilliPersistence::open(root.path()).unwrap();
+        assert!(persistence.import(folder.path(), None).await.is_err());
+        assert!(persistence.list_indexes().await.unwrap().is_empty());
+        assert!(persistence.stats(index("notes")).await.is_err());
+    });
+}
+
+/// The baseline plugin of the michel_core fixtures, allowed its index.
+async fn baseline_plugin(persistence: Arc<MilliPersistence>) -> Plugin<MilliPersistence> {
+    let grants = Arc::new(Grants::in_memory());
+    grants
+        .grant(
+            "baseline",
+            Capability::Index {
+                name: String::from("baseline"),
+            },
+        )
+        .unwrap();
+
+    Plugin::load_from_path(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../michel_core/tests/fixtures/baseline-plugin.wat"),
+        persistence,
+        grants,
+    )
+    .await
+    .unwrap()
+}
+
+#[test]
+fn imported_indexes_are_adopted_by_their_plugin() {
+    block_on(async {
+        let exported = Arc::new(MilliPersistence::new().unwrap());
+        baseline_plugin(exported.clone())
+            .await
+            .index()
+            .await
+            .unwrap();
+
+        let folder = TempDir::new("dump").unwrap();
+        let manifest = exported
+            .export_dump(index("baseline"), folder.path())
+            .await
+            .unwrap();
+        let imported = Arc::new(MilliPersistence::new().unwrap());
+        imported.import(folder.path(), None).await.unwrap();
+
+        assert_eq!(
+            manifest.metadata,
+            exported.metadata(index("baseline")).await.unwrap()
+        );
+        assert_eq!(
+            imported.metadata(index("baseline")).await.unwrap(),
+            manifest.metadata
+        );
+
+        // The plugin writes to the imported index instead of failing to create it again
+        let plugin = baseline_plugin(imported.clone()).await;
+        plugin.migrate_indexes(&imported).await.unwrap();
+        plugin.index().await.unwrap();
+        assert_eq!(everything(&imported, "baseline").await.len(), 1);
+    });
+}
@@ -0,0 +1,60 @@
+use michel_core::conformance::block_on;
+use michel_core::persistence::{Index, IndexMetadata, MichelPersistence};
+use michel_index::MilliPersistence;
+use serde_json::json;
+use tempdir::TempDir;
+
+fn index(name: &str) -> Index {
+    Index {
+        name: String::from(name),
+    }
+}
+
+#[test]
+fn indexes_and_their_versions_outlive_the_persistence() {
+    block_on(async {
+        let root = TempDir::new("indexes").unwrap();
+        let metadata = IndexMetadata {
+            plugin: String::from("notes-plugin"),
+            plugin_version: String::from("1.2.0"),
+            schema_version: 3,
+        };
+
+        let persistence = MilliPersistence::open(root.path()).unwrap();
+        persistence
+            .init_index(String::from("notes/2023"))
+            .await
+            .unwrap();
+        persistence
+            .set_metadata(index("notes/2023"), metadata.clone())
+            .await
+            .unwrap();
+        persistence
+            .add_document(
+                index("notes/2023"),
+                json!({"id": "1", "title": "groceries"})
+                    .as_object()
+                    .cloned()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        persistence.flush(index("notes/2023")).await.unwrap();
+
+        // What a plugin finds on its next load, to migrate its indexes
+        let reopened = MilliPersistence::open(root.path()).unwrap();
+        assert_eq!(reopened.list_indexes().await.unwrap(), vec!["notes/2023"]);
+        assert_eq!(
+            reopened.metadata(index("notes/2023")).await.unwrap(),
+            Some(metadata)
+        );
+        assert_eq!(
+            reopened
+                .stats(index("notes/2023"))
+                .await
+                .unwrap()
+                .document_count,
+            1
+        );
+    });
+}
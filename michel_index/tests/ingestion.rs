@@ -0,0 +1,62 @@
+use michel_core::conformance::block_on;
+use michel_core::persistence::{Index, MichelPersistence, PersistedDocument};
+use michel_index::{IngestionConfig, MilliPersistence};
+use serde_json::json;
+use std::time::Duration;
+
+fn index(name: &str) -> Index {
+    Index {
+        name: String::from(name),
+    }
+}
+
+fn document(value: serde_json::Value) -> PersistedDocument {
+    value.as_object().cloned().unwrap()
+}
+
+#[test]
+fn bad_documents_only_fail_their_push() {
+    block_on(async {
+        // A long window, so that every push ends up in the same batch
+        let persistence = MilliPersistence::new()
+            .unwrap()
+            .with_ingestion(IngestionConfig {
+                batch_window: Duration::from_secs(5),
+                ..IngestionConfig::default()
+            });
+        persistence.init_index(String::from("notes")).await.unwrap();
+
+        for value in [
+            json!({"id": "1", "title": "groceries"}),
+            json!({"id": "not a valid identifier!", "title": "broken"}),
+            json!({"id": "2", "title": "holidays"}),
+            json!({"id": "neither is this one?", "title": "broken"}),
+        ] {
+            persistence
+                .add_document(index("notes"), document(value))
+                .await
+                .unwrap();
+        }
+
+        let error = persistence.flush(index("notes")).await.unwrap_err();
+        assert!(
+            error.to_string().starts_with("2 pushes failed"),
+            "{}",
+            error
+        );
+
+        let found = persistence
+            .search_document(index("notes"), String::new(), None)
+            .await
+            .unwrap();
+        let mut identifiers: Vec<&str> = found
+            .iter()
+            .filter_map(|document| document["id"].as_str())
+            .collect();
+        identifiers.sort();
+        assert_eq!(identifiers, vec!["1", "2"]);
+
+        // Failures are only reported once
+        persistence.flush(index("notes")).await.unwrap();
+    });
+}
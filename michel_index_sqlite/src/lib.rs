@@ -0,0 +1,542 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use michel_core::embedder::{cosine_similarity, document_text, Embedder};
+use michel_core::persistence::{
+    decode_vector, encode_vector, searchable_text, take_vector, DateFilter, HybridQuery, Index,
+    IndexMetadata, IndexStats, MichelPersistence, PersistedDocument, SortOrder, IDENTIFIER_FIELD,
+    TIMESTAMP_KEY,
+};
+use rusqlite::types::ToSql;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
+use tokio::task;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS indexes (
+        name TEXT PRIMARY KEY,
+        updated_at INTEGER,
+        metadata TEXT
+    );
+
+    CREATE TABLE IF NOT EXISTS documents (
+        index_name TEXT NOT NULL,
+        id TEXT NOT NULL,
+        body TEXT NOT NULL,
+        vector BLOB,
+        PRIMARY KEY (index_name, id)
+    );
+
+    CREATE VIRTUAL TABLE IF NOT EXISTS documents_fts USING fts5(
+        index_name UNINDEXED,
+        id UNINDEXED,
+        content
+    );
+";
+
+/**
+    Lightweight persistence for a few thousand documents, relying on SQLite and its FTS5
+    full text search rather than milli. SQLite being synchronous, each operation runs on a
+    blocking thread, taking turns on a single connection.
+*/
+pub struct SqlitePersistence {
+    connection: Arc<Mutex<Connection>>,
+    embedder: Option<Arc<dyn Embedder>>,
+}
+
+impl SqlitePersistence {
+    pub fn open<T: AsRef<Path>>(path: T) -> Result<SqlitePersistence> {
+        SqlitePersistence::from_connection(Connection::open(path)?)
+    }
+
+    /// Opens the database at `path` as the backend chosen in the shared config.
+    pub fn open_backend(
+        path: &Path,
+        embedder: Option<Box<dyn Embedder>>,
+    ) -> Result<Box<dyn MichelPersistence>> {
+        let persistence = SqlitePersistence::open(path)?;
+
+        Ok(match embedder {
+            Some(embedder) => Box::new(persistence.with_embedder(embedder)),
+            None => Box::new(persistence),
+        })
+    }
+
+    pub fn in_memory() -> Result<SqlitePersistence> {
+        SqlitePersistence::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(connection: Connection) -> Result<SqlitePersistence> {
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(SqlitePersistence {
+            connection: Arc::new(Mutex::new(connection)),
+            embedder: None,
+        })
+    }
+
+    /// Embeds the documents pushed without an embedding, and the hybrid search queries.
+    pub fn with_embedder<E: Embedder + 'static>(mut self, embedder: E) -> SqlitePersistence {
+        self.embedder = Some(Arc::new(embedder));
+        self
+    }
+
+    /// Runs some work on the connection, off the async runtime.
+    async fn run<T, F>(&self, work: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let connection = self.connection.clone();
+
+        task::spawn_blocking(move || {
+            let mut connection = connection
+                .lock()
+                .map_err(|_| anyhow!("sqlite connection poisoned"))?;
+
+            work(&mut connection)
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl MichelPersistence for SqlitePersistence {
+    async fn init_index(&self, name: String) -> Result<()> {
+        self.run(move |connection| {
+            if index_exists(connection, &name)? {
+                return Err(anyhow!("index already exists"));
+            }
+
+            connection.execute("INSERT INTO indexes (name) VALUES (?1)", params![name])?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_indexes(&self) -> Result<Vec<String>> {
+        self.run(|connection| {
+            let mut statement = connection.prepare("SELECT name FROM indexes ORDER BY name")?;
+            let names = statement.query_map([], |row| row.get::<_, String>(0))?;
+
+            names.map(|name| name.map_err(Into::into)).collect()
+        })
+        .await
+    }
+
+    /// Indexes share the database file, so the size is the one of their documents and vectors.
+    async fn stats(&self, index: Index) -> Result<IndexStats> {
+        self.run(move |connection| {
+            if !index_exists(connection, &index.name)? {
+                return Err(anyhow!("index not found"));
+            }
+
+            let (document_count, size_on_disk): (i64, i64) = connection.query_row(
+                "SELECT COUNT(*), COALESCE(SUM(length(body) + COALESCE(length(vector), 0)), 0)
+                 FROM documents WHERE index_name = ?1",
+                params![index.name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+
+            let mut statement = connection.prepare(
+                "SELECT field.key, COUNT(*) FROM documents, json_each(documents.body) AS field
+                 WHERE documents.index_name = ?1 GROUP BY field.key",
+            )?;
+            let field_distribution = statement
+                .query_map(params![index.name], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+                })?
+                .map(|field| {
+                    let (field, count) = field?;
+                    Ok((field, u64::try_from(count)?))
+                })
+                .collect::<Result<BTreeMap<String, u64>>>()?;
+
+            let updated_at: Option<i64> = connection.query_row(
+                "SELECT updated_at FROM indexes WHERE name = ?1",
+                params![index.name],
+                |row| row.get(0),
+            )?;
+
+            Ok(IndexStats {
+                name: index.name,
+                document_count: u64::try_from(document_count)?,
+                field_distribution,
+                size_on_disk: u64::try_from(size_on_disk)?,
+                last_update: updated_at
+                    .map(OffsetDateTime::from_unix_timestamp)
+                    .transpose()?,
+            })
+        })
+        .await
+    }
+
+    async fn metadata(&self, index: Index) -> Result<Option<IndexMetadata>> {
+        self.run(move |connection| {
+            let metadata: Option<Option<String>> = connection
+                .query_row(
+                    "SELECT metadata FROM indexes WHERE name = ?1",
+                    params![index.name],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            match metadata.ok_or(anyhow!("index not found"))? {
+                Some(metadata) => Ok(Some(serde_json::from_str(&metadata)?)),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    async fn set_metadata(&self, index: Index, metadata: IndexMetadata) -> Result<()> {
+        self.run(move |connection| {
+            let updated = connection.execute(
+                "UPDATE indexes SET metadata = ?1 WHERE name = ?2",
+                params![serde_json::to_string(&metadata)?, index.name],
+            )?;
+
+            if updated == 0 {
+                return Err(anyhow!("index not found"));
+            }
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn clear_index(&self, index: Index) -> Result<()> {
+        self.run(move |connection| {
+            if !index_exists(connection, &index.name)? {
+                return Err(anyhow!("index not found"));
+            }
+
+            let transaction = connection.transaction()?;
+            transaction.execute(
+                "DELETE FROM documents WHERE index_name = ?1",
+                params![index.name],
+            )?;
+            transaction.execute(
+                "DELETE FROM documents_fts WHERE index_name = ?1",
+                params![index.name],
+            )?;
+            transaction.execute(
+                "UPDATE indexes SET updated_at = ?1 WHERE name = ?2",
+                params![OffsetDateTime::now_utc().unix_timestamp(), index.name],
+            )?;
+
+            transaction.commit().map_err(Into::into)
+        })
+        .await
+    }
+
+    async fn add_document(&self, index: Index, document: PersistedDocument) -> Result<()> {
+        self.add_documents(index, vec![document]).await
+    }
+
+    async fn add_documents(&self, index: Index, documents: Vec<PersistedDocument>) -> Result<()> {
+        let embedder = self.embedder.clone();
+
+        self.run(move |connection| {
+            if !index_exists(connection, &index.name)? {
+                return Err(anyhow!("index not created"));
+            }
+
+            let transaction = connection.transaction()?;
+
+            for mut document in documents {
+                let vector = match take_vector(&mut document)? {
+                    Some(vector) => Some(vector),
+                    None => embedder
+                        .as_ref()
+                        .map(|embedder| embedder.embed(&document_text(&document)))
+                        .transpose()?,
+                };
+
+                let identifier = document
+                    .get(IDENTIFIER_FIELD)
+                    .and_then(|id| id.as_str())
+                    .ok_or(anyhow!("document without identifier"))?;
+
+                // Like milli, a document without vector keeps the one of the document it replaces
+                transaction.execute(
+                    "INSERT INTO documents (index_name, id, body, vector) VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT (index_name, id) DO UPDATE
+                     SET body = excluded.body, vector = COALESCE(excluded.vector, documents.vector)",
+                    params![
+                        index.name,
+                        identifier,
+                        serde_json::to_string(&document)?,
+                        vector.map(|vector| encode_vector(&vector)),
+                    ],
+                )?;
+                transaction.execute(
+                    "DELETE FROM documents_fts WHERE index_name = ?1 AND id = ?2",
+                    params![index.name, identifier],
+                )?;
+                transaction.execute(
+                    "INSERT INTO documents_fts (index_name, id, content) VALUES (?1, ?2, ?3)",
+                    params![index.name, identifier, searchable_text(&document)],
+                )?;
+            }
+
+            transaction.execute(
+                "UPDATE indexes SET updated_at = ?1 WHERE name = ?2",
+                params![OffsetDateTime::now_utc().unix_timestamp(), index.name],
+            )?;
+
+            transaction.commit().map_err(Into::into)
+        })
+        .await
+    }
+
+    /// Documents are written as soon as they are added.
+    async fn flush(&self, index: Index) -> Result<()> {
+        self.run(move |connection| {
+            if !index_exists(connection, &index.name)? {
+                return Err(anyhow!("index not found"));
+            }
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn search_document(
+        &self,
+        index: Index,
+        query: String,
+        limit: Option<u32>,
+    ) -> Result<Vec<PersistedDocument>> {
+        self.run(move |connection| {
+            search(connection, &index.name, &query, None, limit).map(|documents| {
+                documents
+                    .into_iter()
+                    .map(|(_, document)| document)
+                    .collect()
+            })
+        })
+        .await
+    }
+
+    async fn search_document_by_date(
+        &self,
+        index: Index,
+        query: String,
+        filter: DateFilter,
+        limit: Option<u32>,
+    ) -> Result<Vec<PersistedDocument>> {
+        self.run(move |connection| {
+            search(connection, &index.name, &query, Some(&filter), limit).map(|documents| {
+                documents
+                    .into_iter()
+                    .map(|(_, document)| document)
+                    .collect()
+            })
+        })
+        .await
+    }
+
+    async fn hybrid_search_document(
+        &self,
+        index: Index,
+        query: HybridQuery,
+        limit: Option<u32>,
+    ) -> Result<Vec<PersistedDocument>> {
+        let embedder = self.embedder.clone();
+
+        self.run(move |connection| {
+            let limit: usize = limit.unwrap_or(u32::MAX).try_into()?;
+            let candidates = query.candidates(limit);
+
+            let query_vector = match &query.vector {
+                Some(vector) => Some(vector.clone()),
+                None => embedder
+                    .as_ref()
+                    .map(|embedder| embedder.embed(&query.query))
+                    .transpose()?,
+            };
+
+            let keyword_results = search(
+                connection,
+                &index.name,
+                &query.query,
+                None,
+                Some(u32::try_from(candidates).unwrap_or(u32::MAX)),
+            )?;
+
+            let mut similarities = Vec::new();
+            if let Some(vector) = query_vector {
+                let mut statement = connection.prepare(
+                    "SELECT id, vector FROM documents WHERE index_name = ?1 AND vector IS NOT NULL",
+                )?;
+                let vectors = statement.query_map(params![index.name], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+                })?;
+
+                for entry in vectors {
+                    let (identifier, bytes) = entry?;
+                    let document_vector = decode_vector(&bytes);
+
+                    if document_vector.len() == vector.len() {
+                        similarities
+                            .push((identifier, cosine_similarity(&vector, &document_vector)));
+                    }
+                }
+
+                similarities.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+                similarities.truncate(candidates);
+            }
+
+            let ranked = query.blend(
+                keyword_results
+                    .into_iter()
+                    .map(|(identifier, _)| identifier)
+                    .collect(),
+                similarities,
+                limit,
+            );
+
+            ranked
+                .iter()
+                .map(|identifier| document_by_id(connection, &index.name, identifier))
+                .collect()
+        })
+        .await
+    }
+}
+
+fn index_exists(connection: &Connection, name: &str) -> Result<bool> {
+    let found = connection
+        .query_row(
+            "SELECT name FROM indexes WHERE name = ?1",
+            params![name],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?;
+
+    Ok(found.is_some())
+}
+
+fn document_by_id(
+    connection: &Connection,
+    index_name: &str,
+    identifier: &str,
+) -> Result<PersistedDocument> {
+    let body: String = connection.query_row(
+        "SELECT body FROM documents WHERE index_name = ?1 AND id = ?2",
+        params![index_name, identifier],
+        |row| row.get(0),
+    )?;
+
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// Runs a full text search, every word of the query having to prefix a word of the document.
+/// An empty query matches every document, as with milli.
+fn search(
+    connection: &Connection,
+    index_name: &str,
+    query: &str,
+    filter: Option<&DateFilter>,
+    limit: Option<u32>,
+) -> Result<Vec<(String, PersistedDocument)>> {
+    if !index_exists(connection, index_name)? {
+        return Err(anyhow!("index not found"));
+    }
+
+    let mut sql = String::from("SELECT documents.id, documents.body FROM documents");
+    let mut conditions = vec!["documents.index_name = ?"];
+    let mut values: Vec<Box<dyn ToSql>> = vec![Box::new(String::from(index_name))];
+    let mut order = "documents.rowid";
+    let mut order_values: Vec<Box<dyn ToSql>> = vec![];
+
+    if let Some(expression) = match_expression(query) {
+        sql.push_str(
+            " JOIN documents_fts ON documents_fts.index_name = documents.index_name
+              AND documents_fts.id = documents.id",
+        );
+        conditions.push("documents_fts MATCH ?");
+        values.push(Box::new(expression));
+        order = "bm25(documents_fts)";
+    }
+
+    if let Some(filter) = filter {
+        filter.validate()?;
+
+        let path = timestamp_path(&filter.field);
+        let (after, before) = filter.timestamp_range();
+
+        if let Some(after) = after {
+            conditions.push("json_extract(documents.body, ?) >= ?");
+            values.push(Box::new(path.clone()));
+            values.push(Box::new(after));
+        }
+
+        if let Some(before) = before {
+            conditions.push("json_extract(documents.body, ?) <= ?");
+            values.push(Box::new(path.clone()));
+            values.push(Box::new(before));
+        }
+
+        // Documents without the field come last, as with milli
+        if let Some(sort_order) = &filter.order {
+            order = match sort_order {
+                SortOrder::Ascending => {
+                    "json_extract(documents.body, ?) IS NULL, json_extract(documents.body, ?) ASC"
+                }
+                SortOrder::Descending => {
+                    "json_extract(documents.body, ?) IS NULL, json_extract(documents.body, ?) DESC"
+                }
+            };
+            order_values.push(Box::new(path.clone()));
+            order_values.push(Box::new(path));
+        }
+    }
+
+    sql.push_str(" WHERE ");
+    sql.push_str(&conditions.join(" AND "));
+    sql.push_str(" ORDER BY ");
+    sql.push_str(order);
+    sql.push_str(" LIMIT ?");
+    values.append(&mut order_values);
+    values.push(Box::new(i64::from(limit.unwrap_or(u32::MAX))));
+
+    let mut statement = connection.prepare(&sql)?;
+    let rows = statement.query_map(params_from_iter(values.iter()), |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    rows.map(|row| {
+        let (identifier, body) = row?;
+        Ok((identifier, serde_json::from_str(&body)?))
+    })
+    .collect()
+}
+
+fn match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"*", term.to_lowercase()))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// JSON path of the timestamp of a field, whose keys have been validated not to hold quotes.
+fn timestamp_path(field: &str) -> String {
+    let keys: String = field
+        .split('.')
+        .map(|key| format!(".\"{}\"", key))
+        .collect();
+
+    format!("${}.{}", keys, TIMESTAMP_KEY)
+}
@@ -0,0 +1,3 @@
+use michel_index_sqlite::SqlitePersistence;
+
+michel_core::persistence_conformance_tests!(SqlitePersistence::in_memory().unwrap());
@@ -0,0 +1,306 @@
+/*!
+    Conversions between the types of the SDK and the ones generated from the WIT, which
+    flatten lists and records in pre-order, each container carrying its number of children.
+*/
+
+use crate::bindings::types;
+use crate::{
+    Action, DateFilter, Document, Entry, Error, HttpMethod, HttpRequest, HttpResponse,
+    IndexMetadata, IndexStats, Migration, PluginInfo, SortOrder, Value,
+};
+
+impl From<Value> for types::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Text(text) => types::Value::Text(text),
+            Value::Datetime(datetime) => types::Value::Datetime(datetime),
+            Value::Number(number) => types::Value::Number(number),
+            Value::Float(float) => types::Value::Float(float),
+            Value::Boolean(boolean) => types::Value::Boolean(boolean),
+            Value::Null => types::Value::Null,
+            Value::List(values) => {
+                let mut nodes = Vec::new();
+                for value in values {
+                    push_node(&mut nodes, None, value);
+                }
+
+                types::Value::List(nodes)
+            }
+            Value::Record(fields) => {
+                let mut nodes = Vec::new();
+                for (key, value) in fields {
+                    push_node(&mut nodes, Some(key), value);
+                }
+
+                types::Value::Record(nodes)
+            }
+        }
+    }
+}
+
+fn push_node(nodes: &mut Vec<types::Node>, key: Option<String>, value: Value) {
+    let (node_value, children) = match value {
+        Value::Text(text) => (types::NodeValue::Text(text), Vec::new()),
+        Value::Datetime(datetime) => (types::NodeValue::Datetime(datetime), Vec::new()),
+        Value::Number(number) => (types::NodeValue::Number(number), Vec::new()),
+        Value::Float(float) => (types::NodeValue::Float(float), Vec::new()),
+        Value::Boolean(boolean) => (types::NodeValue::Boolean(boolean), Vec::new()),
+        Value::Null => (types::NodeValue::Null, Vec::new()),
+        Value::List(values) => (
+            types::NodeValue::List(values.len() as u32),
+            values.into_iter().map(|value| (None, value)).collect(),
+        ),
+        Value::Record(fields) => (
+            types::NodeValue::Record(fields.len() as u32),
+            fields
+                .into_iter()
+                .map(|(key, value)| (Some(key), value))
+                .collect(),
+        ),
+    };
+
+    nodes.push(types::Node {
+        key,
+        value: node_value,
+    });
+
+    for (key, value) in children {
+        push_node(nodes, key, value);
+    }
+}
+
+impl From<types::Value> for Value {
+    fn from(value: types::Value) -> Self {
+        match value {
+            types::Value::Text(text) => Value::Text(text),
+            types::Value::Datetime(datetime) => Value::Datetime(datetime),
+            types::Value::Number(number) => Value::Number(number),
+            types::Value::Float(float) => Value::Float(float),
+            types::Value::Boolean(boolean) => Value::Boolean(boolean),
+            types::Value::Null => Value::Null,
+            types::Value::List(nodes) => Value::List(
+                read_nodes(&mut nodes.into_iter(), None)
+                    .into_iter()
+                    .map(|(_, value)| value)
+                    .collect(),
+            ),
+            types::Value::Record(nodes) => Value::Record(
+                read_nodes(&mut nodes.into_iter(), None)
+                    .into_iter()
+                    .map(|(key, value)| (key.unwrap_or_default(), value))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Reads `count` sibling nodes and their children, or every node left when `None`.
+fn read_nodes(
+    nodes: &mut impl Iterator<Item = types::Node>,
+    count: Option<u32>,
+) -> Vec<(Option<String>, Value)> {
+    let mut values = Vec::new();
+
+    while count.map_or(true, |count| values.len() < count as usize) {
+        let node = match nodes.next() {
+            Some(node) => node,
+            None => break,
+        };
+
+        let value = match node.value {
+            types::NodeValue::Text(text) => Value::Text(text),
+            types::NodeValue::Datetime(datetime) => Value::Datetime(datetime),
+            types::NodeValue::Number(number) => Value::Number(number),
+            types::NodeValue::Float(float) => Value::Float(float),
+            types::NodeValue::Boolean(boolean) => Value::Boolean(boolean),
+            types::NodeValue::Null => Value::Null,
+            types::NodeValue::List(children) => Value::List(
+                read_nodes(nodes, Some(children))
+                    .into_iter()
+                    .map(|(_, value)| value)
+                    .collect(),
+            ),
+            types::NodeValue::Record(children) => Value::Record(
+                read_nodes(nodes, Some(children))
+                    .into_iter()
+                    .map(|(key, value)| (key.unwrap_or_default(), value))
+                    .collect(),
+            ),
+        };
+
+        values.push((node.key, value));
+    }
+
+    values
+}
+
+impl From<Document> for types::Document {
+    fn from(document: Document) -> Self {
+        types::Document {
+            identifier: document.identifier,
+            fields: document
+                .fields
+                .into_iter()
+                .map(|(name, value)| types::Field {
+                    name,
+                    value: value.into(),
+                })
+                .collect(),
+            embedding: document.embedding,
+        }
+    }
+}
+
+impl From<types::Document> for Document {
+    fn from(document: types::Document) -> Self {
+        Document {
+            identifier: document.identifier,
+            fields: document
+                .fields
+                .into_iter()
+                .map(|field| (field.name, field.value.into()))
+                .collect(),
+            embedding: document.embedding,
+        }
+    }
+}
+
+impl From<DateFilter> for types::DateFilter {
+    fn from(filter: DateFilter) -> Self {
+        types::DateFilter {
+            field: filter.field,
+            after: filter.after,
+            before: filter.before,
+            order: filter.order.map(|order| match order {
+                SortOrder::Ascending => types::SortOrder::Ascending,
+                SortOrder::Descending => types::SortOrder::Descending,
+            }),
+        }
+    }
+}
+
+impl From<types::IndexStats> for IndexStats {
+    fn from(stats: types::IndexStats) -> Self {
+        IndexStats {
+            name: stats.name,
+            document_count: stats.document_count,
+            field_distribution: stats
+                .field_distribution
+                .into_iter()
+                .map(|count| (count.field, count.count))
+                .collect(),
+            size_on_disk: stats.size_on_disk,
+            last_update: stats.last_update,
+        }
+    }
+}
+
+impl From<Entry> for types::Entry {
+    fn from(entry: Entry) -> Self {
+        types::Entry {
+            title: entry.title,
+            description: entry.description,
+            preview: entry.preview,
+            actions: entry
+                .actions
+                .into_iter()
+                .map(|action| types::Action {
+                    identifier: action.identifier,
+                    label: action.label,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<types::Entry> for Entry {
+    fn from(entry: types::Entry) -> Self {
+        Entry {
+            title: entry.title,
+            description: entry.description,
+            preview: entry.preview,
+            actions: entry
+                .actions
+                .into_iter()
+                .map(|action| Action {
+                    identifier: action.identifier,
+                    label: action.label,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<PluginInfo> for types::PluginInfo {
+    fn from(info: PluginInfo) -> Self {
+        types::PluginInfo {
+            identifier: info.identifier,
+            name: info.name,
+            description: info.description,
+            version: info.version,
+            schema_version: info.schema_version,
+            icon: info.icon,
+            url: info.url,
+        }
+    }
+}
+
+impl From<types::IndexMetadata> for IndexMetadata {
+    fn from(metadata: types::IndexMetadata) -> Self {
+        IndexMetadata {
+            plugin_version: metadata.plugin_version,
+            schema_version: metadata.schema_version,
+        }
+    }
+}
+
+impl From<Migration> for types::Migration {
+    fn from(migration: Migration) -> Self {
+        match migration {
+            Migration::Keep => types::Migration::Keep,
+            Migration::Rebuild => types::Migration::Rebuild,
+        }
+    }
+}
+
+impl From<Error> for types::Error {
+    fn from(error: Error) -> Self {
+        types::Error::InvalidValue(error.message)
+    }
+}
+
+impl From<types::Error> for Error {
+    fn from(error: types::Error) -> Self {
+        match error {
+            types::Error::InvalidValue(message) => Error::new(message),
+        }
+    }
+}
+
+impl From<HttpRequest> for types::HttpRequest {
+    fn from(request: HttpRequest) -> Self {
+        types::HttpRequest {
+            method: match request.method {
+                HttpMethod::Get => types::HttpMethod::Get,
+                HttpMethod::Head => types::HttpMethod::Head,
+                HttpMethod::Post => types::HttpMethod::Post,
+                HttpMethod::Put => types::HttpMethod::Put,
+                HttpMethod::Patch => types::HttpMethod::Patch,
+                HttpMethod::Delete => types::HttpMethod::Delete,
+            },
+            url: request.url,
+            headers: request.headers,
+            body: request.body,
+        }
+    }
+}
+
+impl From<types::HttpResponse> for HttpResponse {
+    fn from(response: types::HttpResponse) -> Self {
+        HttpResponse {
+            status: response.status,
+            headers: response.headers,
+            body: response.body,
+        }
+    }
+}
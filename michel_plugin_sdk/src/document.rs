@@ -0,0 +1,176 @@
+/// Value of a document field, lists and records nesting any value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text(String),
+    /// An RFC 3339 datetime, which indexes can be filtered and sorted by.
+    Datetime(String),
+    Number(i64),
+    Float(f64),
+    Boolean(bool),
+    Null,
+    List(Vec<Value>),
+    Record(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Value::Text(text) | Value::Datetime(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<i64> {
+        match self {
+            Value::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(float) => Some(*float),
+            Value::Number(number) => Some(*number as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_boolean(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(boolean) => Some(*boolean),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Value of `key`, for a record.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Record(fields) => fields
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(String::from(value))
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Self {
+        Value::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+/**
+    A document of an index, built field by field :
+
+    ```ignore
+    let document = Document::new("notes/todo.md")
+        .text("title", "Todo")
+        .datetime("modified", "2023-03-12T10:00:00Z")
+        .field("tags", vec!["home", "work"]);
+    ```
+*/
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Document {
+    pub identifier: String,
+    pub fields: Vec<(String, Value)>,
+    /// Vector of the document for hybrid searches, computed by the plugin.
+    pub embedding: Option<Vec<f32>>,
+}
+
+impl Document {
+    pub fn new(identifier: impl Into<String>) -> Document {
+        Document {
+            identifier: identifier.into(),
+            ..Document::default()
+        }
+    }
+
+    /// Sets a field, replacing the previous value of the same name.
+    pub fn field(mut self, name: impl Into<String>, value: impl Into<Value>) -> Document {
+        let name = name.into();
+        self.fields.retain(|(field, _)| *field != name);
+        self.fields.push((name, value.into()));
+        self
+    }
+
+    pub fn text(self, name: impl Into<String>, text: impl Into<String>) -> Document {
+        self.field(name, Value::Text(text.into()))
+    }
+
+    /// `datetime` is an RFC 3339 datetime, like `2023-03-12T10:00:00Z`.
+    pub fn datetime(self, name: impl Into<String>, datetime: impl Into<String>) -> Document {
+        self.field(name, Value::Datetime(datetime.into()))
+    }
+
+    pub fn number(self, name: impl Into<String>, number: i64) -> Document {
+        self.field(name, Value::Number(number))
+    }
+
+    pub fn float(self, name: impl Into<String>, float: f64) -> Document {
+        self.field(name, Value::Float(float))
+    }
+
+    pub fn boolean(self, name: impl Into<String>, boolean: bool) -> Document {
+        self.field(name, Value::Boolean(boolean))
+    }
+
+    pub fn record(self, name: impl Into<String>, fields: Vec<(String, Value)>) -> Document {
+        self.field(name, Value::Record(fields))
+    }
+
+    pub fn embedding(mut self, embedding: Vec<f32>) -> Document {
+        self.embedding = Some(embedding);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value)
+    }
+}
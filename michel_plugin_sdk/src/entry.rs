@@ -0,0 +1,44 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Action {
+    pub identifier: String,
+    pub label: String,
+}
+
+/// What the plugin shows for an input, built with [`Entry::new`] then its chained setters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub title: String,
+    pub description: String,
+    pub preview: Option<String>,
+    pub actions: Vec<Action>,
+}
+
+impl Entry {
+    pub fn new(title: impl Into<String>) -> Entry {
+        Entry {
+            title: title.into(),
+            description: String::new(),
+            preview: None,
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Entry {
+        self.description = description.into();
+        self
+    }
+
+    pub fn preview(mut self, preview: impl Into<String>) -> Entry {
+        self.preview = Some(preview.into());
+        self
+    }
+
+    /// Offered to the user, then passed back to [`crate::MichelPlugin::execute_action`].
+    pub fn action(mut self, identifier: impl Into<String>, label: impl Into<String>) -> Entry {
+        self.actions.push(Action {
+            identifier: identifier.into(),
+            label: label.into(),
+        });
+        self
+    }
+}
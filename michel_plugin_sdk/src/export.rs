@@ -0,0 +1,52 @@
+/*!
+    Calls the host makes to the plugin, converted for [`crate::MichelPlugin`]. Used by
+    [`crate::export_plugin!`] only.
+*/
+
+use crate::bindings::types;
+use crate::MichelPlugin;
+
+pub fn info<T: MichelPlugin>(plugin: &T) -> types::PluginInfo {
+    plugin.info().into()
+}
+
+pub fn index<T: MichelPlugin>(plugin: &T) -> Result<(), types::Error> {
+    plugin.index().map_err(Into::into)
+}
+
+pub fn migrate<T: MichelPlugin>(
+    plugin: &T,
+    index: String,
+    previous: types::IndexMetadata,
+) -> types::Migration {
+    plugin.migrate(&index, previous.into()).into()
+}
+
+/// Configuration isn't exposed to plugins yet.
+pub fn config() -> types::PluginConfig {
+    types::PluginConfig {
+        fs_access: Vec::new(),
+    }
+}
+
+pub fn for_input<T: MichelPlugin>(plugin: &T, input: String) -> Vec<types::Entry> {
+    plugin
+        .for_input(&input)
+        .into_iter()
+        .map(Into::into)
+        .collect()
+}
+
+pub fn autocomplete<T: MichelPlugin>(plugin: &T, input: String) -> Option<String> {
+    plugin.autocomplete(&input)
+}
+
+pub fn execute_action<T: MichelPlugin>(
+    plugin: &T,
+    entry: types::Entry,
+    action: String,
+) -> Result<(), types::Error> {
+    plugin
+        .execute_action(entry.into(), &action)
+        .map_err(Into::into)
+}
@@ -0,0 +1,151 @@
+/*!
+    Indexes of the plugin. Each one has to be declared in the package manifest, or granted by
+    the user when first initialized.
+*/
+
+use crate::bindings::michel_api;
+use crate::{Document, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/// Documents which `field` datetime is between `after` and `before`, both included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateFilter {
+    pub field: String,
+    pub after: Option<String>,
+    pub before: Option<String>,
+    pub order: Option<SortOrder>,
+}
+
+impl DateFilter {
+    pub fn on(field: impl Into<String>) -> DateFilter {
+        DateFilter {
+            field: field.into(),
+            after: None,
+            before: None,
+            order: None,
+        }
+    }
+
+    /// `datetime` is an RFC 3339 datetime.
+    pub fn after(mut self, datetime: impl Into<String>) -> DateFilter {
+        self.after = Some(datetime.into());
+        self
+    }
+
+    /// `datetime` is an RFC 3339 datetime.
+    pub fn before(mut self, datetime: impl Into<String>) -> DateFilter {
+        self.before = Some(datetime.into());
+        self
+    }
+
+    pub fn newest_first(mut self) -> DateFilter {
+        self.order = Some(SortOrder::Descending);
+        self
+    }
+
+    pub fn oldest_first(mut self) -> DateFilter {
+        self.order = Some(SortOrder::Ascending);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexStats {
+    pub name: String,
+    pub document_count: u64,
+    /// Number of documents having each field.
+    pub field_distribution: Vec<(String, u64)>,
+    pub size_on_disk: u64,
+    /// RFC 3339 datetime of the last flush.
+    pub last_update: Option<String>,
+}
+
+pub struct Index {
+    name: String,
+}
+
+impl Index {
+    /**
+        Creates the index, or reuses the one created on a previous run. Fails until the user
+        grants the index to the plugin, which is asked again on the next indexing.
+    */
+    pub fn init(name: impl Into<String>) -> Result<Index> {
+        let name = name.into();
+        michel_api::init_index(&name)?;
+
+        Ok(Index { name })
+    }
+
+    /**
+        An index initialized by [`Index::init`], on this run or a previous one, without asking
+        the host again : searches find nothing until it is.
+    */
+    pub fn open(name: impl Into<String>) -> Index {
+        Index { name: name.into() }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Searchable once flushed, which the host does when [`crate::MichelPlugin::index`] returns.
+    pub fn add(&self, document: Document) -> Result<()> {
+        michel_api::new_document_for_index(&self.name, &document.into()).map_err(Into::into)
+    }
+
+    pub fn add_all(&self, documents: Vec<Document>) -> Result<()> {
+        let documents: Vec<_> = documents.into_iter().map(Into::into).collect();
+
+        michel_api::new_documents_for_index(&self.name, &documents).map_err(Into::into)
+    }
+
+    pub fn search(&self, query: &str) -> Vec<Document> {
+        michel_api::search_in_index(&self.name, query)
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    pub fn search_by_date(&self, query: &str, filter: DateFilter) -> Result<Vec<Document>> {
+        michel_api::search_in_index_by_date(&self.name, query, &filter.into())
+            .map(|documents| documents.into_iter().map(Into::into).collect())
+            .map_err(Into::into)
+    }
+
+    /**
+        Mixes a keyword search with a semantic one on the embedding, `semantic_ratio` going
+        from 0 for keywords only to 1 for the embedding only.
+    */
+    pub fn hybrid_search(
+        &self,
+        query: &str,
+        embedding: Option<&[f32]>,
+        semantic_ratio: f32,
+    ) -> Vec<Document> {
+        michel_api::hybrid_search_in_index(&self.name, query, embedding, semantic_ratio)
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    /// Makes the documents added so far searchable, without waiting for indexing to end.
+    pub fn flush(&self) -> Result<()> {
+        michel_api::flush_index(&self.name).map_err(Into::into)
+    }
+
+    pub fn stats(&self) -> Result<IndexStats> {
+        michel_api::index_stats(&self.name)
+            .map(Into::into)
+            .map_err(Into::into)
+    }
+}
+
+/// Indexes of the plugin, including the ones it initialized on a previous run.
+pub fn list_indexes() -> Vec<String> {
+    michel_api::list_indexes()
+}
@@ -0,0 +1,31 @@
+/*!
+    Keys and values of the plugin, such as cursors, timestamps or tokens, which the host keeps
+    apart from the ones of other plugins, and from a run to the next. Keys and values are limited
+    in size, and count together against a quota.
+*/
+
+use crate::bindings::kv;
+use crate::Result;
+
+pub fn get(key: &str) -> Option<Vec<u8>> {
+    kv::get(key)
+}
+
+/// The value of `key`, if it's UTF-8 text.
+pub fn get_text(key: &str) -> Option<String> {
+    get(key).and_then(|value| String::from_utf8(value).ok())
+}
+
+pub fn set(key: &str, value: impl AsRef<[u8]>) -> Result<()> {
+    kv::set(key, value.as_ref()).map_err(Into::into)
+}
+
+/// Removes a key, if it exists.
+pub fn delete(key: &str) -> Result<()> {
+    kv::delete(key).map_err(Into::into)
+}
+
+/// Keys starting with `prefix`, sorted.
+pub fn list_prefix(prefix: &str) -> Vec<String> {
+    kv::list_prefix(prefix)
+}
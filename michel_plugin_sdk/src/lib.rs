@@ -0,0 +1,279 @@
+/*!
+    Michel plugins in Rust : implement [`MichelPlugin`], then export it with [`export_plugin!`],
+    which also declares the version of the `michel:plugin` world the plugin targets.
+
+    ```ignore
+    use michel_plugin_sdk::{export_plugin, Entry, MichelPlugin, PluginInfo};
+
+    #[derive(Default)]
+    struct Hello;
+
+    impl MichelPlugin for Hello {
+        fn info(&self) -> PluginInfo {
+            PluginInfo::new("hello", "Hello", "0.1.0")
+        }
+
+        fn for_input(&self, input: &str) -> Vec<Entry> {
+            vec![Entry::new(format!("Hello {}", input))]
+        }
+    }
+
+    export_plugin!(Hello);
+    ```
+
+    Built for `wasm32-wasi`, the module then becomes a component with `wasm-tools component new`
+    and the WASI adapter of the host, see `michel_plugin_template`.
+*/
+
+mod convert;
+mod document;
+mod entry;
+#[doc(hidden)]
+pub mod export;
+mod http;
+mod index;
+pub mod kv;
+
+pub use document::{Document, Value};
+pub use entry::{Action, Entry};
+pub use http::{HttpMethod, HttpRequest, HttpResponse};
+pub use index::{list_indexes, DateFilter, Index, IndexStats, SortOrder};
+
+use std::fmt;
+
+/// Bindings of the world, generated from its WIT.
+#[doc(hidden)]
+pub mod bindings {
+    wit_bindgen::generate!({
+        world: "plugong",
+        path: "../wit/michel-plugin@0.3.0",
+        macro_export,
+        macro_call_prefix: "::michel_plugin_sdk::bindings::",
+        export_macro_name: "export_plugin_api",
+    });
+}
+
+/// Version of the `michel:plugin` world the SDK binds, recorded in the plugins it exports.
+pub const API_VERSION: &str = "0.3.0";
+
+/// [`API_VERSION`] as the content of the custom section the host reads it from.
+#[doc(hidden)]
+pub const API_VERSION_SECTION: [u8; API_VERSION.len()] = {
+    let mut section = [0; API_VERSION.len()];
+    let mut i = 0;
+    while i < section.len() {
+        section[i] = API_VERSION.as_bytes()[i];
+        i += 1;
+    }
+    section
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginInfo {
+    pub identifier: String,
+    pub name: String,
+    pub description: String,
+    /// Semver version, which has to match the one of the package manifest.
+    pub version: String,
+    /// Version of the documents the plugin writes, to bump whenever their shape changes.
+    pub schema_version: u32,
+    pub icon: Option<String>,
+    pub url: Option<String>,
+}
+
+impl PluginInfo {
+    pub fn new(
+        identifier: impl Into<String>,
+        name: impl Into<String>,
+        version: impl Into<String>,
+    ) -> PluginInfo {
+        PluginInfo {
+            identifier: identifier.into(),
+            name: name.into(),
+            description: String::new(),
+            version: version.into(),
+            schema_version: 1,
+            icon: None,
+            url: None,
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> PluginInfo {
+        self.description = description.into();
+        self
+    }
+
+    pub fn schema_version(mut self, schema_version: u32) -> PluginInfo {
+        self.schema_version = schema_version;
+        self
+    }
+
+    pub fn icon(mut self, icon: impl Into<String>) -> PluginInfo {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    pub fn url(mut self, url: impl Into<String>) -> PluginInfo {
+        self.url = Some(url.into());
+        self
+    }
+}
+
+/// Versions of the plugin that wrote an index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexMetadata {
+    pub plugin_version: String,
+    pub schema_version: u32,
+}
+
+/// What the host does with an index written by another version of the plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Migration {
+    /// The documents are still valid, or the plugin migrated them itself.
+    Keep,
+    /// The documents are dropped, then the plugin indexes again.
+    Rebuild,
+}
+
+/// An error shown to the user, or returned by the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    message: String,
+}
+
+impl Error {
+    pub fn new(message: impl Into<String>) -> Error {
+        Error {
+            message: message.into(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::new(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::new(message)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::new(error.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/**
+    A plugin, created once with [`Default`] when the host starts it. Calls don't overlap, so
+    state changed during a call can live in a `Cell` or a `RefCell`.
+*/
+pub trait MichelPlugin: Default + 'static {
+    fn info(&self) -> PluginInfo;
+
+    /// Entries for what the user typed.
+    fn for_input(&self, input: &str) -> Vec<Entry>;
+
+    /// Fills the indexes of the plugin, which the host makes searchable once it returns.
+    fn index(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called for each index written by another version of the plugin, before indexing.
+    fn migrate(&self, _index: &str, _previous: IndexMetadata) -> Migration {
+        Migration::Rebuild
+    }
+
+    fn autocomplete(&self, _input: &str) -> Option<String> {
+        None
+    }
+
+    /// `entry` is one the plugin returned, `action` the identifier of one of its actions.
+    fn execute_action(&self, _entry: Entry, action: &str) -> Result<()> {
+        Err(Error::new(format!("unknown action {}", action)))
+    }
+}
+
+/**
+    Exports a [`MichelPlugin`] as the `plugin-api` of the component, and records the version
+    of the world it targets in the `michel-plugin-api` custom section.
+*/
+#[macro_export]
+macro_rules! export_plugin {
+    ($plugin:ty) => {
+        #[link_section = "michel-plugin-api"]
+        #[used]
+        static __MICHEL_PLUGIN_API: [u8; $crate::API_VERSION.len()] = $crate::API_VERSION_SECTION;
+
+        ::std::thread_local! {
+            static __MICHEL_PLUGIN: $plugin = <$plugin as ::std::default::Default>::default();
+        }
+
+        struct __MichelPluginExport;
+
+        impl $crate::bindings::plugin_api::PluginApi for __MichelPluginExport {
+            fn info() -> $crate::bindings::types::PluginInfo {
+                __MICHEL_PLUGIN.with($crate::export::info)
+            }
+
+            fn index() -> ::std::result::Result<(), $crate::bindings::types::Error> {
+                __MICHEL_PLUGIN.with($crate::export::index)
+            }
+
+            fn migrate(
+                index: ::std::string::String,
+                previous: $crate::bindings::types::IndexMetadata,
+            ) -> $crate::bindings::types::Migration {
+                __MICHEL_PLUGIN.with(|plugin| $crate::export::migrate(plugin, index, previous))
+            }
+
+            fn config() -> $crate::bindings::types::PluginConfig {
+                $crate::export::config()
+            }
+
+            fn update_config(
+                _config: $crate::bindings::types::PluginConfig,
+            ) -> ::std::result::Result<(), $crate::bindings::types::Error> {
+                ::std::result::Result::Ok(())
+            }
+
+            fn for_input(
+                input: ::std::string::String,
+            ) -> ::std::vec::Vec<$crate::bindings::types::Entry> {
+                __MICHEL_PLUGIN.with(|plugin| $crate::export::for_input(plugin, input))
+            }
+
+            fn autocomplete(
+                input: ::std::string::String,
+            ) -> ::std::option::Option<::std::string::String> {
+                __MICHEL_PLUGIN.with(|plugin| $crate::export::autocomplete(plugin, input))
+            }
+
+            fn execute_action(
+                entry: $crate::bindings::types::Entry,
+                action: ::std::string::String,
+            ) -> ::std::result::Result<(), $crate::bindings::types::Error> {
+                __MICHEL_PLUGIN.with(|plugin| $crate::export::execute_action(plugin, entry, action))
+            }
+        }
+
+        $crate::export_plugin_api!(__MichelPluginExport);
+    };
+}
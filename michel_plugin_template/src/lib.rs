@@ -0,0 +1,75 @@
+use michel_plugin_sdk::{export_plugin, Document, Entry, Index, MichelPlugin, PluginInfo, Result};
+
+const INDEX: &str = "template-bookmarks";
+
+const BOOKMARKS: [(&str, &str, &str); 3] = [
+    (
+        "rust",
+        "The Rust Programming Language",
+        "https://doc.rust-lang.org/book/",
+    ),
+    ("wasm", "WebAssembly", "https://webassembly.org/"),
+    (
+        "wit",
+        "The WIT format",
+        "https://component-model.bytecodealliance.org/",
+    ),
+];
+
+#[derive(Default)]
+struct Template;
+
+impl MichelPlugin for Template {
+    fn info(&self) -> PluginInfo {
+        PluginInfo::new("template", "Template", env!("CARGO_PKG_VERSION"))
+            .description("Searches a few bookmarks, to start a plugin from")
+    }
+
+    fn index(&self) -> Result<()> {
+        let index = Index::init(INDEX)?;
+
+        index.add_all(
+            BOOKMARKS
+                .iter()
+                .map(|(identifier, title, url)| {
+                    Document::new(*identifier)
+                        .text("title", *title)
+                        .text("url", *url)
+                })
+                .collect(),
+        )
+    }
+
+    fn for_input(&self, input: &str) -> Vec<Entry> {
+        Index::open(INDEX)
+            .search(input)
+            .into_iter()
+            .map(|document| {
+                let text = |field| {
+                    document
+                        .get(field)
+                        .and_then(|value| value.as_text())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+
+                Entry::new(text("title"))
+                    .description(text("url"))
+                    .action("print", "Print the URL")
+            })
+            .collect()
+    }
+
+    fn execute_action(&self, entry: Entry, action: &str) -> Result<()> {
+        match action {
+            // Plugins only reach the stderr of the host, until they are granted more
+            "print" => {
+                eprintln!("{}", entry.description);
+                Ok(())
+            }
+            _ => Err(format!("unknown action {}", action).into()),
+        }
+    }
+}
+
+export_plugin!(Template);
@@ -0,0 +1,308 @@
+/*!
+    Tests for plugins, without the desktop app : a plugin is linked to a mock host, which
+    records every call it makes to the host, answers its searches with scripted documents,
+    and preopens a virtual filesystem, then tests call its exports and assert on the results.
+
+    ```ignore
+    use michel_plugin_test::{text_document, HostCall, TestHost};
+
+    #[tokio::test]
+    async fn bookmarks_are_searched() {
+        let mut plugin = TestHost::new()
+            .search_response("bookmarks", "rust", vec![text_document("1", &[("title", "Rust")])])
+            .file("/home/me/bookmarks.txt", "https://www.rust-lang.org")
+            .load("plugin.wasm")
+            .await
+            .unwrap();
+
+        plugin.index().await.unwrap().unwrap();
+        assert_eq!(plugin.documents("bookmarks").len(), 1);
+
+        let entries = plugin.for_input("rust").await.unwrap();
+        assert_eq!(entries[0].title, "Rust");
+        assert!(matches!(
+            plugin.calls().last(),
+            Some(HostCall::SearchInIndex { query, .. }) if query == "rust"
+        ));
+    }
+    ```
+
+    Plugins have to target the latest version of the world, `michel:plugin@0.3.0`.
+*/
+
+mod mock;
+
+pub use mock::HostCall;
+
+use anyhow::{anyhow, Result};
+use host::WasiCtx;
+use michel_core::declared_api_version;
+use michel_core::http::{HttpClient, HttpLimits};
+use michel_core::package::{api_version_for, PluginPackage, HOST_API_VERSION};
+use mock::MockHost;
+use std::fs;
+use std::path::{Component as PathComponent, Path, PathBuf};
+use tempdir::TempDir;
+use wasi_cap_std_sync::dir::Dir;
+use wasi_cap_std_sync::WasiCtxBuilder;
+use wasmtime::component::{bindgen, Component, Linker};
+use wasmtime::{Config, Engine, Store};
+
+bindgen!({
+    world: "plugong",
+    path: "../wit/michel-plugin@0.3.0",
+    async: true
+});
+
+/// A document holding text fields only, to script searches with.
+pub fn text_document(identifier: &str, fields: &[(&str, &str)]) -> types::Document {
+    types::Document {
+        identifier: String::from(identifier),
+        fields: fields
+            .iter()
+            .map(|(name, text)| types::Field {
+                name: String::from(*name),
+                value: types::Value::Text(String::from(*text)),
+            })
+            .collect(),
+        embedding: None,
+    }
+}
+
+/// Host path of `path` in the virtual filesystem rooted at `root`.
+fn host_path(root: &Path, path: &Path) -> PathBuf {
+    root.join(
+        path.components()
+            .filter(|component| matches!(component, PathComponent::Normal(_)))
+            .collect::<PathBuf>(),
+    )
+}
+
+fn wasi_dir_from_path<P: AsRef<Path>>(path: P) -> Result<Dir> {
+    let file = fs::File::open(path)?;
+
+    Ok(Dir::from_cap_std(wasi_cap_std_sync::Dir::from_std_file(
+        file,
+    )))
+}
+
+struct Ctx {
+    wasi: WasiCtx,
+    michel: MockHost,
+}
+
+/// Mock host and virtual filesystem a plugin is loaded with.
+#[derive(Default)]
+pub struct TestHost {
+    host: MockHost,
+    files: Vec<(PathBuf, Vec<u8>)>,
+    folders: Vec<PathBuf>,
+}
+
+impl TestHost {
+    pub fn new() -> TestHost {
+        TestHost::default()
+    }
+
+    /// Documents answered to every search of `query` in `index`.
+    pub fn search_response(
+        mut self,
+        index: &str,
+        query: &str,
+        documents: Vec<types::Document>,
+    ) -> TestHost {
+        self.host
+            .searches
+            .insert((String::from(index), String::from(query)), documents);
+        self
+    }
+
+    /// Response to every request of `url`, whatever its method.
+    pub fn http_response(mut self, url: &str, response: types::HttpResponse) -> TestHost {
+        self.host.responses.insert(String::from(url), response);
+        self
+    }
+
+    /**
+        Sends the requests that weren't scripted to `domain` through the client of the host, with
+        its limits, to test a plugin against a local stand-in server.
+    */
+    pub fn allow_domain(mut self, domain: &str) -> Result<TestHost> {
+        if self.host.http.is_none() {
+            self.host.http = Some(HttpClient::new(HttpLimits::default())?);
+        }
+        self.host.domains.push(String::from(domain));
+
+        Ok(self)
+    }
+
+    /// A key the plugin finds already stored.
+    pub fn kv(self, key: &str, value: impl Into<Vec<u8>>) -> Result<TestHost> {
+        self.host.kv.set(key, value.into())?;
+
+        Ok(self)
+    }
+
+    /// A file of the virtual filesystem, at the absolute path the plugin reads it from.
+    pub fn file(mut self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) -> TestHost {
+        self.files.push((path.into(), contents.into()));
+        self
+    }
+
+    /// An empty folder of the virtual filesystem.
+    pub fn folder(mut self, path: impl Into<PathBuf>) -> TestHost {
+        self.folders.push(path.into());
+        self
+    }
+
+    /// Loads a package, folder or archive, or a bare wasm component.
+    pub async fn load<T: AsRef<Path>>(self, path: T) -> Result<TestPlugin> {
+        let path = path.as_ref();
+
+        if PluginPackage::is_package(path) {
+            let package = PluginPackage::open(path)?;
+            check_api_version(&package.manifest.min_host_api)?;
+
+            return self.instantiate(&package.wasm).await;
+        }
+
+        self.load_wasm(&fs::read(path)?).await
+    }
+
+    /// Loads a bare component, in its binary or text format.
+    pub async fn load_wasm(self, wasm: &[u8]) -> Result<TestPlugin> {
+        check_api_version(&declared_api_version(wasm)?)?;
+
+        self.instantiate(wasm).await
+    }
+
+    async fn instantiate(self, wasm: &[u8]) -> Result<TestPlugin> {
+        let root = TempDir::new("michel-plugin-test")?;
+        for folder in &self.folders {
+            fs::create_dir_all(host_path(root.path(), folder))?;
+        }
+        for (path, contents) in &self.files {
+            let path = host_path(root.path(), path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, contents)?;
+        }
+
+        let mut config = Config::new();
+        config.wasm_component_model(true);
+        config.async_support(true);
+
+        let engine = Engine::new(&config)?;
+        let component = Component::new(&engine, wasm)?;
+        let mut linker: Linker<Ctx> = Linker::new(&engine);
+        host::add_to_linker(&mut linker, |ctx| &mut ctx.wasi)?;
+        Michel::add_to_linker(&mut linker, |ctx| &mut ctx.michel)?;
+
+        // The virtual filesystem is preopened at the root, so absolute paths resolve in it
+        let mut wasi = WasiCtxBuilder::new()
+            .inherit_stdout()
+            .inherit_stderr()
+            .build();
+        wasi.push_preopened_dir(Box::new(wasi_dir_from_path(root.path())?), "/")?;
+
+        let mut store = Store::new(
+            &engine,
+            Ctx {
+                wasi,
+                michel: self.host,
+            },
+        );
+        let (bindings, _) = Michel::instantiate_async(&mut store, &component, &linker).await?;
+
+        Ok(TestPlugin {
+            store,
+            bindings,
+            root,
+        })
+    }
+}
+
+fn check_api_version(target: &semver::Version) -> Result<()> {
+    match api_version_for(target) {
+        Some(version) if version == HOST_API_VERSION => Ok(()),
+        _ => Err(anyhow!(
+            "the plugin targets michel:plugin@{}, while plugins are tested against michel:plugin@{}",
+            target,
+            HOST_API_VERSION
+        )),
+    }
+}
+
+/// A plugin linked to the mock host, which exports are called one after the other.
+pub struct TestPlugin {
+    store: Store<Ctx>,
+    bindings: Michel,
+    root: TempDir,
+}
+
+impl TestPlugin {
+    pub async fn info(&mut self) -> Result<types::PluginInfo> {
+        self.bindings.plugin_api().call_info(&mut self.store).await
+    }
+
+    pub async fn index(&mut self) -> Result<Result<(), types::Error>> {
+        self.bindings.plugin_api().call_index(&mut self.store).await
+    }
+
+    pub async fn for_input(&mut self, input: &str) -> Result<Vec<types::Entry>> {
+        self.bindings
+            .plugin_api()
+            .call_for_input(&mut self.store, input)
+            .await
+    }
+
+    pub async fn autocomplete(&mut self, input: &str) -> Result<Option<String>> {
+        self.bindings
+            .plugin_api()
+            .call_autocomplete(&mut self.store, input)
+            .await
+    }
+
+    pub async fn execute_action(
+        &mut self,
+        entry: &types::Entry,
+        action: &str,
+    ) -> Result<Result<(), types::Error>> {
+        self.bindings
+            .plugin_api()
+            .call_execute_action(&mut self.store, entry, action)
+            .await
+    }
+
+    /// Calls made to the host so far, in order.
+    pub fn calls(&self) -> &[HostCall] {
+        &self.store.data().michel.calls
+    }
+
+    /// Returns the calls made so far, then forgets them.
+    pub fn take_calls(&mut self) -> Vec<HostCall> {
+        std::mem::take(&mut self.store.data_mut().michel.calls)
+    }
+
+    /// Documents pushed to `index`, empty if it wasn't initialized.
+    pub fn documents(&self, index: &str) -> &[types::Document] {
+        self.store
+            .data()
+            .michel
+            .indexes
+            .get(index)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Value the plugin stored for `key`.
+    pub fn kv(&self, key: &str) -> Option<Vec<u8>> {
+        self.store.data().michel.kv.get(key)
+    }
+
+    /// Host path of a file of the virtual filesystem, to check what the plugin wrote.
+    pub fn path(&self, path: impl AsRef<Path>) -> PathBuf {
+        host_path(self.root.path(), path.as_ref())
+    }
+}
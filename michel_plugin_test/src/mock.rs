@@ -0,0 +1,334 @@
+use crate::{kv, michel_api, types};
+use anyhow::Result;
+use async_trait::async_trait;
+use michel_core::http::{HttpClient, HttpMethod, HttpRequest, HttpResponse};
+use michel_core::kv::{KvLimits, KvStore};
+use std::collections::{BTreeMap, HashMap};
+
+/// A call the plugin made to the `michel-api` of the host, with its arguments.
+#[derive(Debug, Clone)]
+pub enum HostCall {
+    NewDocumentForIndex {
+        index: String,
+        document: types::Document,
+    },
+    NewDocumentsForIndex {
+        index: String,
+        documents: Vec<types::Document>,
+    },
+    SearchInIndex {
+        index: String,
+        query: String,
+    },
+    SearchInIndexByDate {
+        index: String,
+        query: String,
+        filter: types::DateFilter,
+    },
+    HybridSearchInIndex {
+        index: String,
+        query: String,
+        embedding: Option<Vec<f32>>,
+        semantic_ratio: f32,
+    },
+    InitIndex {
+        index: String,
+    },
+    FlushIndex {
+        index: String,
+    },
+    ListIndexes,
+    IndexStats {
+        index: String,
+    },
+    HttpFetch {
+        request: types::HttpRequest,
+    },
+    KvGet {
+        key: String,
+    },
+    KvSet {
+        key: String,
+        value: Vec<u8>,
+    },
+    KvDelete {
+        key: String,
+    },
+    KvListPrefix {
+        prefix: String,
+    },
+}
+
+/**
+    Host the plugin is linked to : it records every call, keeps the documents pushed to the
+    indexes without making them searchable, and answers searches with scripted documents.
+    Requests are answered with scripted responses, or sent to the domains tests allowed, such
+    as the one of a local stand-in server. Keys and values are kept in memory, within the quotas
+    of the host.
+*/
+pub struct MockHost {
+    pub(crate) calls: Vec<HostCall>,
+    /// Documents pushed to each initialized index, in order.
+    pub(crate) indexes: BTreeMap<String, Vec<types::Document>>,
+    /// Documents answered to a search, by index and query.
+    pub(crate) searches: HashMap<(String, String), Vec<types::Document>>,
+    /// Responses to the requests of a URL.
+    pub(crate) responses: HashMap<String, types::HttpResponse>,
+    /// Domains the requests that weren't scripted are sent to.
+    pub(crate) domains: Vec<String>,
+    pub(crate) http: Option<HttpClient>,
+    pub(crate) kv: KvStore,
+}
+
+impl Default for MockHost {
+    fn default() -> Self {
+        MockHost {
+            calls: Vec::new(),
+            indexes: BTreeMap::new(),
+            searches: HashMap::new(),
+            responses: HashMap::new(),
+            domains: Vec::new(),
+            http: None,
+            kv: KvStore::in_memory(KvLimits::default()),
+        }
+    }
+}
+
+impl MockHost {
+    /// Scripted answer to a search, empty for the queries that weren't scripted.
+    fn search(&self, index: &str, query: &str) -> Vec<types::Document> {
+        self.searches
+            .get(&(String::from(index), String::from(query)))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn push_documents(
+        &mut self,
+        index: &str,
+        documents: Vec<types::Document>,
+    ) -> Result<(), types::Error> {
+        match self.indexes.get_mut(index) {
+            Some(pushed) => {
+                pushed.extend(documents);
+                Ok(())
+            }
+            None => Err(not_initialized(index)),
+        }
+    }
+}
+
+impl From<types::HttpRequest> for HttpRequest {
+    fn from(value: types::HttpRequest) -> Self {
+        HttpRequest {
+            method: match value.method {
+                types::HttpMethod::Get => HttpMethod::Get,
+                types::HttpMethod::Head => HttpMethod::Head,
+                types::HttpMethod::Post => HttpMethod::Post,
+                types::HttpMethod::Put => HttpMethod::Put,
+                types::HttpMethod::Patch => HttpMethod::Patch,
+                types::HttpMethod::Delete => HttpMethod::Delete,
+            },
+            url: value.url,
+            headers: value.headers,
+            body: value.body,
+        }
+    }
+}
+
+impl From<HttpResponse> for types::HttpResponse {
+    fn from(value: HttpResponse) -> Self {
+        types::HttpResponse {
+            status: value.status,
+            headers: value.headers,
+            body: value.body,
+        }
+    }
+}
+
+fn not_initialized(index: &str) -> types::Error {
+    types::Error::InvalidValue(format!("{} was not initialized", index))
+}
+
+impl types::Types for MockHost {}
+
+#[async_trait]
+impl michel_api::MichelApi for MockHost {
+    async fn new_document_for_index(
+        &mut self,
+        index: String,
+        document: types::Document,
+    ) -> Result<Result<(), types::Error>> {
+        self.calls.push(HostCall::NewDocumentForIndex {
+            index: index.clone(),
+            document: document.clone(),
+        });
+
+        Ok(self.push_documents(&index, vec![document]))
+    }
+
+    async fn new_documents_for_index(
+        &mut self,
+        index: String,
+        documents: Vec<types::Document>,
+    ) -> Result<Result<(), types::Error>> {
+        self.calls.push(HostCall::NewDocumentsForIndex {
+            index: index.clone(),
+            documents: documents.clone(),
+        });
+
+        Ok(self.push_documents(&index, documents))
+    }
+
+    async fn search_in_index(
+        &mut self,
+        index: String,
+        query: String,
+    ) -> Result<Vec<types::Document>> {
+        let documents = self.search(&index, &query);
+        self.calls.push(HostCall::SearchInIndex { index, query });
+
+        Ok(documents)
+    }
+
+    async fn search_in_index_by_date(
+        &mut self,
+        index: String,
+        query: String,
+        filter: types::DateFilter,
+    ) -> Result<Result<Vec<types::Document>, types::Error>> {
+        let documents = self.search(&index, &query);
+        self.calls.push(HostCall::SearchInIndexByDate {
+            index,
+            query,
+            filter,
+        });
+
+        Ok(Ok(documents))
+    }
+
+    async fn hybrid_search_in_index(
+        &mut self,
+        index: String,
+        query: String,
+        embedding: Option<Vec<f32>>,
+        semantic_ratio: f32,
+    ) -> Result<Vec<types::Document>> {
+        let documents = self.search(&index, &query);
+        self.calls.push(HostCall::HybridSearchInIndex {
+            index,
+            query,
+            embedding,
+            semantic_ratio,
+        });
+
+        Ok(documents)
+    }
+
+    async fn init_index(&mut self, index: String) -> Result<Result<(), types::Error>> {
+        self.indexes.entry(index.clone()).or_default();
+        self.calls.push(HostCall::InitIndex { index });
+
+        Ok(Ok(()))
+    }
+
+    async fn flush_index(&mut self, index: String) -> Result<Result<(), types::Error>> {
+        let flushed = match self.indexes.contains_key(&index) {
+            true => Ok(()),
+            false => Err(not_initialized(&index)),
+        };
+        self.calls.push(HostCall::FlushIndex { index });
+
+        Ok(flushed)
+    }
+
+    async fn list_indexes(&mut self) -> Result<Vec<String>> {
+        self.calls.push(HostCall::ListIndexes);
+
+        Ok(self.indexes.keys().cloned().collect())
+    }
+
+    async fn index_stats(
+        &mut self,
+        index: String,
+    ) -> Result<Result<types::IndexStats, types::Error>> {
+        let stats = match self.indexes.get(&index) {
+            Some(documents) => Ok(types::IndexStats {
+                name: index.clone(),
+                document_count: documents.len() as u64,
+                field_distribution: Vec::new(),
+                size_on_disk: 0,
+                last_update: None,
+            }),
+            None => Err(not_initialized(&index)),
+        };
+        self.calls.push(HostCall::IndexStats { index });
+
+        Ok(stats)
+    }
+
+    async fn http_fetch(
+        &mut self,
+        request: types::HttpRequest,
+    ) -> Result<Result<types::HttpResponse, types::Error>> {
+        self.calls.push(HostCall::HttpFetch {
+            request: request.clone(),
+        });
+
+        if let Some(response) = self.responses.get(&request.url) {
+            return Ok(Ok(response.clone()));
+        }
+
+        let http = match &self.http {
+            Some(http) => http,
+            None => {
+                return Ok(Err(types::Error::InvalidValue(format!(
+                    "no response scripted for {}",
+                    request.url
+                ))))
+            }
+        };
+
+        match http.fetch(request.into(), &self.domains).await {
+            Ok(response) => Ok(Ok(response.into())),
+            Err(error) => Ok(Err(types::Error::InvalidValue(error.to_string()))),
+        }
+    }
+}
+
+#[async_trait]
+impl kv::Kv for MockHost {
+    async fn get(&mut self, key: String) -> Result<Option<Vec<u8>>> {
+        let value = self.kv.get(&key);
+        self.calls.push(HostCall::KvGet { key });
+
+        Ok(value)
+    }
+
+    async fn set(&mut self, key: String, value: Vec<u8>) -> Result<Result<(), types::Error>> {
+        let set = self
+            .kv
+            .set(&key, value.clone())
+            .map_err(|error| types::Error::InvalidValue(error.to_string()));
+        self.calls.push(HostCall::KvSet { key, value });
+
+        Ok(set)
+    }
+
+    async fn delete(&mut self, key: String) -> Result<Result<(), types::Error>> {
+        let deleted = self
+            .kv
+            .delete(&key)
+            .map_err(|error| types::Error::InvalidValue(error.to_string()));
+        self.calls.push(HostCall::KvDelete { key });
+
+        Ok(deleted)
+    }
+
+    async fn list_prefix(&mut self, prefix: String) -> Result<Vec<String>> {
+        let keys = self.kv.list_prefix(&prefix);
+        self.calls.push(HostCall::KvListPrefix { prefix });
+
+        Ok(keys)
+    }
+}
@@ -0,0 +1,12 @@
+#!/bin/sh
+# Rebuilds plugin.wasm, the component the harness tests load, after a change of the crate or the SDK.
+# Takes the WASI adapter of preview2-prototyping the host uses, as the template does.
+set -e
+
+adapter=${1:?usage: build.sh <wasi_snapshot_preview1.wasm>}
+cd "$(dirname "$0")"
+
+cargo build --release --target wasm32-wasi
+wasm-tools component new target/wasm32-wasi/release/michel_plugin_bookmarks.wasm \
+    --adapt wasi_snapshot_preview1="$adapter" \
+    -o plugin.wasm
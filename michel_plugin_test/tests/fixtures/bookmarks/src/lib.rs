@@ -0,0 +1,103 @@
+use michel_plugin_sdk::{export_plugin, Document, Entry, Index, MichelPlugin, PluginInfo, Result};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+const INDEX: &str = "bookmarks";
+
+/// One bookmark per line, as `identifier<TAB>title<TAB>url`.
+const BOOKMARKS_PATH: &str = "/bookmarks/bookmarks.txt";
+
+/// URLs of the entries the `save` action was executed on, one per line.
+const SAVED_PATH: &str = "/bookmarks/saved.txt";
+
+struct Bookmark {
+    identifier: String,
+    title: String,
+    url: String,
+}
+
+/// Bookmarks of the file, none if it's missing.
+fn bookmarks() -> Vec<Bookmark> {
+    fs::read_to_string(BOOKMARKS_PATH)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split('\t');
+
+            Some(Bookmark {
+                identifier: columns.next()?.to_string(),
+                title: columns.next()?.to_string(),
+                url: columns.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Bookmarks;
+
+impl MichelPlugin for Bookmarks {
+    fn info(&self) -> PluginInfo {
+        PluginInfo::new("bookmarks", "Bookmarks", env!("CARGO_PKG_VERSION"))
+            .description("Searches the bookmarks of a file")
+    }
+
+    fn index(&self) -> Result<()> {
+        let index = Index::init(INDEX)?;
+
+        index.add_all(
+            bookmarks()
+                .into_iter()
+                .map(|bookmark| {
+                    Document::new(bookmark.identifier)
+                        .text("title", bookmark.title)
+                        .text("url", bookmark.url)
+                })
+                .collect(),
+        )
+    }
+
+    fn for_input(&self, input: &str) -> Vec<Entry> {
+        Index::open(INDEX)
+            .search(input)
+            .into_iter()
+            .map(|document| {
+                let text = |field| {
+                    document
+                        .get(field)
+                        .and_then(|value| value.as_text())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+
+                Entry::new(text("title"))
+                    .description(text("url"))
+                    .action("save", "Save the URL")
+            })
+            .collect()
+    }
+
+    fn autocomplete(&self, input: &str) -> Option<String> {
+        bookmarks()
+            .into_iter()
+            .map(|bookmark| bookmark.identifier)
+            .find(|identifier| !input.is_empty() && identifier.starts_with(input))
+    }
+
+    fn execute_action(&self, entry: Entry, action: &str) -> Result<()> {
+        match action {
+            "save" => {
+                let mut saved = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(SAVED_PATH)?;
+
+                writeln!(saved, "{}", entry.description)?;
+                Ok(())
+            }
+            _ => Err(format!("unknown action {}", action).into()),
+        }
+    }
+}
+
+export_plugin!(Bookmarks);
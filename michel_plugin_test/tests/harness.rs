@@ -0,0 +1,142 @@
+use michel_plugin_test::{text_document, HostCall, TestHost, TestPlugin};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BOOKMARKS: &str = "rust\tThe Rust Programming Language\thttps://doc.rust-lang.org/book/\n\
+                         wasm\tWebAssembly\thttps://webassembly.org/\n";
+
+/// The template plugin, reading its bookmarks from the virtual filesystem, built by its `build.sh`.
+fn fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bookmarks")
+}
+
+async fn load(host: TestHost) -> TestPlugin {
+    host.file("/bookmarks/bookmarks.txt", BOOKMARKS)
+        .load(fixture())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn template_plugins_are_loaded() {
+    let mut plugin = load(TestHost::new()).await;
+
+    let info = plugin.info().await.unwrap();
+    assert_eq!(info.identifier, "bookmarks");
+    assert_eq!(info.version, "0.1.0");
+    assert!(plugin.calls().is_empty());
+}
+
+#[tokio::test]
+async fn indexing_reads_the_virtual_filesystem() {
+    let mut plugin = load(TestHost::new()).await;
+
+    plugin.index().await.unwrap().unwrap();
+
+    let identifiers: Vec<&str> = plugin
+        .documents("bookmarks")
+        .iter()
+        .map(|document| document.identifier.as_str())
+        .collect();
+    assert_eq!(identifiers, vec!["rust", "wasm"]);
+
+    match plugin.take_calls().as_slice() {
+        [HostCall::InitIndex { index }, HostCall::NewDocumentsForIndex {
+            index: pushed,
+            documents,
+        }] => {
+            assert_eq!(index, "bookmarks");
+            assert_eq!(pushed, "bookmarks");
+            assert_eq!(documents.len(), 2);
+        }
+        calls => panic!("unexpected calls {:?}", calls),
+    }
+    assert!(plugin.calls().is_empty());
+}
+
+#[tokio::test]
+async fn inputs_are_answered_with_scripted_searches() {
+    let bookmark = text_document(
+        "rust",
+        &[("title", "Rust"), ("url", "https://www.rust-lang.org")],
+    );
+    let mut plugin =
+        load(TestHost::new().search_response("bookmarks", "rust", vec![bookmark])).await;
+
+    let entries = plugin.for_input("rust").await.unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].title, "Rust");
+    assert_eq!(entries[0].description, "https://www.rust-lang.org");
+    assert!(matches!(
+        plugin.calls().last(),
+        Some(HostCall::SearchInIndex { index, query }) if index == "bookmarks" && query == "rust"
+    ));
+
+    // Searches that weren't scripted find nothing
+    assert!(plugin.for_input("go").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn inputs_are_autocompleted() {
+    let mut plugin = load(TestHost::new()).await;
+
+    assert_eq!(
+        plugin.autocomplete("wa").await.unwrap().as_deref(),
+        Some("wasm")
+    );
+    assert_eq!(plugin.autocomplete("go").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn actions_write_to_the_virtual_filesystem() {
+    let bookmark = text_document(
+        "wasm",
+        &[
+            ("title", "WebAssembly"),
+            ("url", "https://webassembly.org/"),
+        ],
+    );
+    let mut plugin =
+        load(TestHost::new().search_response("bookmarks", "wasm", vec![bookmark])).await;
+
+    let entries = plugin.for_input("wasm").await.unwrap();
+    plugin
+        .execute_action(&entries[0], "save")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(plugin
+        .execute_action(&entries[0], "share")
+        .await
+        .unwrap()
+        .is_err());
+
+    assert_eq!(
+        fs::read_to_string(plugin.path("/bookmarks/saved.txt")).unwrap(),
+        "https://webassembly.org/\n"
+    );
+}
+
+#[tokio::test]
+async fn plugins_targeting_an_older_world_are_refused() {
+    // Declares no version, so targeting michel:plugin@0.1.0, which the host adapts but tests don't
+    let wasm = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../michel_core/tests/fixtures/baseline-plugin.wat");
+
+    let error = match TestHost::new().load(wasm).await {
+        Ok(_) => panic!("a plugin targeting michel:plugin@0.1.0 was loaded"),
+        Err(error) => error.to_string(),
+    };
+
+    assert!(error.contains("michel:plugin@0.1.0"), "{}", error);
+}
+
+#[tokio::test]
+async fn invalid_components_are_refused() {
+    let loaded = TestHost::new()
+        .file("/home/me/notes.txt", "nothing")
+        .load_wasm(b"\0asm")
+        .await;
+
+    assert!(loaded.is_err());
+}
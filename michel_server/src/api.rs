@@ -0,0 +1,82 @@
+/*!
+    Bodies of the requests and responses of the API, shared with its clients.
+*/
+
+use michel_core::persistence::MichelPersistence;
+use michel_core::{Entry, Plugin};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InputQuery {
+    pub input: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PluginEntries {
+    pub plugin: String,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PluginCompletion {
+    pub plugin: String,
+    pub completion: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DisplayedPlugin {
+    pub identifier: String,
+    pub name: String,
+    pub description: String,
+    pub version: String,
+    pub can_index: bool,
+    pub enabled: bool,
+}
+
+impl<P: MichelPersistence> From<&Plugin<P>> for DisplayedPlugin {
+    fn from(plugin: &Plugin<P>) -> Self {
+        DisplayedPlugin {
+            identifier: plugin.identifier(),
+            name: plugin.name(),
+            description: plugin.description(),
+            version: plugin.version(),
+            can_index: plugin.can_index(),
+            enabled: plugin.enabled(),
+        }
+    }
+}
+
+/// Runs one of the actions of an entry, as returned by the plugin.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ActionRequest {
+    pub entry: Entry,
+    pub action: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+/// Sent by the client over `/stream` each time the input changes, `sequence` growing each time.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamRequest {
+    Query { sequence: u64, input: String },
+}
+
+/// Responses to a query are tagged with its sequence, and stop once a newer query is sent.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamResponse {
+    /// Entries of a single plugin, sent as soon as it answers.
+    Entries {
+        sequence: u64,
+        plugin: String,
+        entries: Vec<Entry>,
+    },
+    /// Every plugin answered the query.
+    Done { sequence: u64 },
+    /// The request couldn't be read, or a plugin failed to answer it.
+    Error { error: String },
+}
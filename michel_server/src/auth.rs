@@ -0,0 +1,161 @@
+/*!
+    Token based access to the API. Tokens live in `tokens.toml` in the config folder, generated
+    on the first run, each with a scope : `query` only lets searching, `admin` lets everything.
+*/
+
+use crate::routes::ApiError;
+use anyhow::Result;
+use axum::extract::State;
+use axum::http::header::AUTHORIZATION;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+const TOKEN_BYTES: usize = 32;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Query,
+    Admin,
+}
+
+impl Scope {
+    fn allows(self, required: Scope) -> bool {
+        self == Scope::Admin || self == required
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Token {
+    pub name: String,
+    pub token: String,
+    pub scope: Scope,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Tokens {
+    #[serde(default)]
+    tokens: Vec<Token>,
+}
+
+impl Tokens {
+    /// Reads the tokens at `path`, first writing an admin and a query token there if missing.
+    pub fn load_or_generate<T: AsRef<Path>>(path: T) -> Result<Tokens> {
+        let path = path.as_ref();
+
+        if path.exists() {
+            return Ok(toml::from_str(&fs::read_to_string(path)?)?);
+        }
+
+        let tokens = Tokens {
+            tokens: vec![
+                Token::generate("admin", Scope::Admin),
+                Token::generate("query", Scope::Query),
+            ],
+        };
+        tokens.save(path)?;
+
+        Ok(tokens)
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        write_private_file(path, toml::to_string(self)?.as_bytes())
+    }
+
+    fn scope_of(&self, token: &str) -> Option<Scope> {
+        self.tokens
+            .iter()
+            .find(|known| constant_time_eq(known.token.as_bytes(), token.as_bytes()))
+            .map(|known| known.scope)
+    }
+}
+
+impl Token {
+    fn generate(name: &str, scope: Scope) -> Token {
+        let mut bytes = [0u8; TOKEN_BYTES];
+        rand::thread_rng().fill_bytes(&mut bytes);
+
+        Token {
+            name: String::from(name),
+            token: bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+            scope,
+        }
+    }
+}
+
+/// State of the middleware guarding the routes needing `scope`.
+#[derive(Clone)]
+pub(crate) struct Access {
+    pub(crate) tokens: Arc<Tokens>,
+    pub(crate) scope: Scope,
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/**
+    Lets the request through if it holds a token with the scope of the route, either as a bearer
+    token or as a `token` query parameter, browsers not setting headers on WebSockets.
+*/
+pub(crate) async fn authorize<B>(
+    State(access): State<Access>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let bearer = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(String::from);
+    let token = bearer.or_else(|| {
+        request
+            .uri()
+            .query()
+            .and_then(|query| serde_urlencoded::from_str::<TokenQuery>(query).ok())
+            .and_then(|query| query.token)
+    });
+
+    match token.and_then(|token| access.tokens.scope_of(&token)) {
+        Some(scope) if scope.allows(access.scope) => next.run(request).await,
+        Some(_) => ApiError::forbidden(String::from("token not allowed here")).into_response(),
+        None => ApiError::unauthorized(String::from("missing or unknown token")).into_response(),
+    }
+}
+
+/// Only readable by the user, as anyone reading tokens or keys gets access to the indexes.
+pub(crate) fn write_private_file(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(path)?.write_all(contents)?;
+
+    Ok(())
+}
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0, |difference, (left, right)| difference | (left ^ right))
+            == 0
+}
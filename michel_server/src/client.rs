@@ -0,0 +1,203 @@
+/*!
+    Client of the API served over a Unix socket, for frontends running next to the daemon.
+*/
+
+use crate::api::{
+    ActionRequest, DisplayedPlugin, ErrorResponse, InputQuery, PluginCompletion, PluginEntries,
+};
+use anyhow::{anyhow, Result};
+use hyper::body::Bytes;
+use hyper::header::{CONTENT_TYPE, HOST};
+use hyper::{Body, Method, Request};
+use michel_core::grants::{Capability, CapabilityRequest};
+use michel_core::persistence::IndexStats;
+use michel_core::registry::AvailablePlugin;
+use michel_core::Entry;
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+use tokio::net::UnixStream;
+
+pub struct Client {
+    socket: PathBuf,
+}
+
+impl Client {
+    /// Fails if no daemon answers on `socket`.
+    pub async fn connect<T: AsRef<Path>>(socket: T) -> Result<Client> {
+        let client = Client {
+            socket: socket.as_ref().to_path_buf(),
+        };
+        client.plugins().await?;
+
+        Ok(client)
+    }
+
+    pub async fn query(&self, input: &str) -> Result<Vec<PluginEntries>> {
+        self.get(&format!("/query?{}", input_query(input)?)).await
+    }
+
+    pub async fn autocomplete(&self, input: &str) -> Result<Vec<PluginCompletion>> {
+        self.get(&format!("/autocomplete?{}", input_query(input)?))
+            .await
+    }
+
+    pub async fn plugins(&self) -> Result<Vec<DisplayedPlugin>> {
+        self.get("/plugins").await
+    }
+
+    /// Waits for the indexing to end, then returns the stats of the indexes of the plugin.
+    pub async fn index_plugin(&self, identifier: &str) -> Result<Vec<IndexStats>> {
+        let body = self
+            .send(
+                Method::POST,
+                &format!("/plugins/{}/index", identifier),
+                Body::empty(),
+            )
+            .await?;
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    pub async fn plugin_indexes(&self, identifier: &str) -> Result<Vec<IndexStats>> {
+        self.get(&format!("/plugins/{}/indexes", identifier)).await
+    }
+
+    pub async fn execute_action(
+        &self,
+        identifier: &str,
+        entry: Entry,
+        action: String,
+    ) -> Result<()> {
+        let body = serde_json::to_vec(&ActionRequest { entry, action })?;
+        self.send(
+            Method::POST,
+            &format!("/plugins/{}/actions", identifier),
+            Body::from(body),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn pending_capabilities(&self) -> Result<Vec<CapabilityRequest>> {
+        self.get("/capabilities/pending").await
+    }
+
+    pub async fn granted_capabilities(&self, identifier: &str) -> Result<Vec<Capability>> {
+        self.get(&format!("/plugins/{}/capabilities", identifier))
+            .await
+    }
+
+    pub async fn grant_capability(&self, identifier: &str, capability: Capability) -> Result<()> {
+        self.send(
+            Method::POST,
+            &format!("/plugins/{}/capabilities/grant", identifier),
+            Body::from(serde_json::to_vec(&capability)?),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn deny_capability(&self, identifier: &str, capability: Capability) -> Result<()> {
+        self.send(
+            Method::POST,
+            &format!("/plugins/{}/capabilities/deny", identifier),
+            Body::from(serde_json::to_vec(&capability)?),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn available_plugins(&self) -> Result<Vec<AvailablePlugin>> {
+        self.get("/registry").await
+    }
+
+    pub async fn install_plugin(&self, identifier: &str) -> Result<()> {
+        self.send(
+            Method::POST,
+            &format!("/plugins/{}/install", identifier),
+            Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns whether a newer version was installed.
+    pub async fn update_plugin(&self, identifier: &str) -> Result<bool> {
+        let body = self
+            .send(
+                Method::POST,
+                &format!("/plugins/{}/update", identifier),
+                Body::empty(),
+            )
+            .await?;
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    pub async fn uninstall_plugin(&self, identifier: &str) -> Result<()> {
+        self.send(
+            Method::DELETE,
+            &format!("/plugins/{}", identifier),
+            Body::empty(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn indexes(&self) -> Result<Vec<String>> {
+        self.get("/indexes").await
+    }
+
+    pub async fn index_stats(&self, name: &str) -> Result<IndexStats> {
+        self.get(&format!("/indexes/{}", name)).await
+    }
+
+    /// Stats of every index.
+    pub async fn all_index_stats(&self) -> Result<Vec<IndexStats>> {
+        self.get("/index-stats").await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let body = self.send(Method::GET, path, Body::empty()).await?;
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Opens a connection per request, the daemon possibly restarting between two of them.
+    async fn send(&self, method: Method, path: &str, body: Body) -> Result<Bytes> {
+        let stream = UnixStream::connect(&self.socket).await?;
+        let (mut sender, connection) = hyper::client::conn::handshake(stream).await?;
+        tokio::spawn(connection);
+
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(HOST, "localhost")
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)?;
+
+        let response = sender.send_request(request).await?;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+
+        if !status.is_success() {
+            return Err(match serde_json::from_slice::<ErrorResponse>(&body) {
+                Ok(response) => anyhow!(response.error),
+                Err(_) => anyhow!("daemon answered {}", status),
+            });
+        }
+
+        Ok(body)
+    }
+}
+
+fn input_query(input: &str) -> Result<String> {
+    Ok(serde_urlencoded::to_string(InputQuery {
+        input: String::from(input),
+    })?)
+}
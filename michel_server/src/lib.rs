@@ -0,0 +1,17 @@
+/*!
+    HTTP/JSON API over a `MichelInstance`, so that several frontends share the same plugins
+    and indexes instead of each running its own.
+*/
+
+#[macro_use]
+extern crate log;
+
+pub mod api;
+pub mod auth;
+#[cfg(unix)]
+pub mod client;
+mod routes;
+mod stream;
+pub mod tls;
+
+pub use routes::router;
@@ -1,12 +1,222 @@
-use yew::prelude::*;
+#[macro_use]
+extern crate log;
 
-#[function_component(App)]
-fn app() -> Html {
-    html! {
-        <h1>{ "Hello World" }</h1>
+use anyhow::{anyhow, Result};
+use axum_server::tls_rustls::RustlsConfig;
+use clap::Parser;
+use michel_core::backend::{Backends, PersistenceConfig};
+use michel_core::signature::SignatureConfig;
+use michel_core::{MichelConfig, MichelInstance};
+use michel_index::MilliPersistence;
+use michel_index_sqlite::SqlitePersistence;
+use michel_server::auth::Tokens;
+use michel_server::tls;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+#[cfg(unix)]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+#[cfg(unix)]
+use tokio_stream::wrappers::UnixListenerStream;
+
+const DEFAULT_PORT: u16 = 7890;
+const TOKENS_FILE: &str = "tokens.toml";
+const TLS_FOLDER: &str = "tls";
+const CERTIFICATE_FILE: &str = "cert.pem";
+const KEY_FILE: &str = "key.pem";
+
+/// Runs Michel in the background, serving its API on a local port or a Unix socket.
+#[derive(Parser)]
+#[command(name = "michel_server")]
+struct Args {
+    /// Port to listen to
+    #[arg(long, default_value_t = DEFAULT_PORT)]
+    port: u16,
+
+    /// Address to listen to, only reachable from this machine by default
+    #[arg(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    host: IpAddr,
+
+    /// Tokens allowed to use the API, generated in the config folder if missing
+    #[arg(long)]
+    tokens: Option<PathBuf>,
+
+    /// Serve over HTTPS, with a certificate generated in the config folder unless given
+    #[arg(long)]
+    tls: bool,
+
+    /// PEM certificate to serve over HTTPS
+    #[arg(long, requires_all = ["tls", "key"])]
+    certificate: Option<PathBuf>,
+
+    /// PEM private key of the certificate
+    #[arg(long, requires_all = ["tls", "certificate"])]
+    key: Option<PathBuf>,
+
+    /// Unix socket to listen to instead of a port. Only the user can connect, without token
+    #[cfg(unix)]
+    #[arg(long, conflicts_with_all = ["port", "host", "tls"])]
+    socket: Option<PathBuf>,
+
+    /// Listen to the socket the desktop app connects to
+    #[cfg(unix)]
+    #[arg(long, conflicts_with_all = ["port", "host", "tls", "socket"])]
+    daemon: bool,
+
+    /// Folder holding the plugins, the one of the desktop app by default
+    #[arg(long)]
+    plugins: Option<PathBuf>,
+
+    /// URL, or local folder, of the registry index plugins are installed from
+    #[arg(long)]
+    registry: Option<String>,
+
+    /// Give hybrid searches trigram vectors for the documents pushed without one, which only
+    /// catch typos and word variations. Also enabled by the config file
+    #[arg(long)]
+    trigram_embedder: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let plugins_path = match args.plugins {
+        Some(path) => path,
+        None => MichelConfig::default_plugins_path()?,
+    };
+
+    if !plugins_path.is_dir() {
+        return Err(anyhow!(
+            "no plugins folder at {}",
+            plugins_path.to_string_lossy()
+        ));
+    }
+
+    // The backend of the config file, so that the daemon serves the indexes of the desktop app
+    let mut persistence = PersistenceConfig::load()?;
+    persistence.trigram_embedder |= args.trigram_embedder;
+    let persistence = persistence.open(&Backends {
+        milli: MilliPersistence::open_backend,
+        sqlite: SqlitePersistence::open_backend,
+    })?;
+
+    let instance = Arc::new(
+        MichelInstance::new(
+            persistence,
+            MichelConfig {
+                name: "Michel".to_string(),
+                plugins_path,
+                grants_path: Some(MichelConfig::default_grants_path()?),
+                kv_path: Some(MichelConfig::default_kv_path()?),
+                signatures: SignatureConfig::load(MichelConfig::default_signatures_path()?)?,
+                registry: args.registry,
+            },
+        )
+        .await?,
+    );
+
+    #[cfg(unix)]
+    let socket = match args.socket {
+        Some(socket) => Some(socket),
+        None if args.daemon => Some(MichelConfig::default_socket_path()?),
+        None => None,
+    };
+
+    #[cfg(unix)]
+    if let Some(socket) = socket {
+        // A socket left by a previous run would prevent binding
+        if socket.exists() {
+            tokio::fs::remove_file(&socket).await?;
+        }
+
+        let listener = bind_private(&socket)?;
+        info!("listening on {}", socket.to_string_lossy());
+
+        axum::Server::builder(hyper::server::accept::from_stream(UnixListenerStream::new(
+            listener,
+        )))
+        .serve(michel_server::router(instance, None).into_make_service())
+        .await?;
+
+        return Ok(());
+    }
+
+    let tokens_path = match args.tokens {
+        Some(path) => path,
+        None => MichelConfig::default_folder()?.join(TOKENS_FILE),
+    };
+    let tokens = Tokens::load_or_generate(&tokens_path)?;
+    info!("tokens read from {}", tokens_path.to_string_lossy());
+
+    let app = michel_server::router(instance, Some(Arc::new(tokens)));
+    let address = SocketAddr::new(args.host, args.port);
+
+    if args.tls {
+        let (certificate, key) = match (args.certificate, args.key) {
+            (Some(certificate), Some(key)) => (certificate, key),
+            _ => {
+                let folder = MichelConfig::default_folder()?.join(TLS_FOLDER);
+                let (certificate, key) = (folder.join(CERTIFICATE_FILE), folder.join(KEY_FILE));
+                tls::ensure_local_certificate(&certificate, &key)?;
+
+                (certificate, key)
+            }
+        };
+
+        let config = RustlsConfig::from_pem_file(certificate, key).await?;
+        info!("listening on https://{}", address);
+
+        axum_server::bind_rustls(address, config)
+            .serve(app.into_make_service())
+            .await?;
+
+        return Ok(());
     }
+
+    if !address.ip().is_loopback() {
+        warn!("tokens will go unencrypted over the network, consider --tls");
+    }
+
+    info!("listening on http://{}", address);
+
+    axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
 }
 
-fn main() {
-    yew::Renderer::<App>::new().render();
+/**
+    Binds a socket only the user can connect to, without a moment where it is open to others :
+    it is bound in a folder only the user can enter, restricted, then moved to `socket`.
+*/
+#[cfg(unix)]
+fn bind_private(socket: &Path) -> Result<UnixListener> {
+    use std::fs::{self, DirBuilder, Permissions};
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let parent = match socket.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+
+    // Next to the socket, as it can only be moved within a filesystem
+    let private = parent.join(format!(".michel-socket-{}", std::process::id()));
+    DirBuilder::new().mode(0o700).create(&private)?;
+    let bound = private.join("socket");
+
+    let listener = UnixListener::bind(&bound).and_then(|listener| {
+        fs::set_permissions(&bound, Permissions::from_mode(0o600))?;
+        fs::rename(&bound, socket)?;
+
+        Ok(listener)
+    });
+    let _ = fs::remove_dir_all(&private);
+
+    Ok(listener?)
 }
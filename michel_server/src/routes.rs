@@ -0,0 +1,330 @@
+use crate::api::{
+    ActionRequest, DisplayedPlugin, ErrorResponse, InputQuery, PluginCompletion, PluginEntries,
+};
+use crate::auth::{authorize, Access, Scope, Tokens};
+use crate::stream;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{middleware, Json, Router};
+use michel_core::grants::{Capability, CapabilityRequest};
+use michel_core::persistence::{Index, IndexStats, MichelPersistence};
+use michel_core::registry::AvailablePlugin;
+use michel_core::{MichelInstance, Plugin};
+use std::sync::Arc;
+
+pub(crate) type Michel<P> = State<Arc<MichelInstance<P>>>;
+
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn not_found(message: String) -> ApiError {
+        ApiError {
+            status: StatusCode::NOT_FOUND,
+            message,
+        }
+    }
+
+    pub(crate) fn unauthorized(message: String) -> ApiError {
+        ApiError {
+            status: StatusCode::UNAUTHORIZED,
+            message,
+        }
+    }
+
+    pub(crate) fn forbidden(message: String) -> ApiError {
+        ApiError {
+            status: StatusCode::FORBIDDEN,
+            message,
+        }
+    }
+}
+
+impl<E: Into<anyhow::Error>> From<E> for ApiError {
+    fn from(error: E) -> Self {
+        ApiError {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: error.into().to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(ErrorResponse {
+                error: self.message,
+            }),
+        )
+            .into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/**
+    Routes of the API, each one needing a token of its scope when `tokens` are given. Searching
+    and running actions only need a query token, indexing, reading the indexes and installing
+    plugins an admin one.
+*/
+pub fn router<P: MichelPersistence + 'static>(
+    michel: Arc<MichelInstance<P>>,
+    tokens: Option<Arc<Tokens>>,
+) -> Router {
+    let query = Router::new()
+        .route("/query", get(query::<P>))
+        .route("/autocomplete", get(autocomplete::<P>))
+        .route("/plugins", get(plugins::<P>))
+        .route("/plugins/:identifier/actions", post(execute_action::<P>))
+        .route("/stream", get(stream::stream::<P>));
+    let admin = Router::new()
+        .route("/plugins/:identifier/index", post(index_plugin::<P>))
+        .route("/plugins/:identifier/indexes", get(plugin_indexes::<P>))
+        .route("/capabilities/pending", get(pending_capabilities::<P>))
+        .route(
+            "/plugins/:identifier/capabilities",
+            get(granted_capabilities::<P>),
+        )
+        .route(
+            "/plugins/:identifier/capabilities/grant",
+            post(grant_capability::<P>),
+        )
+        .route(
+            "/plugins/:identifier/capabilities/deny",
+            post(deny_capability::<P>),
+        )
+        .route("/registry", get(available_plugins::<P>))
+        .route("/plugins/:identifier/install", post(install_plugin::<P>))
+        .route("/plugins/:identifier/update", post(update_plugin::<P>))
+        .route("/plugins/:identifier", delete(uninstall_plugin::<P>))
+        .route("/indexes", get(indexes::<P>))
+        .route("/indexes/:name", get(index_stats::<P>))
+        .route("/index-stats", get(all_index_stats::<P>));
+
+    let (query, admin) = match tokens {
+        Some(tokens) => (
+            query.route_layer(middleware::from_fn_with_state(
+                Access {
+                    tokens: tokens.clone(),
+                    scope: Scope::Query,
+                },
+                authorize,
+            )),
+            admin.route_layer(middleware::from_fn_with_state(
+                Access {
+                    tokens,
+                    scope: Scope::Admin,
+                },
+                authorize,
+            )),
+        ),
+        None => (query, admin),
+    };
+
+    query.merge(admin).with_state(michel)
+}
+
+fn plugin<P: MichelPersistence>(
+    michel: &MichelInstance<P>,
+    identifier: String,
+) -> Result<Arc<Plugin<P>>, ApiError> {
+    michel
+        .plugin(identifier.clone())
+        .ok_or_else(|| ApiError::not_found(format!("no plugin {}", identifier)))
+}
+
+async fn query<P: MichelPersistence>(
+    State(michel): Michel<P>,
+    Query(query): Query<InputQuery>,
+) -> ApiResult<Vec<PluginEntries>> {
+    let mut results = Vec::new();
+
+    // A plugin failing to answer is left out rather than failing the whole query
+    for plugin in michel.plugins().iter().filter(|plugin| plugin.enabled()) {
+        match plugin.entries_for_input(&query.input).await {
+            Ok(entries) => results.push(PluginEntries {
+                plugin: plugin.identifier(),
+                entries,
+            }),
+            Err(error) => warn!("{:#}", error),
+        }
+    }
+
+    Ok(Json(results))
+}
+
+async fn autocomplete<P: MichelPersistence>(
+    State(michel): Michel<P>,
+    Query(query): Query<InputQuery>,
+) -> ApiResult<Vec<PluginCompletion>> {
+    let mut completions = Vec::new();
+
+    for plugin in michel.plugins().iter().filter(|plugin| plugin.enabled()) {
+        match plugin.autocomplete(&query.input).await {
+            Ok(Some(completion)) => completions.push(PluginCompletion {
+                plugin: plugin.identifier(),
+                completion,
+            }),
+            Ok(None) => {}
+            Err(error) => warn!("{} failed to complete : {:#}", plugin.identifier(), error),
+        }
+    }
+
+    Ok(Json(completions))
+}
+
+async fn plugins<P: MichelPersistence>(State(michel): Michel<P>) -> Json<Vec<DisplayedPlugin>> {
+    Json(
+        michel
+            .plugins()
+            .iter()
+            .map(|plugin| DisplayedPlugin::from(plugin.as_ref()))
+            .collect(),
+    )
+}
+
+/// Waits for the indexing to end, then returns the stats of the indexes of the plugin.
+async fn index_plugin<P: MichelPersistence>(
+    State(michel): Michel<P>,
+    Path(identifier): Path<String>,
+) -> ApiResult<Vec<IndexStats>> {
+    let plugin = plugin(&michel, identifier)?;
+    plugin.index().await?;
+
+    Ok(Json(plugin.index_stats().await?))
+}
+
+async fn plugin_indexes<P: MichelPersistence>(
+    State(michel): Michel<P>,
+    Path(identifier): Path<String>,
+) -> ApiResult<Vec<IndexStats>> {
+    Ok(Json(plugin(&michel, identifier)?.index_stats().await?))
+}
+
+async fn execute_action<P: MichelPersistence>(
+    State(michel): Michel<P>,
+    Path(identifier): Path<String>,
+    Json(request): Json<ActionRequest>,
+) -> Result<StatusCode, ApiError> {
+    plugin(&michel, identifier)?
+        .execute_action(request.entry, &request.action)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn pending_capabilities<P: MichelPersistence>(
+    State(michel): Michel<P>,
+) -> Json<Vec<CapabilityRequest>> {
+    Json(michel.grants().pending())
+}
+
+async fn granted_capabilities<P: MichelPersistence>(
+    State(michel): Michel<P>,
+    Path(identifier): Path<String>,
+) -> ApiResult<Vec<Capability>> {
+    let plugin = plugin(&michel, identifier)?;
+
+    Ok(Json(michel.grants().granted(&plugin.identifier())))
+}
+
+/// Taken into account on the next call to the plugin, which must have asked for the capability.
+async fn grant_capability<P: MichelPersistence>(
+    State(michel): Michel<P>,
+    Path(identifier): Path<String>,
+    Json(capability): Json<Capability>,
+) -> Result<StatusCode, ApiError> {
+    let plugin = plugin(&michel, identifier)?;
+    if !michel.asked_for(&plugin.identifier(), &capability) {
+        return Err(ApiError::forbidden(format!(
+            "{} didn't ask for this capability",
+            plugin.identifier()
+        )));
+    }
+    michel.grants().grant(&plugin.identifier(), capability)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Refuses a pending capability, or revokes a granted one.
+async fn deny_capability<P: MichelPersistence>(
+    State(michel): Michel<P>,
+    Path(identifier): Path<String>,
+    Json(capability): Json<Capability>,
+) -> Result<StatusCode, ApiError> {
+    let plugin = plugin(&michel, identifier)?;
+    michel.grants().deny(&plugin.identifier(), capability)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn available_plugins<P: MichelPersistence>(
+    State(michel): Michel<P>,
+) -> ApiResult<Vec<AvailablePlugin>> {
+    Ok(Json(michel.available_plugins().await?))
+}
+
+async fn install_plugin<P: MichelPersistence>(
+    State(michel): Michel<P>,
+    Path(identifier): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    michel.install(&identifier).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Returns whether a newer version was installed.
+async fn update_plugin<P: MichelPersistence>(
+    State(michel): Michel<P>,
+    Path(identifier): Path<String>,
+) -> ApiResult<bool> {
+    plugin(&michel, identifier.clone())?;
+
+    Ok(Json(michel.update(&identifier).await?))
+}
+
+async fn uninstall_plugin<P: MichelPersistence>(
+    State(michel): Michel<P>,
+    Path(identifier): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    plugin(&michel, identifier.clone())?;
+    michel.uninstall(&identifier).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn indexes<P: MichelPersistence>(State(michel): Michel<P>) -> ApiResult<Vec<String>> {
+    Ok(Json(michel.persistence().list_indexes().await?))
+}
+
+/// Stats of every index in a single request, for the pages listing them.
+async fn all_index_stats<P: MichelPersistence>(
+    State(michel): Michel<P>,
+) -> ApiResult<Vec<IndexStats>> {
+    let persistence = michel.persistence();
+
+    let mut stats = Vec::new();
+    for name in persistence.list_indexes().await? {
+        stats.push(persistence.stats(Index { name }).await?);
+    }
+
+    Ok(Json(stats))
+}
+
+async fn index_stats<P: MichelPersistence>(
+    State(michel): Michel<P>,
+    Path(name): Path<String>,
+) -> ApiResult<IndexStats> {
+    let persistence = michel.persistence();
+
+    if !persistence.list_indexes().await?.contains(&name) {
+        return Err(ApiError::not_found(format!("no index {}", name)));
+    }
+
+    Ok(Json(persistence.stats(Index { name }).await?))
+}
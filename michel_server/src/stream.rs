@@ -0,0 +1,127 @@
+/*!
+    Query results streamed over a WebSocket : each plugin's entries are sent as soon as it
+    answers, instead of waiting for the slowest one.
+
+    Sending a new query supersedes the previous one. Plugins not yet called for it are skipped
+    and the entries of the running ones are dropped, but a running plugin call isn't
+    interrupted, as dropping a wasm call midway could leave the plugin instance inconsistent.
+*/
+
+use crate::api::{StreamRequest, StreamResponse};
+use crate::routes::Michel;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use futures::stream::FuturesUnordered;
+use futures::{SinkExt, StreamExt};
+use michel_core::persistence::MichelPersistence;
+use michel_core::MichelInstance;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Sequence of the current query once the client is gone, so that every query is superseded.
+const DISCONNECTED: u64 = u64::MAX;
+
+pub(crate) async fn stream<P: MichelPersistence + 'static>(
+    State(michel): Michel<P>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade.on_upgrade(move |socket| serve(socket, michel))
+}
+
+async fn serve<P: MichelPersistence + 'static>(socket: WebSocket, michel: Arc<MichelInstance<P>>) {
+    let (mut sink, mut requests) = socket.split();
+    let (sender, mut responses) = mpsc::unbounded_channel::<StreamResponse>();
+    let current = Arc::new(AtomicU64::new(0));
+
+    let writer = tokio::spawn(async move {
+        while let Some(response) = responses.recv().await {
+            let message = match serde_json::to_string(&response) {
+                Ok(message) => message,
+                Err(_) => continue,
+            };
+
+            if sink.send(Message::Text(message)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    while let Some(Ok(message)) = requests.next().await {
+        let request = match message {
+            Message::Text(text) => serde_json::from_str::<StreamRequest>(&text),
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        match request {
+            Ok(StreamRequest::Query { sequence, input }) => {
+                current.store(sequence, Ordering::SeqCst);
+                tokio::spawn(run_query(
+                    michel.clone(),
+                    current.clone(),
+                    sequence,
+                    input,
+                    sender.clone(),
+                ));
+            }
+            Err(error) => {
+                let _ = sender.send(StreamResponse::Error {
+                    error: error.to_string(),
+                });
+            }
+        }
+    }
+
+    current.store(DISCONNECTED, Ordering::SeqCst);
+    drop(sender);
+    let _ = writer.await;
+}
+
+async fn run_query<P: MichelPersistence>(
+    michel: Arc<MichelInstance<P>>,
+    current: Arc<AtomicU64>,
+    sequence: u64,
+    input: String,
+    sender: mpsc::UnboundedSender<StreamResponse>,
+) {
+    let is_current = || current.load(Ordering::SeqCst) == sequence;
+
+    let plugins = michel.plugins();
+    let mut batches: FuturesUnordered<_> = plugins
+        .iter()
+        .filter(|plugin| plugin.enabled())
+        .map(|plugin| async {
+            // Checked once the plugin is free, as it may be busy with a previous query
+            let entries = plugin.entries_for_input_if(&input, is_current).await;
+
+            (plugin.identifier(), entries)
+        })
+        .collect();
+
+    // Every call is awaited even once superseded, so none is dropped midway
+    while let Some((plugin, entries)) = batches.next().await {
+        if !is_current() {
+            continue;
+        }
+
+        let response = match entries {
+            Ok(Some(entries)) => StreamResponse::Entries {
+                sequence,
+                plugin,
+                entries,
+            },
+            Ok(None) => continue,
+            Err(error) => StreamResponse::Error {
+                error: error.to_string(),
+            },
+        };
+
+        let _ = sender.send(response);
+    }
+
+    if is_current() {
+        let _ = sender.send(StreamResponse::Done { sequence });
+    }
+}
@@ -0,0 +1,38 @@
+/*!
+    Self-signed certificate for serving the API over HTTPS without setting up a CA, clients
+    having to trust it once.
+*/
+
+use crate::auth::write_private_file;
+use anyhow::Result;
+use rcgen::{Certificate, CertificateParams, SanType};
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+
+/// Writes a certificate for `localhost` and its key at the given paths, unless both exist.
+pub fn ensure_local_certificate<T: AsRef<Path>, U: AsRef<Path>>(
+    certificate: T,
+    key: U,
+) -> Result<()> {
+    let (certificate, key) = (certificate.as_ref(), key.as_ref());
+
+    if certificate.exists() && key.exists() {
+        return Ok(());
+    }
+
+    let mut params = CertificateParams::new(vec![String::from("localhost")]);
+    params
+        .subject_alt_names
+        .push(SanType::IpAddress(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+    let generated = Certificate::from_params(params)?;
+
+    if let Some(folder) = certificate.parent() {
+        fs::create_dir_all(folder)?;
+    }
+
+    fs::write(certificate, generated.serialize_pem()?)?;
+    write_private_file(key, generated.serialize_private_key_pem().as_bytes())?;
+
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use axum::body::Body;
+use axum::http::header::AUTHORIZATION;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use michel_core::grants::Capability;
+use michel_core::memory::MemoryPersistence;
+use michel_core::signature::SignatureConfig;
+use michel_core::{MichelConfig, MichelInstance};
+use michel_server::auth::Tokens;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempdir::TempDir;
+use tower::ServiceExt;
+
+const ADMIN_TOKEN: &str = "admin-secret";
+const QUERY_TOKEN: &str = "query-secret";
+
+/// A router over an instance without plugins, guarded by an admin and a query token if `guarded`.
+async fn router(guarded: bool) -> (Router, TempDir) {
+    let folder = TempDir::new("michel-server").unwrap();
+    let plugins_path = folder.path().join("plugins");
+    fs::create_dir(&plugins_path).unwrap();
+
+    let michel = MichelInstance::new(
+        MemoryPersistence::new(),
+        MichelConfig {
+            name: String::from("Michel"),
+            plugins_path,
+            grants_path: None,
+            kv_path: None,
+            signatures: SignatureConfig::default(),
+            registry: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let tokens = match guarded {
+        true => {
+            let path = folder.path().join("tokens.toml");
+            fs::write(
+                &path,
+                format!(
+                    "[[tokens]]\nname = \"admin\"\ntoken = \"{}\"\nscope = \"admin\"\n\n\
+                     [[tokens]]\nname = \"query\"\ntoken = \"{}\"\nscope = \"query\"\n",
+                    ADMIN_TOKEN, QUERY_TOKEN
+                ),
+            )
+            .unwrap();
+
+            Some(Arc::new(Tokens::load_or_generate(&path).unwrap()))
+        }
+        false => None,
+    };
+
+    (michel_server::router(Arc::new(michel), tokens), folder)
+}
+
+async fn status(router: &Router, uri: &str, authorization: Option<&str>) -> StatusCode {
+    let mut request = Request::get(uri);
+    if let Some(authorization) = authorization {
+        request = request.header(AUTHORIZATION, authorization);
+    }
+
+    router
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+fn bearer(token: &str) -> String {
+    format!("Bearer {}", token)
+}
+
+#[tokio::test]
+async fn requests_without_a_known_token_are_unauthorized() {
+    let (router, _folder) = router(true).await;
+
+    for uri in ["/plugins", "/query?input=rust", "/indexes", "/index-stats"] {
+        assert_eq!(status(&router, uri, None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status(&router, uri, Some(&bearer("unknown"))).await,
+            StatusCode::UNAUTHORIZED
+        );
+        // Only bearer tokens are read from the header
+        assert_eq!(
+            status(&router, uri, Some(&format!("Token {}", ADMIN_TOKEN))).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
+
+#[tokio::test]
+async fn query_tokens_only_reach_query_routes() {
+    let (router, _folder) = router(true).await;
+    let query = bearer(QUERY_TOKEN);
+
+    assert_eq!(
+        status(&router, "/plugins", Some(&query)).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(&router, "/query?input=rust", Some(&query)).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(&router, "/indexes", Some(&query)).await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        status(&router, "/capabilities/pending", Some(&query)).await,
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[tokio::test]
+async fn admin_tokens_reach_every_route() {
+    let (router, _folder) = router(true).await;
+    let admin = bearer(ADMIN_TOKEN);
+
+    for uri in [
+        "/plugins",
+        "/query?input=rust",
+        "/indexes",
+        "/index-stats",
+        "/capabilities/pending",
+    ] {
+        assert_eq!(status(&router, uri, Some(&admin)).await, StatusCode::OK);
+    }
+}
+
+#[tokio::test]
+async fn tokens_are_read_from_the_query_string() {
+    let (router, _folder) = router(true).await;
+
+    assert_eq!(
+        status(&router, &format!("/plugins?token={}", QUERY_TOKEN), None).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(
+            &router,
+            &format!("/query?input=rust&token={}", QUERY_TOKEN),
+            None
+        )
+        .await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(&router, &format!("/indexes?token={}", QUERY_TOKEN), None).await,
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[tokio::test]
+async fn routes_are_open_without_tokens() {
+    let (router, _folder) = router(false).await;
+
+    for uri in ["/plugins", "/indexes", "/index-stats"] {
+        assert_eq!(status(&router, uri, None).await, StatusCode::OK);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn generated_tokens_are_only_readable_by_the_user() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let folder = TempDir::new("michel-tokens").unwrap();
+    let path = folder.path().join("config").join("tokens.toml");
+
+    Tokens::load_or_generate(&path).unwrap();
+    let generated = fs::read_to_string(&path).unwrap();
+
+    assert_eq!(generated.matches("[[tokens]]").count(), 2);
+    assert_eq!(
+        fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+        0o600
+    );
+
+    // Loaded as is afterwards
+    Tokens::load_or_generate(&path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), generated);
+}
+
+#[tokio::test]
+async fn failing_plugins_are_left_out_of_the_answers() {
+    let folder = TempDir::new("michel-server").unwrap();
+    let plugins_path = folder.path().join("plugins");
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("../michel_core/tests/fixtures");
+    fs::create_dir(&plugins_path).unwrap();
+    fs::copy(
+        fixtures.join("baseline-plugin.wat"),
+        plugins_path.join("baseline.wasm"),
+    )
+    .unwrap();
+    fs::copy(
+        fixtures.join("failing-plugin.wat"),
+        plugins_path.join("failing.wasm"),
+    )
+    .unwrap();
+
+    let michel = MichelInstance::new(
+        MemoryPersistence::new(),
+        MichelConfig {
+            name: String::from("Michel"),
+            plugins_path,
+            grants_path: None,
+            kv_path: None,
+            signatures: SignatureConfig::default(),
+            registry: None,
+        },
+    )
+    .await
+    .unwrap();
+    michel
+        .grants()
+        .grant(
+            "baseline",
+            Capability::Index {
+                name: String::from("baseline"),
+            },
+        )
+        .unwrap();
+    michel
+        .plugin(String::from("baseline"))
+        .unwrap()
+        .index()
+        .await
+        .unwrap();
+    let router = michel_server::router(Arc::new(michel), None);
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::get("/query?input=baseline")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let results: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(results.as_array().unwrap().len(), 1);
+    assert_eq!(results[0]["plugin"], "baseline");
+    assert_eq!(results[0]["entries"].as_array().unwrap().len(), 1);
+
+    assert_eq!(
+        status(&router, "/autocomplete?input=base", None).await,
+        StatusCode::OK
+    );
+}
@@ -0,0 +1,36 @@
+use michel_server::tls::ensure_local_certificate;
+use std::fs;
+use tempdir::TempDir;
+
+#[test]
+fn certificate_is_generated_once() {
+    let folder = TempDir::new("michel-tls").unwrap();
+    let (certificate, key) = (
+        folder.path().join("tls").join("cert.pem"),
+        folder.path().join("tls").join("key.pem"),
+    );
+
+    ensure_local_certificate(&certificate, &key).unwrap();
+    let generated = fs::read_to_string(&certificate).unwrap();
+    assert!(generated.starts_with("-----BEGIN CERTIFICATE-----"));
+    assert!(fs::read_to_string(&key).unwrap().contains("PRIVATE KEY"));
+
+    ensure_local_certificate(&certificate, &key).unwrap();
+    assert_eq!(fs::read_to_string(&certificate).unwrap(), generated);
+}
+
+#[cfg(unix)]
+#[test]
+fn key_is_only_readable_by_the_user() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let folder = TempDir::new("michel-tls").unwrap();
+    let key = folder.path().join("key.pem");
+
+    ensure_local_certificate(folder.path().join("cert.pem"), &key).unwrap();
+
+    assert_eq!(
+        fs::metadata(&key).unwrap().permissions().mode() & 0o777,
+        0o600
+    );
+}
@@ -0,0 +1,106 @@
+use crate::models::{ActionRequest, DisplayedPlugin, Entry, ErrorResponse, IndexStats};
+use gloo_net::http::{Request, Response};
+use serde::de::DeserializeOwned;
+use web_sys::Storage;
+
+/// The API is expected under the origin of the page, behind a proxy.
+const API_PATH: &str = "/api";
+/// Local storage key of the token sent to the API.
+const TOKEN_KEY: &str = "michel-token";
+
+fn storage() -> Option<Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+pub fn token() -> Option<String> {
+    storage()?.get_item(TOKEN_KEY).ok()?
+}
+
+pub fn set_token(token: &str) {
+    if let Some(storage) = storage() {
+        let _ = storage.set_item(TOKEN_KEY, token);
+    }
+}
+
+fn authorized(request: Request) -> Request {
+    match token() {
+        Some(token) => request.header("Authorization", &format!("Bearer {}", token)),
+        None => request,
+    }
+}
+
+pub fn stream_url() -> String {
+    let location = web_sys::window().expect("a window").location();
+    let scheme = match location.protocol().as_deref() {
+        Ok("https:") => "wss",
+        _ => "ws",
+    };
+
+    // Browsers don't let WebSockets set headers
+    format!(
+        "{}://{}{}/stream?token={}",
+        scheme,
+        location.host().unwrap_or_default(),
+        API_PATH,
+        token().unwrap_or_default()
+    )
+}
+
+async fn read<T: DeserializeOwned>(response: Response) -> Result<T, String> {
+    if !response.ok() {
+        return Err(match response.json::<ErrorResponse>().await {
+            Ok(error) => error.error,
+            Err(_) => response.status_text(),
+        });
+    }
+
+    response.json().await.map_err(|error| error.to_string())
+}
+
+pub async fn plugins() -> Result<Vec<DisplayedPlugin>, String> {
+    let response = authorized(Request::get(&format!("{}/plugins", API_PATH)))
+        .send()
+        .await
+        .map_err(|error| error.to_string())?;
+
+    read(response).await
+}
+
+pub async fn index_plugin(identifier: &str) -> Result<Vec<IndexStats>, String> {
+    let response = authorized(Request::post(&format!(
+        "{}/plugins/{}/index",
+        API_PATH, identifier
+    )))
+    .send()
+    .await
+    .map_err(|error| error.to_string())?;
+
+    read(response).await
+}
+
+pub async fn indexes() -> Result<Vec<IndexStats>, String> {
+    let response = authorized(Request::get(&format!("{}/index-stats", API_PATH)))
+        .send()
+        .await
+        .map_err(|error| error.to_string())?;
+
+    read(response).await
+}
+
+pub async fn execute_action(plugin: &str, entry: Entry, action: String) -> Result<(), String> {
+    let response = authorized(Request::post(&format!(
+        "{}/plugins/{}/actions",
+        API_PATH, plugin
+    )))
+    .json(&ActionRequest { entry, action })
+    .map_err(|error| error.to_string())?
+    .send()
+    .await
+    .map_err(|error| error.to_string())?;
+
+    if response.ok() {
+        Ok(())
+    } else {
+        read::<()>(response).await
+    }
+}
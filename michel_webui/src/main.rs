@@ -1,3 +1,70 @@
+mod api;
+mod models;
+mod plugins;
+mod search;
+
+use crate::plugins::Plugins;
+use crate::search::Search;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+#[derive(Routable, Clone, PartialEq)]
+enum Route {
+    #[at("/")]
+    Search,
+    #[at("/plugins")]
+    Plugins,
+    #[not_found]
+    #[at("/404")]
+    NotFound,
+}
+
+fn switch(route: Route) -> Html {
+    match route {
+        Route::Search => html! { <Search /> },
+        Route::Plugins => html! { <Plugins /> },
+        Route::NotFound => html! { <Redirect<Route> to={Route::Search} /> },
+    }
+}
+
+/// Token given to the API, the search stream reconnecting with it on reload.
+#[function_component(Token)]
+fn token() -> Html {
+    let on_change = Callback::from(|event: Event| {
+        let input: HtmlInputElement = event.target_unchecked_into();
+        api::set_token(input.value().trim());
+
+        if let Some(window) = web_sys::window() {
+            let _ = window.location().reload();
+        }
+    });
+
+    html! {
+        <input
+            class="token"
+            type="password"
+            placeholder="Token"
+            value={api::token().unwrap_or_default()}
+            onchange={on_change}
+        />
+    }
+}
+
+#[function_component(App)]
+fn app() -> Html {
+    html! {
+        <BrowserRouter>
+            <header>
+                <Link<Route> to={Route::Search}>{ "MICHEL" }</Link<Route>>
+                <Link<Route> to={Route::Plugins}>{ "Plugins" }</Link<Route>>
+                <Token />
+            </header>
+            <Switch<Route> render={switch} />
+        </BrowserRouter>
+    }
+}
+
 fn main() {
-    println!("Hello, world!");
+    yew::Renderer::<App>::new().render();
 }
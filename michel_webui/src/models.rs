@@ -0,0 +1,75 @@
+/*!
+    Mirrors of the bodies of the michel_server API, which can't be shared as they come with
+    the whole host.
+*/
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Action {
+    pub identifier: String,
+    pub label: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub title: String,
+    pub description: String,
+    #[serde(default)]
+    pub preview: Option<String>,
+    #[serde(default)]
+    pub actions: Vec<Action>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct DisplayedPlugin {
+    pub identifier: String,
+    pub name: String,
+    pub description: String,
+    pub version: String,
+    pub can_index: bool,
+    pub enabled: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct IndexStats {
+    pub name: String,
+    pub document_count: u64,
+    pub field_distribution: BTreeMap<String, u64>,
+    pub size_on_disk: u64,
+    pub last_update: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ActionRequest {
+    pub entry: Entry,
+    pub action: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamRequest {
+    Query { sequence: u64, input: String },
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamResponse {
+    Entries {
+        sequence: u64,
+        plugin: String,
+        entries: Vec<Entry>,
+    },
+    Done {
+        sequence: u64,
+    },
+    Error {
+        error: String,
+    },
+}
@@ -0,0 +1,144 @@
+use crate::api;
+use crate::models::{DisplayedPlugin, IndexStats};
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+struct PluginProps {
+    plugin: DisplayedPlugin,
+    on_indexed: Callback<()>,
+}
+
+#[function_component(Plugin)]
+fn plugin(props: &PluginProps) -> Html {
+    let indexing = use_state(|| false);
+    let error = use_state(|| None::<String>);
+
+    let on_index = {
+        let identifier = props.plugin.identifier.clone();
+        let on_indexed = props.on_indexed.clone();
+        let indexing = indexing.clone();
+        let error = error.clone();
+
+        Callback::from(move |_| {
+            let identifier = identifier.clone();
+            let on_indexed = on_indexed.clone();
+            let indexing = indexing.clone();
+            let error = error.clone();
+            indexing.set(true);
+
+            spawn_local(async move {
+                error.set(api::index_plugin(&identifier).await.err());
+                indexing.set(false);
+                on_indexed.emit(());
+            });
+        })
+    };
+
+    html! {
+        <div class="plugin">
+            <div class="name">
+                <h2>{ &props.plugin.name }</h2>
+                <p>{ &props.plugin.description }</p>
+                <p>{ format!("version {}", props.plugin.version) }</p>
+                if let Some(error) = error.as_ref() {
+                    <p class="error">{ error }</p>
+                }
+            </div>
+            if props.plugin.can_index {
+                if *indexing {
+                    <span>{ "Indexing..." }</span>
+                } else {
+                    <button onclick={on_index}>{ "Index" }</button>
+                }
+            }
+        </div>
+    }
+}
+
+#[function_component(Plugins)]
+pub fn plugins() -> Html {
+    let plugins = use_state(Vec::<DisplayedPlugin>::new);
+    let indexes = use_state(Vec::<IndexStats>::new);
+    let error = use_state(|| None::<String>);
+
+    let refresh = {
+        let plugins = plugins.clone();
+        let indexes = indexes.clone();
+        let error = error.clone();
+
+        Callback::from(move |_: ()| {
+            let plugins = plugins.clone();
+            let indexes = indexes.clone();
+            let error = error.clone();
+
+            spawn_local(async move {
+                match (api::plugins().await, api::indexes().await) {
+                    (Ok(fetched_plugins), Ok(fetched_indexes)) => {
+                        plugins.set(fetched_plugins);
+                        indexes.set(fetched_indexes);
+                        error.set(None);
+                    }
+                    (Err(fetch_error), _) | (_, Err(fetch_error)) => error.set(Some(fetch_error)),
+                }
+            });
+        })
+    };
+
+    {
+        let refresh = refresh.clone();
+        use_effect_with_deps(
+            move |_| {
+                refresh.emit(());
+                || ()
+            },
+            (),
+        );
+    }
+
+    html! {
+        <div class="plugins">
+            <h1>{ "Plugins" }</h1>
+            if let Some(error) = error.as_ref() {
+                <p class="error">{ error }</p>
+            }
+            { for plugins.iter().map(|plugin| html! {
+                <Plugin plugin={plugin.clone()} on_indexed={refresh.clone()} />
+            }) }
+            <h1>{ "Indexes" }</h1>
+            <table>
+                <tr>
+                    <th>{ "Index" }</th>
+                    <th>{ "Documents" }</th>
+                    <th>{ "Size" }</th>
+                    <th>{ "Last update" }</th>
+                </tr>
+                { for indexes.iter().map(|index| html! {
+                    <tr>
+                        <td>{ &index.name }</td>
+                        <td>{ index.document_count }</td>
+                        <td>{ format_size(index.size_on_disk) }</td>
+                        <td>{ index.last_update.clone().unwrap_or_default() }</td>
+                    </tr>
+                }) }
+            </table>
+        </div>
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -0,0 +1,226 @@
+use crate::api;
+use crate::models::{Entry, StreamRequest, StreamResponse};
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use gloo_net::websocket::futures::WebSocket;
+use gloo_net::websocket::Message;
+use std::rc::Rc;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Entries of the current query, filled plugin by plugin as they answer.
+#[derive(Default, PartialEq)]
+struct Results {
+    sequence: u64,
+    batches: Vec<(String, Vec<Entry>)>,
+    done: bool,
+    error: Option<String>,
+}
+
+enum ResultsAction {
+    Query(u64),
+    Response(StreamResponse),
+}
+
+impl Reducible for Results {
+    type Action = ResultsAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        match action {
+            ResultsAction::Query(sequence) => Rc::new(Results {
+                sequence,
+                ..Results::default()
+            }),
+            // Responses to superseded queries may still come in
+            ResultsAction::Response(StreamResponse::Entries {
+                sequence,
+                plugin,
+                entries,
+            }) if sequence == self.sequence => {
+                let mut batches = self.batches.clone();
+                batches.push((plugin, entries));
+
+                Rc::new(Results {
+                    sequence,
+                    batches,
+                    done: self.done,
+                    error: None,
+                })
+            }
+            ResultsAction::Response(StreamResponse::Done { sequence })
+                if sequence == self.sequence =>
+            {
+                Rc::new(Results {
+                    sequence,
+                    batches: self.batches.clone(),
+                    done: true,
+                    error: None,
+                })
+            }
+            ResultsAction::Response(StreamResponse::Error { error }) => Rc::new(Results {
+                sequence: self.sequence,
+                batches: self.batches.clone(),
+                done: self.done,
+                error: Some(error),
+            }),
+            ResultsAction::Response(_) => self,
+        }
+    }
+}
+
+#[function_component(Search)]
+pub fn search() -> Html {
+    let results = use_reducer(Results::default);
+    let queries = use_state(|| None::<mpsc::UnboundedSender<StreamRequest>>);
+    let sequence = use_mut_ref(|| 0u64);
+    let selected = use_state(|| None::<(String, Entry)>);
+    let action_error = use_state(|| None::<String>);
+
+    {
+        let results = results.dispatcher();
+        let queries = queries.clone();
+
+        use_effect_with_deps(
+            move |_| {
+                match WebSocket::open(&api::stream_url()) {
+                    Ok(socket) => {
+                        let (mut sink, mut responses) = socket.split();
+                        let (sender, mut requests) = mpsc::unbounded::<StreamRequest>();
+                        queries.set(Some(sender));
+
+                        spawn_local(async move {
+                            while let Some(request) = requests.next().await {
+                                let message = match serde_json::to_string(&request) {
+                                    Ok(message) => message,
+                                    Err(_) => continue,
+                                };
+
+                                if sink.send(Message::Text(message)).await.is_err() {
+                                    break;
+                                }
+                            }
+                        });
+
+                        spawn_local(async move {
+                            while let Some(Ok(Message::Text(text))) = responses.next().await {
+                                if let Ok(response) = serde_json::from_str(&text) {
+                                    results.dispatch(ResultsAction::Response(response));
+                                }
+                            }
+                        });
+                    }
+                    Err(error) => {
+                        results.dispatch(ResultsAction::Response(StreamResponse::Error {
+                            error: error.to_string(),
+                        }))
+                    }
+                }
+
+                || ()
+            },
+            (),
+        );
+    }
+
+    let on_input = {
+        let results = results.dispatcher();
+        let queries = queries.clone();
+        let selected = selected.clone();
+
+        Callback::from(move |event: InputEvent| {
+            let input: HtmlInputElement = event.target_unchecked_into();
+            let next = {
+                let mut sequence = sequence.borrow_mut();
+                *sequence += 1;
+                *sequence
+            };
+
+            results.dispatch(ResultsAction::Query(next));
+            selected.set(None);
+
+            if let Some(queries) = queries.as_ref() {
+                let _ = queries.unbounded_send(StreamRequest::Query {
+                    sequence: next,
+                    input: input.value(),
+                });
+            }
+        })
+    };
+
+    let entries = results.batches.iter().flat_map(|(plugin, entries)| {
+        entries.iter().map(move |entry| {
+            let is_selected = selected
+                .as_ref()
+                .map_or(false, |(_, current)| current == entry);
+            let on_click = {
+                let selected = selected.clone();
+                let plugin = plugin.clone();
+                let entry = entry.clone();
+                Callback::from(move |_| selected.set(Some((plugin.clone(), entry.clone()))))
+            };
+
+            html! {
+                <div class={classes!("entry", is_selected.then_some("selected"))} onclick={on_click}>
+                    <h2>{ &entry.title }</h2>
+                    <p>{ &entry.description }</p>
+                </div>
+            }
+        })
+    });
+
+    let preview = selected.as_ref().map(|(plugin, entry)| {
+        let actions = entry.actions.iter().map(|action| {
+            let on_click = {
+                let plugin = plugin.clone();
+                let entry = entry.clone();
+                let action = action.identifier.clone();
+                let action_error = action_error.clone();
+
+                Callback::from(move |_| {
+                    let plugin = plugin.clone();
+                    let entry = entry.clone();
+                    let action = action.clone();
+                    let action_error = action_error.clone();
+
+                    spawn_local(async move {
+                        action_error.set(api::execute_action(&plugin, entry, action).await.err());
+                    });
+                })
+            };
+
+            html! { <button onclick={on_click}>{ &action.label }</button> }
+        });
+
+        html! {
+            <div class="preview">
+                <h2>{ &entry.title }</h2>
+                if let Some(preview) = &entry.preview {
+                    <pre>{ preview }</pre>
+                }
+                <div class="actions">{ for actions }</div>
+                if let Some(error) = action_error.as_ref() {
+                    <p class="error">{ error }</p>
+                }
+            </div>
+        }
+    });
+
+    html! {
+        <div class="search">
+            <input class="search-bar" name="search-content" oninput={on_input} />
+            if let Some(error) = &results.error {
+                <p class="error">{ error }</p>
+            }
+            <div class="results">
+                <div class="entries">
+                    { for entries }
+                    if !results.done && results.sequence > 0 {
+                        <span>{ "Searching..." }</span>
+                    }
+                </div>
+                { for preview }
+            </div>
+        </div>
+    }
+}